@@ -16,26 +16,6 @@ if ! command -v cargo &> /dev/null; then
     exit 1
 fi
 
-# Check if init.sh exists
-if [ ! -f "../init.sh" ] && [ ! -f "init.sh" ]; then
-    echo -e "${RED}Error: init.sh not found.${NC}"
-    echo -e "Please make sure init.sh is in the parent directory or current directory."
-    exit 1
-fi
-
-# Copy init.sh if needed
-if [ ! -f "init.sh" ] && [ -f "../init.sh" ]; then
-    echo -e "${CYAN}Copying init.sh from parent directory...${NC}"
-    cp ../init.sh .
-fi
-
-# Copy init_wrapper.sh if it doesn't exist
-if [ ! -f "init_wrapper.sh" ]; then
-    echo -e "${RED}Error: init_wrapper.sh not found.${NC}"
-    echo -e "The wrapper script is missing. Please make sure it's in the project directory."
-    exit 1
-fi
-
 # Build the project
 echo -e "${CYAN}Building MacDevKit CLI...${NC}"
 cargo build --release
@@ -0,0 +1,181 @@
+use std::fmt;
+
+/// One line of a Brewfile that the CLI understands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Entry {
+    Tap(String),
+    Brew(String),
+    Cask(String),
+    /// Mac App Store app, installed with `mas`.
+    Mas { name: String, id: u64 },
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Entry::Tap(name) => write!(f, "tap \"{}\"", name),
+            Entry::Brew(name) => write!(f, "brew \"{}\"", name),
+            Entry::Cask(name) => write!(f, "cask \"{}\"", name),
+            Entry::Mas { name, id } => write!(f, "mas \"{}\", id: {}", name, id),
+        }
+    }
+}
+
+/// Parses the `tap`, `brew`, `cask` and `mas` lines of a Brewfile.
+///
+/// Options such as `args:` or `restart_service:` are ignored. Other
+/// directives (`vscode`, `whalebrew`, Ruby code) are returned as warnings.
+pub fn parse(content: &str) -> Result<(Vec<Entry>, Vec<String>), String> {
+    let mut entries = Vec::new();
+    let mut warnings = Vec::new();
+
+    for (index, raw_line) in content.lines().enumerate() {
+        let line = strip_comment(raw_line).trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let line_number = index + 1;
+        let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let name = || {
+            first_string(rest)
+                .ok_or_else(|| format!("Line {}: expected a quoted name: {}", line_number, line))
+        };
+
+        match keyword {
+            "tap" => entries.push(Entry::Tap(name()?)),
+            "brew" => entries.push(Entry::Brew(name()?)),
+            "cask" => entries.push(Entry::Cask(name()?)),
+            "mas" => {
+                let id = mas_id(rest)
+                    .ok_or_else(|| format!("Line {}: mas entry needs an `id:`: {}", line_number, line))?;
+                entries.push(Entry::Mas { name: name()?, id });
+            }
+            _ => warnings.push(format!("Line {}: unsupported entry skipped: {}", line_number, line)),
+        }
+    }
+
+    Ok((entries, warnings))
+}
+
+/// Renders entries as a Brewfile, taps first as `brew bundle` expects.
+pub fn render(entries: &[Entry]) -> String {
+    let mut sorted: Vec<&Entry> = entries.iter().collect();
+    sorted.sort_by_key(|entry| match entry {
+        Entry::Tap(_) => 0,
+        Entry::Brew(_) => 1,
+        Entry::Cask(_) => 2,
+        Entry::Mas { .. } => 3,
+    });
+
+    sorted.iter().map(|entry| format!("{}\n", entry)).collect()
+}
+
+/// Removes a trailing `# comment`, ignoring `#` inside quotes.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    for (index, c) in line.char_indices() {
+        match (c, quote) {
+            ('"' | '\'', None) => quote = Some(c),
+            (c, Some(open)) if c == open => quote = None,
+            ('#', None) => return &line[..index],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// First single- or double-quoted string in `text`.
+fn first_string(text: &str) -> Option<String> {
+    let start = text.find(['"', '\''])?;
+    let quote = text[start..].chars().next()?;
+    let rest = &text[start + 1..];
+    let end = rest.find(quote)?;
+    Some(rest[..end].to_string())
+}
+
+/// The number after `id:` in a `mas` line.
+fn mas_id(text: &str) -> Option<u64> {
+    let after = &text[text.find("id:")? + 3..];
+    let digits: String = after
+        .trim_start()
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect();
+    digits.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(content: &str) -> Vec<Entry> {
+        parse(content).unwrap().0
+    }
+
+    #[test]
+    fn skips_comments_and_blank_lines() {
+        let content = "# Taps\n\ntap \"homebrew/cask-fonts\" # fonts\n  brew \"jq\"  # JSON\n";
+        assert_eq!(
+            entries(content),
+            [Entry::Tap("homebrew/cask-fonts".to_string()), Entry::Brew("jq".to_string())]
+        );
+    }
+
+    #[test]
+    fn reads_both_quote_styles_and_ignores_options() {
+        let content = "brew 'wget'\nbrew \"mysql\", restart_service: true\ncask \"font#mono\", args: { appdir: \"~/Apps\" }\n";
+        assert_eq!(
+            entries(content),
+            [
+                Entry::Brew("wget".to_string()),
+                Entry::Brew("mysql".to_string()),
+                Entry::Cask("font#mono".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_mas_ids() {
+        assert_eq!(
+            entries("mas \"Xcode\", id: 497799835\nmas \"1Password 7\",id:1333542190\n"),
+            [
+                Entry::Mas { name: "Xcode".to_string(), id: 497799835 },
+                Entry::Mas { name: "1Password 7".to_string(), id: 1333542190 },
+            ]
+        );
+        assert!(parse("mas \"Xcode\"\n").unwrap_err().contains("Line 1"));
+    }
+
+    #[test]
+    fn warns_about_unsupported_lines() {
+        let (entries, warnings) = parse("brew \"jq\"\nvscode \"eamodio.gitlens\"\nwhalebrew \"whalebrew/wget\"\n").unwrap();
+        assert_eq!(entries, [Entry::Brew("jq".to_string())]);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].starts_with("Line 2:"));
+    }
+
+    #[test]
+    fn rejects_unquoted_names() {
+        assert!(parse("brew jq\n").unwrap_err().contains("expected a quoted name"));
+    }
+
+    #[test]
+    fn render_puts_taps_first_and_parses_back() {
+        let original = vec![
+            Entry::Cask("firefox".to_string()),
+            Entry::Mas { name: "Xcode".to_string(), id: 497799835 },
+            Entry::Brew("jq".to_string()),
+            Entry::Tap("homebrew/cask-fonts".to_string()),
+        ];
+        let rendered = render(&original);
+        assert_eq!(
+            rendered,
+            "tap \"homebrew/cask-fonts\"\nbrew \"jq\"\ncask \"firefox\"\nmas \"Xcode\", id: 497799835\n"
+        );
+        let (parsed, warnings) = parse(&rendered).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(parsed.len(), original.len());
+        assert!(original.iter().all(|entry| parsed.contains(entry)));
+    }
+}
@@ -0,0 +1,213 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::node::NodeManager;
+
+/// Kind of package a step installed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PackageKind {
+    Formula,
+    Cask,
+    Npm,
+    VscodeExtension,
+    /// Mac App Store app; the name is its numeric id.
+    Mas,
+}
+
+/// Value a `defaults` key had before a step overwrote it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PreviousDefault {
+    /// `defaults write` type flag, e.g. `-bool`.
+    pub value_type: String,
+    pub value: String,
+}
+
+/// A change made by a step that `undo` knows how to revert.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Change {
+    /// `previous` is `None` if the key did not exist before.
+    DefaultsWrite {
+        domain: String,
+        key: String,
+        previous: Option<PreviousDefault>,
+    },
+    FileAppend { path: String, content: String },
+    /// `content` was removed from a file; `undo` appends it again.
+    FileRemove { path: String, content: String },
+    /// A line of a file was replaced with `line`; `previous` is the line
+    /// before.
+    LineReplace {
+        path: String,
+        previous: String,
+        line: String,
+    },
+    /// `node_manager` is the manager whose Node.js an npm package was
+    /// installed with, so `undo` removes it from the same Node.js.
+    PackageInstall {
+        kind: PackageKind,
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        node_manager: Option<NodeManager>,
+    },
+    /// A [managed block](crate::managed_block) was written; `previous` is
+    /// its content before, `None` if the file did not have the block.
+    ManagedBlock {
+        path: String,
+        name: String,
+        previous: Option<String>,
+    },
+    /// `go env -w`; `previous` is `None` if the variable was not set.
+    GoEnv { key: String, previous: Option<String> },
+    /// `git config --global`; `previous` is `None` if the key was not set.
+    GitConfig { key: String, previous: Option<String> },
+}
+
+impl Change {
+    /// True if both changes overwrite the same setting.
+    fn same_setting(&self, other: &Change) -> bool {
+        match (self, other) {
+            (Change::DefaultsWrite { domain, key, .. }, Change::DefaultsWrite { domain: d, key: k, .. }) => {
+                domain == d && key == k
+            }
+            (Change::ManagedBlock { path, name, .. }, Change::ManagedBlock { path: p, name: n, .. }) => {
+                path == p && name == n
+            }
+            (Change::GoEnv { key, .. }, Change::GoEnv { key: k, .. }) => key == k,
+            (Change::GitConfig { key, .. }, Change::GitConfig { key: k, .. }) => key == k,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::DefaultsWrite { domain, key, .. } => write!(f, "defaults {} {}", domain, key),
+            Change::FileAppend { path, .. } => write!(f, "lines added to {}", path),
+            Change::FileRemove { path, .. } => write!(f, "lines removed from {}", path),
+            Change::LineReplace { path, previous, .. } => write!(f, "{} in {}", previous, path),
+            Change::PackageInstall { name, .. } => write!(f, "package {}", name),
+            Change::ManagedBlock { path, name, .. } => write!(f, "the {} block in {}", name, path),
+            Change::GoEnv { key, .. } => write!(f, "go env {}", key),
+            Change::GitConfig { key, .. } => write!(f, "git config {}", key),
+        }
+    }
+}
+
+/// Changes made by each step, persisted across runs so they can be undone.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ChangeLog {
+    pub steps: BTreeMap<String, Vec<Change>>,
+
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl ChangeLog {
+    /// Loads the change log at `path`, or starts an empty one.
+    pub fn open(path: PathBuf) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(ChangeLog {
+                steps: BTreeMap::new(),
+                path,
+            });
+        }
+
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let mut log: ChangeLog = serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid change log {}: {}", path.display(), e))?;
+
+        log.path = path;
+        Ok(log)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(&self.path, content)
+            .map_err(|e| format!("Failed to write {}: {}", self.path.display(), e))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Adds a change to `step`'s record.
+    ///
+    /// A setting that was already recorded, such as a `defaults` key, keeps
+    /// its first previous value, so rerunning a step does not lose the
+    /// original setting.
+    pub fn record(&mut self, step: &str, change: Change) {
+        let changes = self.steps.entry(step.to_string()).or_default();
+
+        if changes.iter().any(|existing| existing.same_setting(&change)) {
+            return;
+        }
+
+        changes.push(change);
+    }
+
+    pub fn changes(&self, step: &str) -> &[Change] {
+        self.steps.get(step).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Drops one change from `step`'s record once it has been reverted.
+    pub fn remove(&mut self, step: &str, change: &Change) {
+        if let Some(changes) = self.steps.get_mut(step) {
+            if let Some(index) = changes.iter().position(|existing| existing == change) {
+                changes.remove(index);
+            }
+            if changes.is_empty() {
+                self.steps.remove(step);
+            }
+        }
+    }
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    crate::journal::state_dir().map(|dir| dir.join("changes.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git_config(key: &str, previous: Option<&str>) -> Change {
+        Change::GitConfig {
+            key: key.to_string(),
+            previous: previous.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn record_keeps_the_first_previous_value() {
+        let mut log = ChangeLog::default();
+        log.record("git", git_config("pull.rebase", Some("true")));
+        log.record("git", git_config("pull.rebase", Some("false")));
+        log.record("git", git_config("fetch.prune", None));
+
+        assert_eq!(log.changes("git"), [git_config("pull.rebase", Some("true")), git_config("fetch.prune", None)]);
+    }
+
+    #[test]
+    fn remove_drops_one_change_and_then_the_step() {
+        let mut log = ChangeLog::default();
+        log.record("git", git_config("pull.rebase", None));
+        log.record("git", git_config("fetch.prune", None));
+
+        log.remove("git", &git_config("pull.rebase", None));
+        assert_eq!(log.changes("git"), [git_config("fetch.prune", None)]);
+        log.remove("git", &git_config("fetch.prune", None));
+        assert!(!log.steps.contains_key("git"));
+    }
+}
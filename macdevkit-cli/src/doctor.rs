@@ -0,0 +1,122 @@
+use colored::*;
+use serde::Serialize;
+use std::fmt;
+
+use crate::script_handler::ScriptHandler;
+use crate::steps::Step;
+
+/// Result of a single health check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Health {
+    Pass,
+    /// Usable, but something is off, e.g. Docker is installed but not running.
+    Warn,
+    Fail,
+}
+
+impl fmt::Display for Health {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Health::Pass => write!(f, "pass"),
+            Health::Warn => write!(f, "warn"),
+            Health::Fail => write!(f, "fail"),
+        }
+    }
+}
+
+/// What `doctor` found for one step, with a hint on how to fix it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnosis {
+    pub health: Health,
+    pub summary: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+impl Diagnosis {
+    pub fn pass(summary: impl Into<String>) -> Self {
+        Diagnosis {
+            health: Health::Pass,
+            summary: summary.into(),
+            hint: None,
+        }
+    }
+
+    pub fn warn(summary: impl Into<String>, hint: impl Into<String>) -> Self {
+        Diagnosis {
+            health: Health::Warn,
+            summary: summary.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    pub fn fail(summary: impl Into<String>, hint: impl Into<String>) -> Self {
+        Diagnosis {
+            health: Health::Fail,
+            summary: summary.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Finding {
+    pub step: &'static str,
+    #[serde(flatten)]
+    pub diagnosis: Diagnosis,
+}
+
+/// Checks every step's postconditions without changing anything.
+pub fn examine(handler: &ScriptHandler, steps: &[Box<dyn Step>]) -> Vec<Finding> {
+    steps
+        .iter()
+        .map(|step| Finding {
+            step: step.id(),
+            diagnosis: step.diagnose(handler),
+        })
+        .collect()
+}
+
+/// True if any check failed; warnings do not count.
+pub fn has_failures(findings: &[Finding]) -> bool {
+    findings.iter().any(|finding| finding.diagnosis.health == Health::Fail)
+}
+
+/// Renders the findings as a table followed by the remediation hints.
+pub fn render_table(findings: &[Finding]) -> String {
+    let width = findings
+        .iter()
+        .map(|finding| finding.step.len())
+        .max()
+        .unwrap_or(0)
+        .max("STEP".len());
+
+    let mut table = format!("{:<width$}  {:<6}  {}\n", "STEP", "STATUS", "DETAILS");
+    for finding in findings {
+        // 先补齐宽度再着色，转义序列不计入列宽
+        let status = format!("{:<6}", finding.diagnosis.health.to_string());
+        let status = match finding.diagnosis.health {
+            Health::Pass => status.green(),
+            Health::Warn => status.yellow(),
+            Health::Fail => status.red(),
+        };
+        table.push_str(&format!(
+            "{:<width$}  {}  {}\n",
+            finding.step, status, finding.diagnosis.summary
+        ));
+    }
+
+    let hints: Vec<_> = findings
+        .iter()
+        .filter_map(|finding| finding.diagnosis.hint.as_ref().map(|hint| (finding.step, hint)))
+        .collect();
+    if !hints.is_empty() {
+        table.push_str("\nTo fix:\n");
+        for (step, hint) in hints {
+            table.push_str(&format!("  {}: {}\n", step, hint));
+        }
+    }
+
+    table
+}
@@ -0,0 +1,139 @@
+use std::fmt;
+use std::io;
+
+/// Why a step, or the CLI as a whole, could not finish.
+#[derive(Debug)]
+pub enum SetupError {
+    /// The program is not installed or not on `PATH`.
+    CommandNotFound { program: String },
+    /// The command ran but exited unsuccessfully; `code` is `None` if it was
+    /// killed by a signal.
+    CommandFailed { command: String, code: Option<i32> },
+    /// Reading or writing a file failed.
+    Io { context: String, source: io::Error },
+    /// The manifest or answers file is invalid.
+    Config(String),
+    /// The user cancelled, or a question could not be answered because
+    /// stdin is not a terminal.
+    Aborted(String),
+    UnsupportedPlatform(String),
+    /// Any other failure, described by the step.
+    Other(String),
+}
+
+impl SetupError {
+    pub fn io(context: impl Into<String>, source: io::Error) -> Self {
+        SetupError::Io {
+            context: context.into(),
+            source,
+        }
+    }
+
+    /// Maps a failure to start `program` to a typed error.
+    pub fn spawn(program: &str, source: io::Error) -> Self {
+        if source.kind() == io::ErrorKind::NotFound {
+            SetupError::CommandNotFound {
+                program: program.to_string(),
+            }
+        } else {
+            SetupError::io(format!("Failed to run {}", program), source)
+        }
+    }
+
+    pub fn exit_code(&self) -> ExitCode {
+        match self {
+            SetupError::Config(_) => ExitCode::Config,
+            SetupError::Aborted(_) => ExitCode::Aborted,
+            SetupError::UnsupportedPlatform(_) => ExitCode::UnsupportedPlatform,
+            _ => ExitCode::Failed,
+        }
+    }
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetupError::CommandNotFound { program } => write!(f, "Command not found: {}", program),
+            SetupError::CommandFailed { command, code: Some(code) } => {
+                write!(f, "`{}` failed with exit code {}", command, code)
+            }
+            SetupError::CommandFailed { command, code: None } => {
+                write!(f, "`{}` was terminated by a signal", command)
+            }
+            SetupError::Io { context, source } => write!(f, "{}: {}", context, source),
+            SetupError::Config(message)
+            | SetupError::Aborted(message)
+            | SetupError::UnsupportedPlatform(message)
+            | SetupError::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for SetupError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SetupError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<String> for SetupError {
+    fn from(message: String) -> Self {
+        SetupError::Other(message)
+    }
+}
+
+impl From<&str> for SetupError {
+    fn from(message: &str) -> Self {
+        SetupError::Other(message.to_string())
+    }
+}
+
+/// Exit codes of the CLI, for CI and wrapper scripts.
+///
+/// | Code | Meaning                                                   |
+/// |------|-----------------------------------------------------------|
+/// | 0    | Every step that was selected completed                    |
+/// | 1    | At least one step or check failed                         |
+/// | 2    | Invalid command-line arguments                            |
+/// | 3    | Nothing failed, but at least one step was skipped         |
+/// | 4    | The manifest or answers file is invalid                   |
+/// | 5    | Aborted by the user, or a question could not be answered  |
+/// | 6    | Not running on macOS                                      |
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitCode {
+    Success = 0,
+    Failed = 1,
+    Usage = 2,
+    Skipped = 3,
+    Config = 4,
+    Aborted = 5,
+    UnsupportedPlatform = 6,
+}
+
+impl ExitCode {
+    /// The more serious of two outcomes, e.g. a failure outranks a skip.
+    pub fn max(self, other: ExitCode) -> ExitCode {
+        if other.severity() > self.severity() {
+            other
+        } else {
+            self
+        }
+    }
+
+    fn severity(self) -> u8 {
+        match self {
+            ExitCode::Success => 0,
+            ExitCode::Skipped => 1,
+            ExitCode::Failed => 2,
+            ExitCode::Usage | ExitCode::Config | ExitCode::Aborted | ExitCode::UnsupportedPlatform => 3,
+        }
+    }
+}
+
+impl From<ExitCode> for std::process::ExitCode {
+    fn from(code: ExitCode) -> Self {
+        std::process::ExitCode::from(code as u8)
+    }
+}
@@ -0,0 +1,97 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+use crate::journal::StepStatus;
+use crate::runner::OutputStream;
+
+/// Format of the progress events streamed with `--events`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum EventFormat {
+    /// One JSON object per line.
+    Ndjson,
+}
+
+/// Progress of a run, streamed as it happens.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum Event {
+    StepStarted {
+        step: String,
+    },
+    StepFinished {
+        step: String,
+        status: StepStatus,
+        duration_ms: u64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// The packages of the steps run so far are being installed together.
+    InstallStarted {
+        steps: Vec<String>,
+    },
+    /// The package install has finished; `failed` lists the steps whose
+    /// packages did not all install, which are now failed.
+    InstallFinished {
+        duration_ms: u64,
+        failed: Vec<String>,
+    },
+    /// One line written by a command a step runs.
+    CommandOutput {
+        step: Option<String>,
+        command: String,
+        stream: OutputStream,
+        line: String,
+    },
+}
+
+/// Writes events as NDJSON, one line per event.
+pub struct EventStream {
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl EventStream {
+    pub fn new(writer: Box<dyn Write + Send>) -> Self {
+        EventStream {
+            writer: Mutex::new(writer),
+        }
+    }
+
+    pub fn stdout() -> Self {
+        Self::new(Box::new(io::stdout()))
+    }
+
+    pub fn emit(&self, event: &Event) {
+        let Ok(line) = serde_json::to_string(event) else {
+            return;
+        };
+        let mut writer = self.writer.lock().unwrap();
+        // 事件流写入失败（如管道已关闭）时不影响安装本身
+        let _ = writeln!(writer, "{}", line).and_then(|_| writer.flush());
+    }
+}
+
+/// Outcome of one step, as reported by `--output json`.
+#[derive(Debug, Clone, Serialize)]
+pub struct StepSummary {
+    pub id: String,
+    pub status: StepStatus,
+    pub duration_ms: u64,
+    /// Command lines the step ran, not counting read-only checks.
+    pub commands: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Final report of a run.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RunSummary {
+    pub steps: Vec<StepSummary>,
+}
+
+impl RunSummary {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
@@ -0,0 +1,148 @@
+//! Git identities scoped to directories, and global Git settings.
+//!
+//! Each identity lives in its own include file, which the global config
+//! pulls in with `includeIf "gitdir:<dir>"` for every directory the identity
+//! is used in. Repositories elsewhere use the global `user.name` and
+//! `user.email`.
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+use crate::signing::SigningMethod;
+
+/// `[git]` table of the manifest.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GitSettings {
+    /// Global config values. When left out, the built-in [`default_config`]
+    /// is used but only fills in keys that are not set yet.
+    pub config: Option<BTreeMap<String, String>>,
+    /// Patterns for the global gitignore.
+    pub ignore: Vec<String>,
+    /// Identities by name, e.g. `[git.identities.work]`.
+    pub identities: BTreeMap<String, GitIdentity>,
+    /// Sign commits and tags; see `git signing`.
+    pub signing: Option<SigningMethod>,
+}
+
+/// A name and email used for the repositories under `dirs`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GitIdentity {
+    pub name: String,
+    pub email: String,
+    /// Directories whose repositories use the identity, e.g. `~/Workspace/work/`.
+    pub dirs: Vec<String>,
+    /// Key to sign with instead of the global one: the name of an SSH key
+    /// from `[ssh]`, or a GPG key id.
+    pub signing_key: Option<String>,
+}
+
+/// Global config written when the manifest has no `[git.config]`.
+pub fn default_config() -> BTreeMap<String, String> {
+    [
+        ("init.defaultBranch", "main"),
+        ("core.editor", "code --wait"),
+        ("pull.rebase", "false"),
+        ("fetch.prune", "true"),
+    ]
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), value.to_string()))
+    .collect()
+}
+
+/// Global gitignore patterns when the manifest lists none.
+pub fn default_ignore() -> Vec<String> {
+    [".DS_Store", "._*", ".AppleDouble", ".LSOverride", ".Spotlight-V100", ".Trashes", "*.swp", "*~"]
+        .into_iter()
+        .map(str::to_string)
+        .collect()
+}
+
+impl GitSettings {
+    /// Patterns for the global gitignore: the manifest's, or the defaults.
+    pub fn ignore(&self) -> Vec<String> {
+        if self.ignore.is_empty() {
+            default_ignore()
+        } else {
+            self.ignore.clone()
+        }
+    }
+
+    /// The identities whose directories contain `path`, in the order Git
+    /// includes them; the last one wins.
+    pub fn identities_for(&self, path: &str, home: &str) -> Vec<(&str, &GitIdentity)> {
+        let path = with_slash(path);
+        self.identities
+            .iter()
+            .filter(|(_, identity)| {
+                identity
+                    .dirs
+                    .iter()
+                    .any(|dir| path.starts_with(&with_slash(&expand(dir, home))))
+            })
+            .map(|(id, identity)| (id.as_str(), identity))
+            .collect()
+    }
+}
+
+impl GitIdentity {
+    /// Content of the identity's include file; `signing_key` as Git
+    /// expects it, `None` to sign with the global key.
+    pub fn include_body(&self, signing_key: Option<&str>) -> String {
+        let mut body = format!("[user]\n\tname = {}\n\temail = {}", quote(&self.name), quote(&self.email));
+        if let Some(key) = signing_key {
+            body.push_str(&format!("\n\tsigningkey = {}", quote(key)));
+        }
+        body
+    }
+
+    /// `includeIf` keys that load the identity, one per directory.
+    pub fn include_keys(&self) -> Vec<String> {
+        self.dirs
+            .iter()
+            .map(|dir| format!("includeIf.gitdir:{}.path", with_slash(dir)))
+            .collect()
+    }
+}
+
+/// Include file of identity `id` under `home`.
+pub fn include_path(id: &str, home: &str) -> String {
+    format!("{}/identities/{}.gitconfig", config_dir(home), id)
+}
+
+/// `$XDG_CONFIG_HOME/git`, where Git also looks for the global gitignore.
+pub fn config_dir(home: &str) -> String {
+    match std::env::var("XDG_CONFIG_HOME") {
+        Ok(dir) if !dir.is_empty() => format!("{}/git", dir),
+        _ => format!("{}/.config/git", home),
+    }
+}
+
+/// `dir` ending in `/`, which makes `gitdir:` match everything below it.
+fn with_slash(dir: &str) -> String {
+    if dir.ends_with('/') {
+        dir.to_string()
+    } else {
+        format!("{}/", dir)
+    }
+}
+
+fn expand(path: &str, home: &str) -> String {
+    match path.strip_prefix("~/") {
+        Some(rest) => format!("{}/{}", home, rest),
+        None => path.to_string(),
+    }
+}
+
+/// A config value, quoted if Git would otherwise misread it.
+fn quote(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value.trim() == value
+        && !value.contains(['"', '\\', ';', '#']);
+    if plain {
+        value.to_string()
+    } else {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
@@ -0,0 +1,278 @@
+//! REST APIs of Git hosting services, for uploading SSH keys.
+//!
+//! Requests go through `curl` via the handler, like every other command, so
+//! a [`RecordingRunner`](crate::runner::RecordingRunner) can stub them and a
+//! local mock server can stand in for the service through `--base-url`. The
+//! token is read from an environment variable by the shell that runs `curl`,
+//! so it never appears in the plan, the summary or an error message.
+
+use clap::ValueEnum;
+use serde_json::{json, Value};
+
+use crate::error::SetupError;
+use crate::script_handler::ScriptHandler;
+
+/// Sends a request with `curl` and prints the body followed by the status
+/// code on its own line. Arguments: method, URL, auth header name, auth
+/// value prefix, token variable, JSON body (empty for none).
+const CURL: &str = r#"auth="$3: $4$(printenv "$5")"
+if [ -n "$6" ]; then
+  exec curl --silent --show-error --request "$1" --header "$auth" --header "Accept: application/json" --header "Content-Type: application/json" --data "$6" --write-out "\n%{http_code}" "$2"
+fi
+exec curl --silent --show-error --request "$1" --header "$auth" --header "Accept: application/json" --write-out "\n%{http_code}" "$2""#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Provider {
+    Github,
+    Gitlab,
+    Gitea,
+}
+
+impl Provider {
+    pub fn name(self) -> &'static str {
+        match self {
+            Provider::Github => "GitHub",
+            Provider::Gitlab => "GitLab",
+            Provider::Gitea => "Gitea",
+        }
+    }
+
+    /// API root of the public service.
+    pub fn default_base_url(self) -> &'static str {
+        match self {
+            Provider::Github => "https://api.github.com",
+            Provider::Gitlab => "https://gitlab.com/api/v4",
+            Provider::Gitea => "https://gitea.com/api/v1",
+        }
+    }
+
+    pub fn default_token_env(self) -> &'static str {
+        match self {
+            Provider::Github => "GITHUB_TOKEN",
+            Provider::Gitlab => "GITLAB_TOKEN",
+            Provider::Gitea => "GITEA_TOKEN",
+        }
+    }
+
+    /// Token permission needed to add keys, for error messages.
+    fn scope(self, signing: bool) -> &'static str {
+        match self {
+            Provider::Github if signing => "the write:public_key and write:ssh_signing_key scopes",
+            Provider::Github => "the write:public_key scope",
+            Provider::Gitlab => "the api scope",
+            Provider::Gitea => "the write:user scope",
+        }
+    }
+
+    /// Header name and value prefix that carry the token.
+    fn auth_header(self) -> (&'static str, &'static str) {
+        match self {
+            Provider::Github => ("Authorization", "Bearer "),
+            Provider::Gitlab => ("PRIVATE-TOKEN", ""),
+            Provider::Gitea => ("Authorization", "token "),
+        }
+    }
+}
+
+/// Which list a key is added to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyUse {
+    /// Logging in over SSH.
+    Authentication,
+    /// Verifying signed commits.
+    Signing,
+}
+
+/// A Git hosting service reached with a token.
+pub struct GitHost {
+    pub provider: Provider,
+    /// API root, e.g. `https://git.example.com/api/v1` for self-hosted Gitea.
+    pub base_url: String,
+    pub token_env: String,
+}
+
+impl GitHost {
+    pub fn new(provider: Provider, base_url: Option<&str>, token_env: Option<&str>) -> Self {
+        GitHost {
+            provider,
+            base_url: base_url
+                .unwrap_or(provider.default_base_url())
+                .trim_end_matches('/')
+                .to_string(),
+            token_env: token_env.unwrap_or(provider.default_token_env()).to_string(),
+        }
+    }
+
+    /// Fails unless the token variable is set, before any request is sent.
+    pub fn require_token(&self) -> Result<(), SetupError> {
+        match std::env::var(&self.token_env) {
+            Ok(token) if !token.is_empty() => Ok(()),
+            _ => Err(SetupError::Config(format!(
+                "Set {} to a {} token with {}",
+                self.token_env,
+                self.provider.name(),
+                self.provider.scope(false)
+            ))),
+        }
+    }
+
+    /// Path of the key list, `None` if the service keeps no separate list
+    /// for `key_use`.
+    fn keys_path(&self, key_use: KeyUse) -> Option<&'static str> {
+        match (self.provider, key_use) {
+            (Provider::Github, KeyUse::Signing) => Some("/user/ssh_signing_keys"),
+            // GitLab 把用途记在同一个密钥上，Gitea 用登录密钥验证签名
+            (_, KeyUse::Signing) => None,
+            (_, KeyUse::Authentication) => Some("/user/keys"),
+        }
+    }
+
+    /// True if `public_key` is already in the list for `key_use`.
+    pub fn has_key(&self, handler: &ScriptHandler, public_key: &str, key_use: KeyUse) -> Result<bool, SetupError> {
+        let path = match (self.provider, key_use) {
+            // GitLab 的签名用途记在登录密钥的 usage_type 上
+            (Provider::Gitlab, KeyUse::Signing) => "/user/keys",
+            _ => match self.keys_path(key_use) {
+                Some(path) => path,
+                None => return Ok(false),
+            },
+        };
+        let limit = match self.provider {
+            Provider::Gitea => "limit=50",
+            _ => "per_page=100",
+        };
+        let url = format!("{}{}?{}", self.base_url, path, limit);
+
+        let output = handler.query("sh", &self.curl_args("GET", &url, ""))?;
+        let body = self.response(&output.stdout, output.success, key_use == KeyUse::Signing)?;
+
+        let wanted = key_material(public_key);
+        let keys = body.as_array().map(Vec::as_slice).unwrap_or_default();
+        Ok(keys
+            .iter()
+            .filter(|key| key.get("key").and_then(Value::as_str).is_some_and(|key| key_material(key) == wanted))
+            .any(|key| self.usable_for(key, key_use)))
+    }
+
+    /// True if a listed key can be used for `key_use`. Only GitLab keys say
+    /// what they are for, in `usage_type`.
+    fn usable_for(&self, key: &Value, key_use: KeyUse) -> bool {
+        if self.provider != Provider::Gitlab {
+            return true;
+        }
+        let usage_type = key.get("usage_type").and_then(Value::as_str).unwrap_or("auth_and_signing");
+        match key_use {
+            KeyUse::Authentication => usage_type != "signing",
+            KeyUse::Signing => usage_type != "auth",
+        }
+    }
+
+    /// Adds `public_key` under `title`. Returns false if the service has
+    /// no list for `key_use`.
+    pub fn add_key(
+        &self,
+        handler: &ScriptHandler,
+        title: &str,
+        public_key: &str,
+        key_use: KeyUse,
+        also_signing: bool,
+    ) -> Result<bool, SetupError> {
+        let Some(path) = self.keys_path(key_use) else {
+            return Ok(false);
+        };
+        let mut body = json!({ "title": title, "key": public_key.trim() });
+        if self.provider == Provider::Gitlab && also_signing {
+            body["usage_type"] = json!("auth_and_signing");
+        }
+
+        let url = format!("{}{}", self.base_url, path);
+        let output = handler.run_output("sh", &self.curl_args("POST", &url, &body.to_string()))?;
+        // dry-run 不发送请求
+        if handler.planner().is_some() {
+            return Ok(true);
+        }
+        self.response(&output.stdout, output.success, key_use == KeyUse::Signing || also_signing)?;
+        Ok(true)
+    }
+
+    fn curl_args<'a>(&'a self, method: &'a str, url: &'a str, body: &'a str) -> Vec<&'a str> {
+        let (header, prefix) = self.provider.auth_header();
+        vec!["-c", CURL, "sh", method, url, header, prefix, &self.token_env, body]
+    }
+
+    /// The JSON body of a `curl` response, or an error naming the status
+    /// and the service's message.
+    fn response(&self, stdout: &str, success: bool, signing: bool) -> Result<Value, SetupError> {
+        let (body, status) = stdout.trim_end().rsplit_once('\n').unwrap_or(("", stdout.trim()));
+        let status: u16 = status.trim().parse().unwrap_or(0);
+        if !success || status == 0 {
+            return Err(format!("Could not reach {} at {}", self.provider.name(), self.base_url).into());
+        }
+
+        let json: Value = serde_json::from_str(body).unwrap_or(Value::Null);
+        if (200..300).contains(&status) {
+            return Ok(json);
+        }
+
+        let message = json
+            .get("message")
+            .map(|message| match message {
+                Value::String(message) => message.clone(),
+                other => other.to_string(),
+            })
+            .unwrap_or_else(|| body.trim().to_string());
+        let hint = match status {
+            401 | 403 => format!("; check that {} holds a token with {}", self.token_env, self.provider.scope(signing)),
+            404 => format!("; check the base URL {}", self.base_url),
+            _ => String::new(),
+        };
+        Err(format!("{} answered HTTP {}: {}{}", self.provider.name(), status, message, hint).into())
+    }
+}
+
+/// Key type and data without the comment, which services may drop.
+fn key_material(public_key: &str) -> String {
+    public_key.split_whitespace().take(2).collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::runner::{CommandOutput, RecordingRunner};
+    use crate::test_support::{handler, TempHome};
+
+    const KEY: &str = "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIGxq ada@example.com";
+
+    fn gitlab_keys(usage_type: &str) -> Arc<RecordingRunner> {
+        let host = GitHost::new(Provider::Gitlab, None, None);
+        let prefix = format!("sh -c {} sh GET {}/user/keys", CURL, host.base_url);
+        let body = json!([{ "id": 1, "key": "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIGxq", "usage_type": usage_type }]);
+        Arc::new(RecordingRunner::new().respond(&prefix, CommandOutput::success(&format!("{}\n200", body))))
+    }
+
+    #[test]
+    fn gitlab_signing_key_follows_usage_type() {
+        let home = TempHome::new("git-host-usage-type");
+        let host = GitHost::new(Provider::Gitlab, None, None);
+
+        let both = gitlab_keys("auth_and_signing");
+        assert!(host.has_key(&handler(&both, &home), KEY, KeyUse::Authentication).unwrap());
+        assert!(host.has_key(&handler(&both, &home), KEY, KeyUse::Signing).unwrap());
+
+        let auth = gitlab_keys("auth");
+        assert!(host.has_key(&handler(&auth, &home), KEY, KeyUse::Authentication).unwrap());
+        assert!(!host.has_key(&handler(&auth, &home), KEY, KeyUse::Signing).unwrap());
+    }
+
+    #[test]
+    fn response_reports_status_and_hint() {
+        let host = GitHost::new(Provider::Github, None, None);
+        assert_eq!(host.response("[]\n200", true, false).unwrap(), json!([]));
+
+        let error = host.response("{\"message\":\"Bad credentials\"}\n401", true, false).unwrap_err().to_string();
+        assert!(error.contains("HTTP 401: Bad credentials"));
+        assert!(error.contains("GITHUB_TOKEN"));
+    }
+}
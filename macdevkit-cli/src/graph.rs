@@ -0,0 +1,237 @@
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::error::SetupError;
+use crate::steps::{self, Step};
+
+/// A step and the steps it needs, for `graph --output json`.
+#[derive(Debug, Serialize)]
+pub struct Node {
+    pub id: &'static str,
+    pub dependencies: &'static [&'static str],
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mark {
+    Visiting,
+    Done,
+}
+
+struct Sorter<'a> {
+    registry: &'a [Box<dyn Step>],
+    /// Steps to order; `None` follows every dependency.
+    selected: Option<&'a [&'a str]>,
+    marks: HashMap<&'static str, Mark>,
+    path: Vec<&'static str>,
+    order: Vec<&'static str>,
+}
+
+impl Sorter<'_> {
+    fn visit(&mut self, id: &str) -> Result<(), SetupError> {
+        let step = self
+            .registry
+            .iter()
+            .find(|step| step.id() == id)
+            .ok_or_else(|| SetupError::Other(format!("Unknown step in dependencies: {}", id)))?;
+        let id = step.id();
+
+        match self.marks.get(id) {
+            Some(Mark::Done) => return Ok(()),
+            Some(Mark::Visiting) => {
+                // 从环的起点开始输出，例如 a → b → a
+                let start = self.path.iter().position(|&visiting| visiting == id).unwrap_or(0);
+                let mut cycle = self.path[start..].to_vec();
+                cycle.push(id);
+                return Err(SetupError::Other(format!("Step dependency cycle: {}", cycle.join(" → "))));
+            }
+            None => {}
+        }
+
+        self.marks.insert(id, Mark::Visiting);
+        self.path.push(id);
+        for &dependency in step.dependencies() {
+            if self.selected.is_none_or(|selected| selected.contains(&dependency)) {
+                self.visit(dependency)?;
+            }
+        }
+        self.path.pop();
+        self.marks.insert(id, Mark::Done);
+        self.order.push(id);
+        Ok(())
+    }
+}
+
+/// Ids in `ids` and, if `with_dependencies`, everything they depend on,
+/// ordered so each step comes after its dependencies and otherwise in
+/// registry order.
+fn sort(registry: &[Box<dyn Step>], ids: &[&str], with_dependencies: bool) -> Result<Vec<&'static str>, SetupError> {
+    let mut sorter = Sorter {
+        registry,
+        selected: if with_dependencies { None } else { Some(ids) },
+        marks: HashMap::new(),
+        path: Vec::new(),
+        order: Vec::new(),
+    };
+
+    for step in registry.iter().filter(|step| ids.contains(&step.id())) {
+        sorter.visit(step.id())?;
+    }
+    Ok(sorter.order)
+}
+
+fn boxed(ids: Vec<&'static str>) -> Vec<Box<dyn Step>> {
+    ids.into_iter().filter_map(steps::find).collect()
+}
+
+/// Orders `steps` so that each runs after those of its dependencies that
+/// are among them. Fails if the dependencies form a cycle.
+pub fn order(steps: Vec<Box<dyn Step>>) -> Result<Vec<Box<dyn Step>>, SetupError> {
+    let ids: Vec<&str> = steps.iter().map(|step| step.id()).collect();
+    sort(&steps::registry(), &ids, false).map(boxed)
+}
+
+/// `steps` plus every step they depend on, in the order to run them.
+pub fn with_dependencies(steps: Vec<Box<dyn Step>>) -> Result<Vec<Box<dyn Step>>, SetupError> {
+    let ids: Vec<&str> = steps.iter().map(|step| step.id()).collect();
+    sort(&steps::registry(), &ids, true).map(boxed)
+}
+
+/// Everything `step` depends on, directly or not, in the order to run them.
+pub fn prerequisites(step: &dyn Step) -> Result<Vec<Box<dyn Step>>, SetupError> {
+    prerequisite_ids(&steps::registry(), step.id()).map(boxed)
+}
+
+fn prerequisite_ids(registry: &[Box<dyn Step>], id: &str) -> Result<Vec<&'static str>, SetupError> {
+    let mut ids = sort(registry, &[id], true)?;
+    ids.retain(|&prerequisite| prerequisite != id);
+    Ok(ids)
+}
+
+/// Every step in run order with its direct dependencies.
+pub fn nodes() -> Result<Vec<Node>, SetupError> {
+    let ordered = with_dependencies(steps::registry())?;
+    Ok(ordered
+        .iter()
+        .map(|step| Node {
+            id: step.id(),
+            dependencies: step.dependencies(),
+        })
+        .collect())
+}
+
+/// `nodes` as text, one step per line with the steps it needs.
+pub fn render(nodes: &[Node]) -> String {
+    let width = nodes.iter().map(|node| node.id.len()).max().unwrap_or(0);
+    let mut out = String::new();
+    for node in nodes {
+        if node.dependencies.is_empty() {
+            out.push_str(&format!("{}\n", node.id));
+        } else {
+            out.push_str(&format!("{:<width$}  ← {}\n", node.id, node.dependencies.join(", "), width = width));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::script_handler::ScriptHandler;
+
+    struct Fake {
+        id: &'static str,
+        dependencies: &'static [&'static str],
+    }
+
+    impl Step for Fake {
+        fn id(&self) -> &'static str {
+            self.id
+        }
+
+        fn title(&self) -> &'static str {
+            self.id
+        }
+
+        fn description(&self) -> &'static str {
+            self.id
+        }
+
+        fn dependencies(&self) -> &'static [&'static str] {
+            self.dependencies
+        }
+
+        fn check(&self, _handler: &ScriptHandler) -> bool {
+            false
+        }
+
+        fn apply(&self, _handler: &ScriptHandler) -> Result<bool, SetupError> {
+            Ok(true)
+        }
+    }
+
+    fn registry(steps: &[(&'static str, &'static [&'static str])]) -> Vec<Box<dyn Step>> {
+        steps
+            .iter()
+            .map(|&(id, dependencies)| Box::new(Fake { id, dependencies }) as Box<dyn Step>)
+            .collect()
+    }
+
+    /// `app` needs `lib` and `tools`, which both need `base`; `extra` is
+    /// listed before all of them and needs nothing.
+    fn diamond() -> Vec<Box<dyn Step>> {
+        registry(&[
+            ("app", &["lib", "tools"]),
+            ("extra", &[]),
+            ("tools", &["base"]),
+            ("lib", &["base"]),
+            ("base", &[]),
+        ])
+    }
+
+    #[test]
+    fn orders_steps_after_their_dependencies() {
+        let registry = diamond();
+        assert_eq!(sort(&registry, &["app", "lib", "base", "tools"], false).unwrap(), ["base", "lib", "tools", "app"]);
+        // 彼此无依赖的步骤保持注册表顺序
+        assert_eq!(sort(&registry, &["base", "extra"], false).unwrap(), ["extra", "base"]);
+    }
+
+    #[test]
+    fn order_leaves_out_unselected_dependencies() {
+        let registry = diamond();
+        assert_eq!(sort(&registry, &["app", "tools"], false).unwrap(), ["tools", "app"]);
+    }
+
+    #[test]
+    fn with_dependencies_adds_everything_needed() {
+        let registry = diamond();
+        assert_eq!(sort(&registry, &["app"], true).unwrap(), ["base", "lib", "tools", "app"]);
+        assert_eq!(sort(&registry, &["extra", "tools"], true).unwrap(), ["extra", "base", "tools"]);
+    }
+
+    #[test]
+    fn prerequisites_leave_out_the_step_itself() {
+        let registry = diamond();
+        assert_eq!(prerequisite_ids(&registry, "app").unwrap(), ["base", "lib", "tools"]);
+        assert!(prerequisite_ids(&registry, "base").unwrap().is_empty());
+    }
+
+    #[test]
+    fn cycle_is_an_error() {
+        let registry = registry(&[("a", &["b"]), ("b", &["a"])]);
+        let error = sort(&registry, &["a"], true).unwrap_err();
+        assert_eq!(error.to_string(), "Step dependency cycle: a → b → a");
+        assert!(sort(&registry, &["a", "b"], false).is_err());
+    }
+
+    #[test]
+    fn unknown_dependency_is_an_error() {
+        let registry = registry(&[("a", &["missing"])]);
+        assert!(sort(&registry, &["a"], true).is_err());
+    }
+
+    #[test]
+    fn registry_has_no_cycles() {
+        assert_eq!(nodes().unwrap().len(), steps::registry().len());
+    }
+}
@@ -0,0 +1,325 @@
+//! Package installs, batched and run concurrently.
+//!
+//! Steps return the formulae, casks, npm packages and VS Code extensions
+//! they need from [`Step::packages`](crate::steps::Step::packages) instead of
+//! installing them one by one. Each [`Batch`] becomes a single command, such
+//! as `brew install jq ripgrep fd`. Batches for different tools run at the
+//! same time with a progress line each; batches for the same tool run one
+//! after another, since Homebrew refuses to run twice at once. Their output
+//! goes to `install.log` in the state directory instead of the terminal.
+
+use colored::*;
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::changes::{Change, PackageKind};
+use crate::error::SetupError;
+use crate::journal;
+use crate::node::{self, NodeManager};
+use crate::runner::OutputStream;
+use crate::script_handler::ScriptHandler;
+
+/// Lines of a failed command's output shown on the terminal.
+const TAIL_LINES: usize = 5;
+
+type CommandFn = dyn Fn(&[String]) -> (String, Vec<String>) + Send + Sync;
+
+/// Packages of one kind that a step installs with one command.
+pub struct Batch {
+    pub step: &'static str,
+    pub kind: PackageKind,
+    pub names: Vec<String>,
+    /// Manager whose Node.js npm packages are installed with.
+    pub node_manager: Option<NodeManager>,
+    command: Box<CommandFn>,
+}
+
+impl Batch {
+    /// `command` returns the program and arguments that install some of
+    /// the packages.
+    pub fn new(
+        step: &'static str,
+        kind: PackageKind,
+        names: Vec<String>,
+        command: impl Fn(&[String]) -> (String, Vec<String>) + Send + Sync + 'static,
+    ) -> Self {
+        Batch {
+            step,
+            kind,
+            names,
+            node_manager: None,
+            command: Box::new(command),
+        }
+    }
+
+    /// `brew install` for formulae, or `brew install --cask` for casks.
+    pub fn brew(step: &'static str, names: Vec<String>, cask: bool) -> Self {
+        let kind = if cask { PackageKind::Cask } else { PackageKind::Formula };
+        Self::new(step, kind, names, move |names| {
+            let mut args = vec!["install".to_string()];
+            if cask {
+                args.push("--cask".to_string());
+            }
+            args.extend(names.iter().cloned());
+            ("brew".to_string(), args)
+        })
+    }
+
+    /// `code --install-extension` once per extension.
+    pub fn extensions(step: &'static str, names: Vec<String>) -> Self {
+        Self::new(step, PackageKind::VscodeExtension, names, |names| {
+            let args = names
+                .iter()
+                .flat_map(|name| ["--install-extension".to_string(), name.clone()])
+                .collect();
+            ("code".to_string(), args)
+        })
+    }
+
+    /// `npm install -g` in `bash`, after the `manager`'s `prelude`; see
+    /// [`node::bash_args`].
+    pub fn npm(step: &'static str, names: Vec<String>, manager: NodeManager, prelude: String) -> Self {
+        let mut batch = Self::new(step, PackageKind::Npm, names, move |names| {
+            let mut command = vec!["npm", "install", "-g"];
+            command.extend(names.iter().map(String::as_str));
+            ("bash".to_string(), node::bash_args(&prelude, &command))
+        });
+        batch.node_manager = Some(manager);
+        batch
+    }
+
+    fn label(&self) -> String {
+        let kind = match self.kind {
+            PackageKind::Formula => "brew",
+            PackageKind::Cask => "brew cask",
+            PackageKind::Npm => "npm",
+            PackageKind::VscodeExtension => "code",
+            PackageKind::Mas => "mas",
+        };
+        format!("{} {}", kind, self.names.join(", "))
+    }
+
+    /// Batches that share a lane run one after another.
+    fn lane(&self) -> u8 {
+        match self.kind {
+            // brew 同一时间只允许一个进程
+            PackageKind::Formula | PackageKind::Cask => 0,
+            PackageKind::Npm => 1,
+            PackageKind::VscodeExtension => 2,
+            PackageKind::Mas => 3,
+        }
+    }
+}
+
+/// What became of one batch.
+struct BatchResult {
+    step: &'static str,
+    label: String,
+    installed: Vec<String>,
+    failed: Vec<String>,
+    error: Option<SetupError>,
+    /// Last lines of output of the command that failed.
+    tail: Vec<String>,
+}
+
+/// Appends the output of every install to one file.
+struct InstallLog {
+    path: Option<PathBuf>,
+    file: Option<Mutex<File>>,
+}
+
+impl InstallLog {
+    fn open(handler: &ScriptHandler) -> Self {
+        // dry-run 不执行命令，也就没有输出可记录
+        if handler.planner().is_some() {
+            return InstallLog { path: None, file: None };
+        }
+
+        let path = log_path();
+        let file = path.as_ref().and_then(|path| {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir).ok()?;
+            }
+            match fs::OpenOptions::new().create(true).append(true).open(path) {
+                Ok(file) => Some(Mutex::new(file)),
+                Err(e) => {
+                    say!("{}", format!("Warning: could not open {}: {}", path.display(), e).yellow());
+                    None
+                }
+            }
+        });
+        InstallLog { path, file }
+    }
+
+    fn write(&self, label: &str, line: &str) {
+        if let Some(file) = &self.file {
+            // 日志写入失败不影响安装本身
+            let _ = writeln!(file.lock().unwrap(), "[{}] {}", label, line);
+        }
+    }
+}
+
+/// `install.log` in the state directory.
+pub fn log_path() -> Option<PathBuf> {
+    journal::state_dir().map(|dir| dir.join("install.log"))
+}
+
+/// Installs `batches`, each with one command, and returns for every step
+/// whether all of its packages were installed.
+///
+/// A batch that fails is retried one package at a time, so a single bad
+/// name does not fail the rest.
+pub fn install(handler: &ScriptHandler, batches: Vec<Batch>) -> BTreeMap<&'static str, bool> {
+    let mut outcome = BTreeMap::new();
+    let mut lanes: BTreeMap<u8, Vec<Batch>> = BTreeMap::new();
+    for batch in batches.into_iter().filter(|batch| !batch.names.is_empty()) {
+        outcome.insert(batch.step, true);
+        lanes.entry(batch.lane()).or_default().push(batch);
+    }
+    if lanes.is_empty() {
+        return outcome;
+    }
+
+    let log = InstallLog::open(handler);
+    let multi = MultiProgress::new();
+    if handler.planner().is_some() {
+        multi.set_draw_target(ProgressDrawTarget::hidden());
+    }
+
+    let results = Mutex::new(Vec::new());
+    std::thread::scope(|scope| {
+        for lane in lanes.values() {
+            let (log, multi, results) = (&log, &multi, &results);
+            scope.spawn(move || {
+                for batch in lane {
+                    let bar = multi.add(spinner(&batch.label()));
+                    let result = run_batch(handler, batch, &bar, log);
+                    bar.finish_and_clear();
+                    results.lock().unwrap().push(result);
+                }
+            });
+        }
+    });
+
+    for result in results.into_inner().unwrap() {
+        if !result.installed.is_empty() {
+            handler.report_installed(&result.installed.join(", "));
+        }
+        if result.failed.is_empty() && result.error.is_none() {
+            continue;
+        }
+
+        outcome.insert(result.step, false);
+        match &result.error {
+            Some(e) => say!("{}", format!("Failed to run {}: {}", result.label, e).red()),
+            None => say!("{}", format!("Failed to install {}", result.failed.join(", ")).red()),
+        }
+        for line in result.tail.iter() {
+            say!("    {}", line.dimmed());
+        }
+        if let Some(path) = &log.path {
+            say!("{}", format!("See {} for the full output", path.display()).yellow());
+        }
+    }
+
+    outcome
+}
+
+fn spinner(label: &str) -> ProgressBar {
+    let bar = ProgressBar::new_spinner()
+        .with_style(
+            ProgressStyle::with_template("{spinner:.cyan} {prefix:.bold} {wide_msg:.dim}")
+                .unwrap_or_else(|_| ProgressStyle::default_spinner()),
+        )
+        .with_prefix(label.to_string());
+    bar.enable_steady_tick(Duration::from_millis(100));
+    bar
+}
+
+fn run_batch(handler: &ScriptHandler, batch: &Batch, bar: &ProgressBar, log: &InstallLog) -> BatchResult {
+    let mut result = BatchResult {
+        step: batch.step,
+        label: batch.label(),
+        installed: Vec::new(),
+        failed: Vec::new(),
+        error: None,
+        tail: Vec::new(),
+    };
+
+    match run(handler, batch, &batch.names, bar, log) {
+        Ok((true, _)) => {
+            result.installed = batch.names.clone();
+        }
+        Ok((false, tail)) if batch.names.len() == 1 => {
+            result.failed = batch.names.clone();
+            result.tail = tail;
+        }
+        Ok((false, _)) => {
+            // 整批失败时逐个重试，找出真正失败的包
+            for name in batch.names.iter() {
+                match run(handler, batch, std::slice::from_ref(name), bar, log) {
+                    Ok((true, _)) => result.installed.push(name.clone()),
+                    Ok((false, tail)) => {
+                        result.failed.push(name.clone());
+                        result.tail = tail;
+                    }
+                    Err(e) => {
+                        result.failed.push(name.clone());
+                        result.error = Some(e);
+                        break;
+                    }
+                }
+            }
+        }
+        Err(e) => {
+            result.failed = batch.names.clone();
+            result.error = Some(e);
+        }
+    }
+
+    for name in result.installed.iter() {
+        handler.record_change_for(
+            batch.step,
+            Change::PackageInstall {
+                kind: batch.kind,
+                name: name.clone(),
+                node_manager: batch.node_manager,
+            },
+        );
+    }
+    result
+}
+
+/// Runs the batch's command for `names`; returns whether it succeeded and
+/// the last lines it wrote.
+fn run(
+    handler: &ScriptHandler,
+    batch: &Batch,
+    names: &[String],
+    bar: &ProgressBar,
+    log: &InstallLog,
+) -> Result<(bool, Vec<String>), SetupError> {
+    let (program, args) = (batch.command)(names);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let label = format!("{} {}", batch.step, program);
+    log.write(&label, &format!("$ {} {}", program, args.join(" ")));
+
+    let tail = Mutex::new(VecDeque::with_capacity(TAIL_LINES));
+    let on_line = |_stream: OutputStream, line: &str| {
+        log.write(&label, line);
+        bar.set_message(line.to_string());
+        let mut tail = tail.lock().unwrap();
+        if tail.len() == TAIL_LINES {
+            tail.pop_front();
+        }
+        tail.push_back(line.to_string());
+    };
+
+    let success = handler.run_logged(batch.step, &program, &args, &on_line)?;
+    Ok((success, tail.into_inner().unwrap().into()))
+}
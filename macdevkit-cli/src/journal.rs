@@ -0,0 +1,219 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Outcome of a step in the journal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Done,
+    Failed,
+    Skipped,
+}
+
+impl StepStatus {
+    /// Done and skipped steps are not run again by `resume`.
+    pub fn is_complete(self) -> bool {
+        matches!(self, StepStatus::Done | StepStatus::Skipped)
+    }
+}
+
+impl fmt::Display for StepStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StepStatus::Done => write!(f, "done"),
+            StepStatus::Failed => write!(f, "failed"),
+            StepStatus::Skipped => write!(f, "skipped"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepRecord {
+    pub id: String,
+    pub status: StepStatus,
+    /// Seconds since the Unix epoch.
+    pub started_at: u64,
+    pub finished_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Persistent record of the current setup run, so an interrupted setup can
+/// be resumed after a failure or a reboot.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Journal {
+    pub started_at: u64,
+    /// Profile the setup was started with, so `resume` runs the same steps.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    pub steps: Vec<StepRecord>,
+
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl Journal {
+    /// Starts an empty journal at `path`, replacing any previous run.
+    pub fn new(path: PathBuf) -> Self {
+        Journal {
+            started_at: now(),
+            profile: None,
+            steps: Vec::new(),
+            path,
+        }
+    }
+
+    /// Reads the journal at `path`; `Ok(None)` if there is none yet.
+    pub fn load(path: &Path) -> Result<Option<Self>, String> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let mut journal: Journal = serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid journal {}: {}", path.display(), e))?;
+
+        journal.path = path.to_path_buf();
+        Ok(Some(journal))
+    }
+
+    /// Loads the journal at `path`, or starts a new one if there is none.
+    pub fn open(path: PathBuf) -> Result<Self, String> {
+        Ok(Self::load(&path)?.unwrap_or_else(|| Journal::new(path)))
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(&self.path, content)
+            .map_err(|e| format!("Failed to write {}: {}", self.path.display(), e))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Records the outcome of a step, replacing any earlier record for it.
+    pub fn record(&mut self, id: &str, status: StepStatus, started_at: u64, error: Option<String>) {
+        self.steps.retain(|record| record.id != id);
+        self.steps.push(StepRecord {
+            id: id.to_string(),
+            status,
+            started_at,
+            finished_at: now(),
+            error,
+        });
+    }
+
+    /// Removes the step's record, e.g. after it has been undone.
+    pub fn forget(&mut self, id: &str) {
+        self.steps.retain(|record| record.id != id);
+    }
+
+    pub fn status(&self, id: &str) -> Option<StepStatus> {
+        self.steps
+            .iter()
+            .find(|record| record.id == id)
+            .map(|record| record.status)
+    }
+}
+
+/// `$XDG_STATE_HOME/macdevkit`, falling back to `~/.local/state/macdevkit`.
+pub fn state_dir() -> Option<PathBuf> {
+    let state_home = match std::env::var_os("XDG_STATE_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".local").join("state"),
+    };
+    Some(state_home.join("macdevkit"))
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    state_dir().map(|dir| dir.join("journal.json"))
+}
+
+/// Current time in seconds since the Unix epoch.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempHome;
+
+    #[test]
+    fn record_replaces_earlier_outcome() {
+        let mut journal = Journal::new(PathBuf::from("journal.json"));
+        journal.record("brew", StepStatus::Failed, 10, Some("network".to_string()));
+        journal.record("git", StepStatus::Skipped, 11, None);
+        journal.record("brew", StepStatus::Done, 12, None);
+
+        assert_eq!(journal.status("brew"), Some(StepStatus::Done));
+        assert_eq!(journal.status("git"), Some(StepStatus::Skipped));
+        assert_eq!(journal.status("node"), None);
+        assert_eq!(journal.steps.iter().filter(|record| record.id == "brew").count(), 1);
+        let brew = journal.steps.iter().find(|record| record.id == "brew").unwrap();
+        assert_eq!((brew.started_at, brew.error.as_deref()), (12, None));
+        assert!(brew.finished_at >= brew.started_at);
+    }
+
+    #[test]
+    fn forget_removes_the_step() {
+        let mut journal = Journal::new(PathBuf::from("journal.json"));
+        journal.record("brew", StepStatus::Done, 10, None);
+        journal.record("git", StepStatus::Done, 11, None);
+
+        journal.forget("brew");
+        journal.forget("node");
+        assert_eq!(journal.status("brew"), None);
+        assert_eq!(journal.status("git"), Some(StepStatus::Done));
+    }
+
+    #[test]
+    fn only_done_and_skipped_are_complete() {
+        assert!(StepStatus::Done.is_complete());
+        assert!(StepStatus::Skipped.is_complete());
+        assert!(!StepStatus::Failed.is_complete());
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let home = TempHome::new("journal");
+        let path = home.path.join("state/journal.json");
+        assert!(Journal::load(&path).unwrap().is_none());
+
+        let mut journal = Journal::open(path.clone()).unwrap();
+        journal.profile = Some("frontend".to_string());
+        journal.record("brew", StepStatus::Done, 10, None);
+        journal.record("node", StepStatus::Failed, 11, Some("nvm install failed".to_string()));
+        journal.save().unwrap();
+
+        let loaded = Journal::load(&path).unwrap().unwrap();
+        assert_eq!(loaded.path(), path);
+        assert_eq!(loaded.started_at, journal.started_at);
+        assert_eq!(loaded.profile.as_deref(), Some("frontend"));
+        assert_eq!(loaded.status("brew"), Some(StepStatus::Done));
+        assert_eq!(loaded.status("node"), Some(StepStatus::Failed));
+        assert_eq!(loaded.steps[1].error.as_deref(), Some("nvm install failed"));
+        assert!(std::fs::read_to_string(&path).unwrap().contains("\"status\": \"failed\""));
+    }
+
+    #[test]
+    fn load_rejects_invalid_journal() {
+        let home = TempHome::new("journal-invalid");
+        let path = home.path.join("journal.json");
+        std::fs::write(&path, "{ not json").unwrap();
+        assert!(Journal::load(&path).unwrap_err().starts_with("Invalid journal"));
+    }
+}
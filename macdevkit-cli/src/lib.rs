@@ -0,0 +1,39 @@
+//! MacDevKit: setup steps and the machinery that runs them.
+//!
+//! The `macdevkit` binary is a thin clap front end over this library; the
+//! steps themselves only talk to the system through [`ScriptHandler`] and its
+//! [`CommandRunner`], so they can be driven by a [`RecordingRunner`] in tests.
+//!
+//! [`ScriptHandler`]: script_handler::ScriptHandler
+//! [`CommandRunner`]: runner::CommandRunner
+//! [`RecordingRunner`]: runner::RecordingRunner
+
+#[macro_use]
+pub mod output;
+pub mod brewfile;
+pub mod changes;
+pub mod doctor;
+pub mod error;
+pub mod events;
+pub mod git;
+pub mod git_host;
+pub mod graph;
+pub mod install;
+pub mod journal;
+pub mod managed_block;
+pub mod manifest;
+pub mod mirror;
+pub mod node;
+pub mod plan;
+pub mod profile;
+pub mod platform;
+pub mod prompt;
+pub mod runner;
+pub mod script_handler;
+pub mod shell_profile;
+pub mod signing;
+pub mod ssh;
+pub mod steps;
+
+#[cfg(test)]
+mod test_support;
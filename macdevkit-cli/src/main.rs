@@ -1,78 +1,247 @@
-use clap::{Parser, Subcommand};
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand};
 use colored::*;
-use dialoguer::{theme::ColorfulTheme, Confirm, Select};
-use std::process::Command;
-use which::which;
+use std::path::PathBuf;
 
-mod script_handler;
-use script_handler::ScriptHandler;
+use std::sync::Arc;
+
+use macdevkit_cli::changes::{self, ChangeLog};
+use macdevkit_cli::doctor;
+use macdevkit_cli::error::{ExitCode, SetupError};
+use macdevkit_cli::events::{EventFormat, EventStream};
+use macdevkit_cli::graph;
+use macdevkit_cli::journal::{self, Journal, StepStatus};
+use macdevkit_cli::manifest::Manifest;
+use macdevkit_cli::mirror::{BrewMirror, MirrorName};
+use macdevkit_cli::output::{self, OutputFormat};
+use macdevkit_cli::plan::Planner;
+use macdevkit_cli::profile::Profile;
+use macdevkit_cli::prompt::{Answers, Confirmation, Prompter};
+use macdevkit_cli::runner::SystemRunner;
+use macdevkit_cli::say;
+use macdevkit_cli::script_handler::ScriptHandler;
+use macdevkit_cli::steps::{self, Step};
 
 #[derive(Parser)]
 #[command(name = "macdevkit")]
 #[command(about = "MacDevKit: A comprehensive setup tool for macOS development environments", long_about = None)]
 struct Cli {
+    /// Setup manifest to use (default: ~/.config/macdevkit/macdevkit.toml)
+    #[arg(long, global = true, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Print every command and file change instead of executing it
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// Format of the results written to stdout
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+
+    /// Stream step progress and command output to stdout as it happens
+    #[arg(long, global = true, value_enum, value_name = "FORMAT")]
+    events: Option<EventFormat>,
+
+    /// Answer yes to every confirmation instead of asking
+    #[arg(long, short = 'y', global = true, conflicts_with = "no")]
+    yes: bool,
+
+    /// Answer no to every confirmation instead of asking
+    #[arg(long, global = true)]
+    no: bool,
+
+    /// TOML file with answers to prompts, e.g. the Git name and email
+    #[arg(long, global = true, value_name = "FILE")]
+    answers: Option<PathBuf>,
+
+    /// Run each step with this shell script instead of the built-in implementation
+    #[arg(long, global = true, value_name = "FILE")]
+    script: Option<PathBuf>,
+
+    /// Install Homebrew and its packages from a mirror
+    #[arg(long, global = true, value_enum, value_name = "MIRROR")]
+    mirror: Option<MirrorName>,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
 
+// Each setup step adds its own subcommand from the step registry
 #[derive(Subcommand)]
 enum Commands {
-    /// Run the full setup with interactive prompts
-    Setup,
-    /// Install Xcode Command Line Tools
-    Xcode,
-    /// Install Homebrew
-    Brew,
-    /// Install and configure Git
-    Git,
-    /// Generate SSH key
-    Ssh,
-    /// Install Visual Studio Code
-    Vscode,
-    /// Install Node.js via NVM
-    Node,
-    /// Install iTerm2
-    Iterm,
-    /// Install Oh My Zsh
-    Zsh,
-    /// Install Docker
-    Docker,
-    /// Install additional developer tools
-    DevTools,
-    /// Install useful applications
-    Apps,
-    /// Configure macOS settings
-    MacOS,
-    /// Create development workspace
-    Workspace,
+    /// Run the full setup, or the steps of a profile
+    Setup {
+        /// Run only the steps and packages of this profile, e.g. `frontend`, `backend`, `mobile` or `data`
+        #[arg(long, value_name = "NAME")]
+        profile: Option<String>,
+    },
+    /// Continue the last setup from the first step that did not complete
+    Resume,
+    /// Revert the changes a step made: defaults, shell profile lines and packages
+    Undo {
+        /// Step to undo, e.g. `macos` or `brew`
+        step: String,
+    },
+    /// Check every step without changing anything and report what needs fixing
+    Doctor,
+    /// Print the steps in run order with the steps each one needs
+    Graph,
 }
 
-fn main() {
+fn main() -> std::process::ExitCode {
+    let matches = build_cli().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    output::set_machine_readable(
+        cli.output == OutputFormat::Json || cli.events.is_some() || exports_to_stdout(&matches),
+    );
     print_welcome();
-    
-    let cli = Cli::parse();
-    
-    match &cli.command {
-        Some(Commands::Setup) => run_full_setup(),
-        Some(Commands::Xcode) => install_xcode_tools(),
-        Some(Commands::Brew) => install_homebrew(),
-        Some(Commands::Git) => install_git(),
-        Some(Commands::Ssh) => generate_ssh_key(),
-        Some(Commands::Vscode) => install_vscode(),
-        Some(Commands::Node) => install_node(),
-        Some(Commands::Iterm) => install_iterm(),
-        Some(Commands::Zsh) => install_oh_my_zsh(),
-        Some(Commands::Docker) => install_docker(),
-        Some(Commands::DevTools) => install_dev_tools(),
-        Some(Commands::Apps) => install_apps(),
-        Some(Commands::MacOS) => configure_macos(),
-        Some(Commands::Workspace) => create_workspace(),
-        None => run_interactive_menu(),
+
+    let mut manifest = match Manifest::load(cli.config.as_deref()) {
+        Ok(manifest) => manifest,
+        Err(e) => return report_error(&SetupError::Config(e)).into(),
+    };
+    if cli.mirror.is_some() {
+        manifest.mirror.name = cli.mirror;
+    }
+
+    // resume 沿用上次 setup 选择的 profile
+    let profile_name = match &cli.command {
+        Some(Commands::Setup { profile }) => profile.clone(),
+        Some(Commands::Resume) => journal::default_path()
+            .and_then(|path| Journal::load(&path).ok().flatten())
+            .and_then(|journal| journal.profile),
+        _ => None,
+    };
+    let profile = match profile_name {
+        Some(name) => match select_profile(&mut manifest, &name) {
+            Ok(profile) => Some((name, profile)),
+            Err(e) => return report_error(&e).into(),
+        },
+        None => None,
+    };
+    let profile = profile.as_ref().map(|(name, profile)| (name.as_str(), profile));
+
+    match BrewMirror::from_settings(&manifest.mirror) {
+        Ok(Some(mirror)) => mirror.export(),
+        Ok(None) => {}
+        Err(e) => return report_error(&SetupError::Config(e)).into(),
+    }
+    let answers = match cli.answers.as_deref().map(Answers::load).transpose() {
+        Ok(answers) => answers.unwrap_or_default(),
+        Err(e) => return report_error(&SetupError::Config(e)).into(),
+    };
+    let confirmation = if cli.yes {
+        Confirmation::Yes
+    } else if cli.no {
+        Confirmation::No
+    } else {
+        Confirmation::Ask
+    };
+
+    let mut script_handler = ScriptHandler::new(manifest, Arc::new(SystemRunner))
+        .with_prompter(Prompter::new(confirmation, answers));
+    if let Some(script) = &cli.script {
+        match script.canonicalize() {
+            Ok(script) if script.is_file() => script_handler = script_handler.with_script(script),
+            Ok(_) => return report_error(&SetupError::Config(format!("{} is not a file", script.display()))).into(),
+            Err(e) => {
+                let error = SetupError::Config(format!("Failed to read script {}: {}", script.display(), e));
+                return report_error(&error).into();
+            }
+        }
+    }
+    if cli.events == Some(EventFormat::Ndjson) {
+        script_handler = script_handler.with_events(EventStream::stdout());
+    }
+    if cli.dry_run {
+        script_handler = script_handler.with_planner(Planner::new(cli.output == OutputFormat::Text));
+    } else if let Some(path) = journal::default_path() {
+        match Journal::open(path) {
+            Ok(journal) => script_handler = script_handler.with_journal(journal),
+            Err(e) => say!("{}", format!("Warning: {}", e).yellow()),
+        }
+    }
+    if let Some(path) = changes::default_path() {
+        match ChangeLog::open(path) {
+            Ok(changes) => script_handler = script_handler.with_changes(changes),
+            Err(e) => say!("{}", format!("Warning: {}", e).yellow()),
+        }
+    }
+
+    // doctor 和 graph 只做检查，dry-run 不改动系统，其余命令只能在macOS上运行
+    let read_only = cli.dry_run || matches!(cli.command, Some(Commands::Doctor | Commands::Graph));
+    if !read_only && std::env::consts::OS != "macos" {
+        let platform = std::env::consts::OS;
+        let error = SetupError::UnsupportedPlatform(format!("MacDevKit only runs on macOS, not {}", platform));
+        return report_error(&error).into();
+    }
+
+    let exit_code = match &cli.command {
+        Some(Commands::Setup { .. }) => run_full_setup(&script_handler, profile),
+        Some(Commands::Resume) => resume_setup(&script_handler, profile),
+        Some(Commands::Undo { step }) => undo_step(&script_handler, step),
+        Some(Commands::Doctor) => run_doctor(&script_handler, cli.output),
+        Some(Commands::Graph) => print_graph(cli.output),
+        None => match matches.subcommand() {
+            Some((name, step_matches)) => run_step_by_name(&script_handler, name, step_matches),
+            None => run_interactive_menu(&script_handler),
+        },
+    };
+
+    if cli.output == OutputFormat::Json {
+        print_json_result(&script_handler, cli.events.is_some());
+    }
+
+    exit_code.into()
+}
+
+/// Prints the dry-run plan, or the summary of the steps that ran. Commands
+/// that run no steps, such as `doctor`, print their own result instead.
+fn print_json_result(script_handler: &ScriptHandler, events: bool) {
+    if let Some(planner) = script_handler.planner() {
+        println!("{}", planner.to_json());
+        return;
+    }
+
+    let summary = script_handler.summary();
+    if summary.steps.is_empty() {
+        return;
     }
+    if events {
+        // 与事件流共用stdout时，摘要也必须占一行
+        println!("{}", serde_json::to_string(&summary).unwrap_or_default());
+    } else {
+        println!("{}", summary.to_json());
+    }
+}
+
+/// True for `<step> export` without `--file`, whose result goes to stdout
+/// and must not be mixed with the banner and progress messages.
+fn exports_to_stdout(matches: &ArgMatches) -> bool {
+    matches
+        .subcommand()
+        .and_then(|(_, step_matches)| step_matches.subcommand())
+        .is_some_and(|(name, export_matches)| {
+            name == "export" && !export_matches.contains_id("file")
+        })
+}
+
+fn build_cli() -> clap::Command {
+    steps::registry()
+        .iter()
+        .fold(Cli::command(), |cli, step| cli.subcommand(step_command(step.as_ref())))
+}
+
+fn step_command(step: &dyn Step) -> clap::Command {
+    clap::Command::new(step.id())
+        .about(step.title())
+        .long_about(step.description())
+        .aliases(step.aliases())
+        .subcommands(step.subcommands())
 }
 
 fn print_welcome() {
-    println!("{}", r#"
+    say!("{}", r#"
     __  ___          ____             __ __ _ __ 
    /  |/  /___ _____/ __ \___ _   __/ //_/(_) /_
   / /|_/ / __ `/ __/ / / / _ \ | / / ,<  / / __/
@@ -81,366 +250,299 @@ fn print_welcome() {
                                                  
 "#.bright_blue());
     
-    println!("{}", "Welcome to MacDevKit - Your Ultimate macOS Development Environment Setup Tool".yellow());
-    println!();
-    println!("{}", "This CLI tool will help you:".cyan());
-    println!("  {}  Install essential developer tools", "✓".green());
-    println!("  {}  Configure your development environment", "✓".green());
-    println!("  {}  Set up programming languages and frameworks", "✓".green());
-    println!("  {}  Install useful applications", "✓".green());
-    println!("  {}  Optimize your macOS settings", "✓".green());
-    println!();
+    say!("{}", "Welcome to MacDevKit - Your Ultimate macOS Development Environment Setup Tool".yellow());
+    say!();
+    say!("{}", "This CLI tool will help you:".cyan());
+    say!("  {}  Install essential developer tools", "✓".green());
+    say!("  {}  Configure your development environment", "✓".green());
+    say!("  {}  Set up programming languages and frameworks", "✓".green());
+    say!("  {}  Install useful applications", "✓".green());
+    say!("  {}  Optimize your macOS settings", "✓".green());
+    say!();
 }
 
-fn run_interactive_menu() {
-    let options = vec![
-        "Full Setup",
-        "Install Xcode Command Line Tools",
-        "Install Homebrew",
-        "Install and configure Git",
-        "Generate SSH key",
-        "Install Visual Studio Code",
-        "Install Node.js via NVM",
-        "Install iTerm2",
-        "Install Oh My Zsh",
-        "Install Docker",
-        "Install additional developer tools",
-        "Install useful applications",
-        "Configure macOS settings",
-        "Create development workspace",
-        "Exit",
-    ];
-    
-    let selection = Select::with_theme(&ColorfulTheme::default())
-        .with_prompt("Select an option")
-        .default(0)
-        .items(&options)
-        .interact()
-        .unwrap();
-    
-    match selection {
-        0 => run_full_setup(),
-        1 => install_xcode_tools(),
-        2 => install_homebrew(),
-        3 => install_git(),
-        4 => generate_ssh_key(),
-        5 => install_vscode(),
-        6 => install_node(),
-        7 => install_iterm(),
-        8 => install_oh_my_zsh(),
-        9 => install_docker(),
-        10 => install_dev_tools(),
-        11 => install_apps(),
-        12 => configure_macos(),
-        13 => create_workspace(),
-        14 => println!("{}", "Goodbye!".green()),
-        _ => unreachable!(),
+fn run_interactive_menu(script_handler: &ScriptHandler) -> ExitCode {
+    let steps = steps::registry();
+
+    let mut options = vec!["Full Setup"];
+    options.extend(steps.iter().map(|step| step.title()));
+    options.push("Exit");
+
+    let selection = match script_handler.select("Select an option", &options) {
+        Ok(selection) => selection,
+        Err(e) => return report_error(&e),
+    };
+
+    if selection == 0 {
+        run_full_setup(script_handler, None)
+    } else if let Some(step) = steps.get(selection - 1) {
+        run_step(script_handler, step.as_ref())
+    } else {
+        say!("{}", "Goodbye!".green());
+        ExitCode::Success
     }
 }
 
-fn run_full_setup() {
-    println!("{}", "\n==== Running Full Setup ====\n".blue());
-    
-    // Run all steps sequentially with confirmation for each
-    if confirm_step("Install Xcode Command Line Tools") {
-        install_xcode_tools();
-    }
-    
-    if confirm_step("Install Homebrew") {
-        install_homebrew();
-    }
-    
-    if confirm_step("Install and Configure Git") {
-        install_git();
-    }
-    
-    if confirm_step("Generate SSH Key") {
-        generate_ssh_key();
-    }
-    
-    if confirm_step("Install Visual Studio Code") {
-        install_vscode();
-    }
-    
-    if confirm_step("Install Node.js via NVM") {
-        install_node();
+/// Looks up the profile `name` and applies its package lists to `manifest`.
+fn select_profile(manifest: &mut Manifest, name: &str) -> Result<Profile, SetupError> {
+    let Some(profile) = manifest.profile(name) else {
+        return Err(SetupError::Config(format!(
+            "Unknown profile: {} (available: {})",
+            name,
+            manifest.profile_names().join(", ")
+        )));
+    };
+    if let Some(unknown) = profile.steps.iter().find(|id| steps::find(id).is_none()) {
+        return Err(SetupError::Config(format!("Profile {} lists unknown step: {}", name, unknown)));
     }
-    
-    if confirm_step("Install iTerm2") {
-        install_iterm();
-    }
-    
-    if confirm_step("Install Oh My Zsh") {
-        install_oh_my_zsh();
-    }
-    
-    if confirm_step("Install Docker") {
-        install_docker();
-    }
-    
-    if confirm_step("Install Additional Developer Tools") {
-        install_dev_tools();
-    }
-    
-    if confirm_step("Install Useful Applications") {
-        install_apps();
-    }
-    
-    if confirm_step("Configure macOS Settings") {
-        configure_macos();
-    }
-    
-    if confirm_step("Create Development Workspace") {
-        create_workspace();
-    }
-    
-    println!("{}", "\n==== Setup Complete! ====\n".blue());
-    println!("{}", "Your Mac has been set up for development.".green());
-    println!("{}", "Some changes may require a restart to take effect.".yellow());
-    println!("{}", "Enjoy your new development environment!".green());
-    
-    if confirm_restart() {
-        restart_computer();
-    }
-}
 
-fn confirm_step(step_name: &str) -> bool {
-    Confirm::with_theme(&ColorfulTheme::default())
-        .with_prompt(format!("Do you want to {}?", step_name))
-        .default(true)
-        .interact()
-        .unwrap_or(false)
+    manifest.apply_profile(&profile);
+    Ok(profile)
 }
 
-fn confirm_restart() -> bool {
-    Confirm::with_theme(&ColorfulTheme::default())
-        .with_prompt("Do you want to restart your computer now?")
-        .default(false)
-        .interact()
-        .unwrap_or(false)
+/// Steps of the setup in run order: all of them, or those the profile lists
+/// and the steps they depend on.
+fn setup_steps(profile: Option<&Profile>) -> Result<Vec<Box<dyn Step>>, SetupError> {
+    match profile {
+        Some(profile) => {
+            let listed = profile.steps.iter().filter_map(|id| steps::find(id)).collect();
+            graph::with_dependencies(listed)
+        }
+        None => graph::order(steps::registry()),
+    }
 }
 
-fn command_exists(command: &str) -> bool {
-    which(command).is_ok()
-}
+fn run_full_setup(script_handler: &ScriptHandler, profile: Option<(&str, &Profile)>) -> ExitCode {
+    say!("{}", "\n==== Running Full Setup ====\n".blue());
 
-// Implementation of step functions using ScriptHandler
-fn install_xcode_tools() {
-    let script_handler = ScriptHandler::new();
-    match script_handler.run_section("xcode") {
-        Ok(success) => {
-            if success {
-                println!("{}", "Xcode Command Line Tools installation completed".green());
-            } else {
-                println!("{}", "Xcode Command Line Tools installation failed".red());
-            }
-        },
-        Err(e) => {
-            println!("{}", format!("Error: {}", e).red());
-            
-            // Fallback to direct implementation
-            if command_exists("xcode-select") {
-                println!("{}", "✓ Xcode Command Line Tools already installed".green());
-            } else {
-                println!("{}", "Installing Xcode Command Line Tools...".cyan());
-                let _ = Command::new("xcode-select")
-                    .args(["--install"])
-                    .status();
-                println!("{}", "Xcode Command Line Tools installation triggered".green());
-                println!("Please wait for the installation to complete.");
+    script_handler.reset_journal(profile.map(|(name, _)| name));
+    let steps = match setup_steps(profile.map(|(_, profile)| profile)) {
+        Ok(steps) => steps,
+        Err(e) => return report_error(&e),
+    };
+
+    // 指定profile时直接运行其中的步骤，否则让用户勾选要运行的步骤
+    let chosen: Vec<usize> = match profile {
+        Some((name, profile)) => {
+            say!("{}", format!("Using profile {}: {}", name, profile.description).cyan());
+            (0..steps.len()).collect()
+        }
+        None => {
+            let titles: Vec<&str> = steps.iter().map(|step| step.title()).collect();
+            match script_handler.choose("Select the steps to run", &titles, &vec![true; titles.len()]) {
+                Ok(chosen) => chosen,
+                Err(e) => return report_error(&e),
             }
         }
+    };
+
+    let mut exit_code = ExitCode::Success;
+    let mut selected: Vec<&dyn Step> = Vec::new();
+    for (index, step) in steps.iter().enumerate() {
+        if chosen.contains(&index) {
+            selected.push(step.as_ref());
+        } else {
+            script_handler.skip_section(step.as_ref());
+            exit_code = exit_code.max(ExitCode::Skipped);
+        }
     }
+
+    // 各步骤依次运行，软件包最后一起安装
+    let results = script_handler.run_sections(&selected);
+    for (step, result) in selected.iter().zip(results) {
+        exit_code = exit_code.max(step_exit_code(*step, result));
+    }
+
+    exit_code.max(finish_setup(script_handler))
 }
 
-fn install_homebrew() {
-    let script_handler = ScriptHandler::new();
-    match script_handler.run_section("brew") {
-        Ok(success) => {
-            if success {
-                println!("{}", "Homebrew installation completed".green());
-            } else {
-                println!("{}", "Homebrew installation failed".red());
-            }
-        },
-        Err(e) => {
-            println!("{}", format!("Error: {}", e).red());
-            
-            // Fallback to direct implementation
-            if command_exists("brew") {
-                println!("{}", "✓ Homebrew already installed".green());
-                
-                let _ = Command::new("brew")
-                    .arg("update")
-                    .status();
-                println!("{}", "Homebrew updated".green());
-            } else {
-                println!("{}", "Installing Homebrew...".cyan());
-                let brew_install_script = "/bin/bash -c \"$(curl -fsSL https://raw.githubusercontent.com/Homebrew/install/HEAD/install.sh)\"";
-                let _ = Command::new("bash")
-                    .args(["-c", brew_install_script])
-                    .status();
-                println!("{}", "Homebrew installed".green());
+fn resume_setup(script_handler: &ScriptHandler, profile: Option<(&str, &Profile)>) -> ExitCode {
+    let steps = match setup_steps(profile.map(|(_, profile)| profile)) {
+        Ok(steps) => steps,
+        Err(e) => return report_error(&e),
+    };
+
+    if steps.iter().all(|step| script_handler.journal_status(step.id()).is_none()) {
+        say!("{}", "No previous setup to resume. Run `macdevkit setup` to start one.".yellow());
+        return ExitCode::Success;
+    }
+
+    say!("{}", "\n==== Resuming Setup ====\n".blue());
+
+    let mut exit_code = ExitCode::Success;
+    for step in steps {
+        match script_handler.journal_status(step.id()) {
+            Some(status) if status.is_complete() => {
+                say!("{}", format!("✓ {} ({})", step.title(), status).green());
+                if status == StepStatus::Skipped {
+                    exit_code = exit_code.max(ExitCode::Skipped);
+                }
             }
+            _ => match confirm_step(script_handler, step.as_ref()) {
+                Ok(code) => exit_code = exit_code.max(code),
+                Err(e) => return report_error(&e),
+            },
         }
     }
+
+    exit_code.max(finish_setup(script_handler))
 }
 
-fn install_git() {
-    let script_handler = ScriptHandler::new();
-    if let Err(e) = script_handler.run_section("git") {
-        println!("{}", format!("Error: {}", e).red());
-        
-        // Fallback implementation
-        if command_exists("git") {
-            println!("{}", "✓ Git already installed".green());
-        } else {
-            println!("{}", "Installing Git...".cyan());
-            let _ = Command::new("brew")
-                .args(["install", "git"])
-                .status();
-            println!("{}", "Git installed".green());
-        }
-        
-        // Configure Git if not already
-        let username_output = Command::new("git")
-            .args(["config", "--global", "user.name"])
-            .output();
-        
-        // 检查是否有输出，而不是使用 unwrap_or_else 直接创建 Output
-        let has_username = username_output.is_ok() && 
-            !username_output.as_ref().unwrap().stdout.is_empty();
-        
-        if !has_username {
-            // Let user enter git configuration
-            let git_username = dialoguer::Input::<String>::new()
-                .with_prompt("Enter your Git username")
-                .interact()
-                .unwrap_or_else(|_| String::from(""));
-                
-            let _ = Command::new("git")
-                .args(["config", "--global", "user.name", &git_username])
-                .status();
-                
-            let git_email = dialoguer::Input::<String>::new()
-                .with_prompt("Enter your Git email")
-                .interact()
-                .unwrap_or_else(|_| String::from(""));
-                
-            let _ = Command::new("git")
-                .args(["config", "--global", "user.email", &git_email])
-                .status();
-                
-            // Set some sensible Git defaults
-            let _ = Command::new("git")
-                .args(["config", "--global", "init.defaultBranch", "main"])
-                .status();
-                
-            let _ = Command::new("git")
-                .args(["config", "--global", "core.editor", "code --wait"])
-                .status();
-                
-            let _ = Command::new("git")
-                .args(["config", "--global", "pull.rebase", "false"])
-                .status();
-                
-            println!("{}", "Git configured".green());
-        } else {
-            println!("{}", "✓ Git already configured".green());
+fn finish_setup(script_handler: &ScriptHandler) -> ExitCode {
+    say!("{}", "\n==== Setup Complete! ====\n".blue());
+    say!("{}", "Your Mac has been set up for development.".green());
+    say!("{}", "Some changes may require a restart to take effect.".yellow());
+    say!("{}", "Enjoy your new development environment!".green());
+
+    let answer = script_handler.restart_answer();
+    match script_handler.confirm("Do you want to restart your computer now?", false, answer) {
+        Ok(true) => {
+            restart_computer(script_handler);
+            ExitCode::Success
         }
+        Ok(false) => ExitCode::Success,
+        Err(e) => report_error(&e),
     }
 }
 
-// Implement the remaining functions using the ScriptHandler
-fn generate_ssh_key() {
-    let script_handler = ScriptHandler::new();
-    if let Err(e) = script_handler.run_section("ssh") {
-        println!("{}", format!("Error: {}", e).red());
-        // Fallback implementation would go here
+/// Asks whether to run `step`, then runs or skips it. An error means the
+/// question could not be answered and the setup should stop.
+fn confirm_step(script_handler: &ScriptHandler, step: &dyn Step) -> Result<ExitCode, SetupError> {
+    if script_handler.confirm(&format!("Do you want to {}?", step.title()), true, None)? {
+        Ok(run_step(script_handler, step))
+    } else {
+        script_handler.skip_section(step);
+        Ok(ExitCode::Skipped)
     }
 }
 
-fn install_vscode() {
-    let script_handler = ScriptHandler::new();
-    if let Err(e) = script_handler.run_section("vscode") {
-        println!("{}", format!("Error: {}", e).red());
-        // Fallback implementation would go here
+fn run_step_by_name(script_handler: &ScriptHandler, name: &str, matches: &ArgMatches) -> ExitCode {
+    let Some(step) = steps::find(name) else {
+        say!("{}", format!("Unknown step: {}", name).red());
+        return ExitCode::Usage;
+    };
+
+    match matches.subcommand() {
+        Some((subcommand, sub_matches)) => {
+            match script_handler.run_subcommand(step.as_ref(), subcommand, sub_matches) {
+                Ok(true) => ExitCode::Success,
+                Ok(false) => {
+                    say!("{}", format!("{} {} failed", step.id(), subcommand).red());
+                    ExitCode::Failed
+                }
+                Err(e) => report_error(&e),
+            }
+        }
+        None => run_with_prerequisites(script_handler, step.as_ref()),
     }
 }
 
-fn install_node() {
-    let script_handler = ScriptHandler::new();
-    if let Err(e) = script_handler.run_section("node") {
-        println!("{}", format!("Error: {}", e).red());
-        // Fallback implementation would go here
+/// Runs `step`, first offering to run the steps it depends on that are not
+/// set up yet. A dry run includes them in the plan without asking.
+fn run_with_prerequisites(script_handler: &ScriptHandler, step: &dyn Step) -> ExitCode {
+    let missing: Vec<Box<dyn Step>> = match graph::prerequisites(step) {
+        Ok(prerequisites) => prerequisites.into_iter().filter(|p| !p.check(script_handler)).collect(),
+        Err(e) => return report_error(&e),
+    };
+
+    if !missing.is_empty() {
+        let ids: Vec<&str> = missing.iter().map(|p| p.id()).collect();
+        let prompt = format!("{} needs {}, which is not set up yet. Run it first?", step.id(), ids.join(", "));
+        let answer = script_handler.planner().map(|_| true);
+        match script_handler.confirm(&prompt, true, answer) {
+            Ok(true) => {
+                for prerequisite in missing.iter() {
+                    let code = run_step(script_handler, prerequisite.as_ref());
+                    if code != ExitCode::Success {
+                        say!("{}", format!("Not running {} because {} did not complete", step.id(), prerequisite.id()).red());
+                        return code;
+                    }
+                }
+            }
+            Ok(false) => {}
+            Err(e) => return report_error(&e),
+        }
     }
+
+    run_step(script_handler, step)
 }
 
-fn install_iterm() {
-    let script_handler = ScriptHandler::new();
-    if let Err(e) = script_handler.run_section("iterm") {
-        println!("{}", format!("Error: {}", e).red());
-        // Fallback implementation would go here
+fn print_graph(format: OutputFormat) -> ExitCode {
+    let nodes = match graph::nodes() {
+        Ok(nodes) => nodes,
+        Err(e) => return report_error(&e),
+    };
+
+    match format {
+        OutputFormat::Text => print!("{}", graph::render(&nodes)),
+        OutputFormat::Json => match serde_json::to_string_pretty(&nodes) {
+            Ok(json) => println!("{}", json),
+            Err(e) => say!("{}", format!("Error: {}", e).red()),
+        },
     }
+    ExitCode::Success
 }
 
-fn install_oh_my_zsh() {
-    let script_handler = ScriptHandler::new();
-    if let Err(e) = script_handler.run_section("zsh") {
-        println!("{}", format!("Error: {}", e).red());
-        // Fallback implementation would go here
-    }
+fn run_step(script_handler: &ScriptHandler, step: &dyn Step) -> ExitCode {
+    step_exit_code(step, script_handler.run_section(step))
 }
 
-fn install_docker() {
-    let script_handler = ScriptHandler::new();
-    if let Err(e) = script_handler.run_section("docker") {
-        println!("{}", format!("Error: {}", e).red());
-        // Fallback implementation would go here
+fn step_exit_code(step: &dyn Step, result: Result<bool, SetupError>) -> ExitCode {
+    match result {
+        Ok(true) => ExitCode::Success,
+        Ok(false) => {
+            say!("{}", format!("{} failed", step.title()).red());
+            ExitCode::Failed
+        }
+        Err(e) => report_error(&e),
     }
 }
 
-fn install_dev_tools() {
-    let script_handler = ScriptHandler::new();
-    if let Err(e) = script_handler.run_section("devtools") {
-        println!("{}", format!("Error: {}", e).red());
-        // Fallback implementation would go here
+fn undo_step(script_handler: &ScriptHandler, name: &str) -> ExitCode {
+    let Some(step) = steps::find(name) else {
+        say!("{}", format!("Unknown step: {}", name).red());
+        return ExitCode::Usage;
+    };
+
+    match script_handler.undo_section(step.as_ref()) {
+        Ok(true) => {
+            say!("{}", format!("{} undone", step.title()).green());
+            ExitCode::Success
+        }
+        Ok(false) => {
+            say!("{}", format!("Some changes of {} could not be reverted", step.id()).red());
+            ExitCode::Failed
+        }
+        Err(e) => report_error(&e),
     }
 }
 
-fn install_apps() {
-    let script_handler = ScriptHandler::new();
-    if let Err(e) = script_handler.run_section("apps") {
-        println!("{}", format!("Error: {}", e).red());
-        // Fallback implementation would go here
+fn run_doctor(script_handler: &ScriptHandler, format: OutputFormat) -> ExitCode {
+    say!("{}", "\n==== Checking your development environment ====\n".blue());
+    say!("{}", script_handler.platform().to_string().dimmed());
+
+    let findings = doctor::examine(script_handler, &steps::registry());
+    match format {
+        OutputFormat::Text => print!("{}", doctor::render_table(&findings)),
+        OutputFormat::Json => match serde_json::to_string_pretty(&findings) {
+            Ok(json) => println!("{}", json),
+            Err(e) => say!("{}", format!("Error: {}", e).red()),
+        },
     }
-}
 
-fn configure_macos() {
-    let script_handler = ScriptHandler::new();
-    if let Err(e) = script_handler.run_section("macos") {
-        println!("{}", format!("Error: {}", e).red());
-        // Fallback implementation would go here
+    if doctor::has_failures(&findings) {
+        ExitCode::Failed
+    } else {
+        ExitCode::Success
     }
 }
 
-fn create_workspace() {
-    let script_handler = ScriptHandler::new();
-    if let Err(e) = script_handler.run_section("workspace") {
-        println!("{}", format!("Error: {}", e).red());
-        
-        // Fallback implementation
-        let home_dir = std::env::var("HOME").unwrap_or_else(|_| String::from("~"));
-        let _ = Command::new("mkdir")
-            .args(["-p", &format!("{}/Workspace", home_dir)])
-            .status();
-        println!("{}", "Created ~/Workspace directory".green());
-    }
+fn report_error(error: &SetupError) -> ExitCode {
+    say!("{}", format!("Error: {}", error).red());
+    error.exit_code()
 }
 
-fn restart_computer() {
-    println!("{}", "Restarting your computer now...".cyan());
-    let _ = Command::new("sudo")
-        .args(["shutdown", "-r", "now"])
-        .status();
-} 
\ No newline at end of file
+fn restart_computer(script_handler: &ScriptHandler) {
+    say!("{}", "Restarting your computer now...".cyan());
+    let _ = script_handler.run("sudo", &["shutdown", "-r", "now"]);
+}
@@ -0,0 +1,149 @@
+//! Sections of config files that MacDevKit owns, delimited by marker lines:
+//!
+//! ```text
+//! # >>> macdevkit mirror >>>
+//! registry=https://registry.npmmirror.com
+//! # <<< macdevkit mirror <<<
+//! ```
+//!
+//! Each block is named after the concern it configures, so a file can hold
+//! several. Rewriting a block replaces it in place instead of appending
+//! another copy, and everything outside the markers is left untouched.
+
+fn begin_marker(name: &str) -> String {
+    format!("# >>> macdevkit {} >>>", name)
+}
+
+fn end_marker(name: &str) -> String {
+    format!("# <<< macdevkit {} <<<", name)
+}
+
+/// Byte range of block `name` in `text`, from its begin marker up to and
+/// including the newline after its end marker.
+fn span(text: &str, name: &str) -> Option<(usize, usize)> {
+    let begin = begin_marker(name);
+    let end = end_marker(name);
+
+    let mut offset = 0;
+    let mut start = None;
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        match start {
+            None if trimmed == begin => start = Some(offset),
+            Some(start) if trimmed == end => return Some((start, offset + line.len())),
+            _ => {}
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// Content of block `name`, without the markers.
+pub fn find<'a>(text: &'a str, name: &str) -> Option<&'a str> {
+    let (start, end) = span(text, name)?;
+    let block = &text[start..end];
+    let body_start = block.find('\n')? + 1;
+    let body_end = block.trim_end_matches('\n').rfind('\n').unwrap_or(0) + 1;
+    Some(block.get(body_start..body_end).unwrap_or("").trim_end_matches('\n'))
+}
+
+/// `text` with block `name` set to `body`: replaced where it is, or added
+/// at the end of the file if it is not there yet.
+pub fn upsert(text: &str, name: &str, body: &str) -> String {
+    let block = format!("{}\n{}\n{}\n", begin_marker(name), body.trim_end_matches('\n'), end_marker(name));
+
+    match span(text, name) {
+        Some((start, end)) => format!("{}{}{}", &text[..start], block, &text[end..]),
+        None if text.is_empty() => block,
+        None if text.ends_with('\n') => format!("{}\n{}", text, block),
+        None => format!("{}\n\n{}", text, block),
+    }
+}
+
+/// Like [`upsert`], but a new block goes at the start of the file, for
+/// files where the first setting wins such as `~/.ssh/config`.
+pub fn upsert_first(text: &str, name: &str, body: &str) -> String {
+    let block = format!("{}\n{}\n{}\n", begin_marker(name), body.trim_end_matches('\n'), end_marker(name));
+
+    match span(text, name) {
+        Some((start, end)) => format!("{}{}{}", &text[..start], block, &text[end..]),
+        None if text.is_empty() => block,
+        None => format!("{}\n{}", block, text),
+    }
+}
+
+/// `text` without block `name`.
+pub fn remove(text: &str, name: &str) -> String {
+    match span(text, name) {
+        // 去掉 upsert_first 在块后补的空行
+        Some((0, end)) => text[end..].strip_prefix('\n').unwrap_or(&text[end..]).to_string(),
+        Some((start, end)) => {
+            // 去掉 upsert 在块前补的空行
+            let before = text[..start].strip_suffix("\n\n").map_or(&text[..start], |before| &text[..before.len() + 1]);
+            format!("{}{}", before, &text[end..])
+        }
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLOCK: &str = "# >>> macdevkit mirror >>>\nregistry=a\n# <<< macdevkit mirror <<<\n";
+
+    #[test]
+    fn upsert_adds_the_block_at_the_end() {
+        assert_eq!(upsert("", "mirror", "registry=a"), BLOCK);
+        assert_eq!(upsert("save-exact=true\n", "mirror", "registry=a\n"), format!("save-exact=true\n\n{}", BLOCK));
+        assert_eq!(upsert("save-exact=true", "mirror", "registry=a"), format!("save-exact=true\n\n{}", BLOCK));
+    }
+
+    #[test]
+    fn upsert_replaces_the_block_in_place() {
+        let text = format!("before\n{}after\n", BLOCK);
+        let updated = upsert(&text, "mirror", "registry=b");
+        assert_eq!(updated, "before\n# >>> macdevkit mirror >>>\nregistry=b\n# <<< macdevkit mirror <<<\nafter\n");
+        assert_eq!(upsert(&updated, "mirror", "registry=b"), updated);
+    }
+
+    #[test]
+    fn upsert_first_adds_the_block_at_the_start() {
+        assert_eq!(upsert_first("", "mirror", "registry=a"), BLOCK);
+        assert_eq!(upsert_first("Host *\n", "mirror", "registry=a"), format!("{}\nHost *\n", BLOCK));
+
+        let text = format!("Host *\n{}", BLOCK);
+        assert_eq!(upsert_first(&text, "mirror", "registry=b"), text.replace("registry=a", "registry=b"));
+    }
+
+    #[test]
+    fn find_returns_the_body() {
+        let text = format!("before\n{}", upsert("", "other", "x=1\ny=2"));
+        let text = upsert(&text, "mirror", "registry=a");
+        assert_eq!(find(&text, "mirror"), Some("registry=a"));
+        assert_eq!(find(&text, "other"), Some("x=1\ny=2"));
+        assert_eq!(find(&text, "missing"), None);
+        assert_eq!(find(&upsert("", "empty", ""), "empty"), Some(""));
+    }
+
+    #[test]
+    fn remove_undoes_upsert() {
+        for original in ["", "save-exact=true\n", "save-exact=true"] {
+            let with_block = upsert(original, "mirror", "registry=a");
+            let expected = if original.ends_with('\n') || original.is_empty() {
+                original.to_string()
+            } else {
+                format!("{}\n", original)
+            };
+            assert_eq!(remove(&with_block, "mirror"), expected);
+        }
+        assert_eq!(remove(&upsert_first("Host *\n", "mirror", "registry=a"), "mirror"), "Host *\n");
+        assert_eq!(remove("untouched\n", "mirror"), "untouched\n");
+    }
+
+    #[test]
+    fn remove_keeps_other_blocks() {
+        let text = upsert(&upsert("", "one", "1"), "two", "2");
+        assert_eq!(remove(&text, "one"), upsert("", "two", "2"));
+    }
+}
@@ -0,0 +1,319 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::git::GitSettings;
+use crate::mirror::MirrorSettings;
+use crate::node::NodeSettings;
+use crate::profile::Profile;
+use crate::ssh::SshSettings;
+
+/// Declarative description of the machine, loaded from `macdevkit.toml`.
+///
+/// Every table is optional; a table missing from the file keeps the
+/// built-in defaults below.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Manifest {
+    pub brew: BrewPackages,
+    pub git: GitSettings,
+    pub mirror: MirrorSettings,
+    pub vscode: VscodeSettings,
+    pub node: NodeSettings,
+    pub npm: NpmSettings,
+    pub ssh: SshSettings,
+    pub zsh: ZshSettings,
+    pub defaults: Vec<DefaultsSetting>,
+    pub workspace: WorkspaceSettings,
+    /// User-defined setup profiles, in addition to the built-in ones.
+    pub profiles: BTreeMap<String, Profile>,
+
+    /// File the manifest was read from, `None` for the built-in defaults.
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BrewPackages {
+    pub taps: Vec<String>,
+    pub formulae: Vec<String>,
+    pub casks: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VscodeSettings {
+    pub extensions: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NpmSettings {
+    pub globals: Vec<String>,
+}
+
+/// Oh My Zsh theme and plugins. Themes and plugins that do not ship with
+/// Oh My Zsh, such as `powerlevel10k` or `zsh-autosuggestions`, are cloned
+/// into `$ZSH_CUSTOM`.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ZshSettings {
+    pub theme: String,
+    pub plugins: Vec<String>,
+}
+
+/// A single `defaults write` entry. The value type (`-bool`, `-int`,
+/// `-float` or `-string`) follows the TOML type of `value`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DefaultsSetting {
+    pub domain: String,
+    pub key: String,
+    pub value: toml::Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkspaceSettings {
+    pub dirs: Vec<String>,
+}
+
+fn strings(items: &[&str]) -> Vec<String> {
+    items.iter().map(|item| item.to_string()).collect()
+}
+
+impl Default for BrewPackages {
+    fn default() -> Self {
+        BrewPackages {
+            taps: Vec::new(),
+            formulae: strings(&[
+                "cmake", "ninja", "jq", "ripgrep", "fd", "bat", "exa", "htop", "tldr", "fzf", "tmux", "tree", "wget",
+                "httpie", "gh",
+            ]),
+            casks: strings(&[
+                "google-chrome",
+                "firefox",
+                "slack",
+                "postman",
+                "rectangle",
+                "alfred",
+                "notion",
+                "obsidian",
+                "figma",
+            ]),
+        }
+    }
+}
+
+impl Default for VscodeSettings {
+    fn default() -> Self {
+        VscodeSettings {
+            extensions: strings(&[
+                "ms-vscode.vscode-typescript-next",
+                "dbaeumer.vscode-eslint",
+                "esbenp.prettier-vscode",
+                "ms-python.python",
+                "ms-azuretools.vscode-docker",
+                "github.copilot",
+                "eamodio.gitlens",
+                "ms-vscode-remote.remote-containers",
+                "ritwickdey.liveserver",
+                "streetsidesoftware.code-spell-checker",
+            ]),
+        }
+    }
+}
+
+impl Default for NpmSettings {
+    fn default() -> Self {
+        NpmSettings {
+            globals: strings(&[
+                "yarn",
+                "typescript",
+                "ts-node",
+                "nodemon",
+                "http-server",
+                "eslint",
+                "prettier",
+            ]),
+        }
+    }
+}
+
+impl Default for ZshSettings {
+    fn default() -> Self {
+        ZshSettings {
+            theme: "powerlevel10k".to_string(),
+            plugins: strings(&["git", "zsh-autosuggestions", "zsh-syntax-highlighting"]),
+        }
+    }
+}
+
+impl Default for WorkspaceSettings {
+    fn default() -> Self {
+        WorkspaceSettings {
+            dirs: strings(&["~/Workspace"]),
+        }
+    }
+}
+
+impl DefaultsSetting {
+    fn new(domain: &str, key: &str, value: toml::Value) -> Self {
+        DefaultsSetting {
+            domain: domain.to_string(),
+            key: key.to_string(),
+            value,
+        }
+    }
+
+    /// Arguments for `defaults write`, e.g. `["-bool", "true"]`.
+    pub fn write_args(&self) -> Result<[String; 2], String> {
+        match &self.value {
+            toml::Value::Boolean(b) => Ok(["-bool".to_string(), b.to_string()]),
+            toml::Value::Integer(i) => Ok(["-int".to_string(), i.to_string()]),
+            toml::Value::Float(f) => Ok(["-float".to_string(), f.to_string()]),
+            toml::Value::String(s) => Ok(["-string".to_string(), s.clone()]),
+            other => Err(format!(
+                "Unsupported value type '{}' for defaults {} {}",
+                other.type_str(),
+                self.domain,
+                self.key
+            )),
+        }
+    }
+}
+
+impl Default for Manifest {
+    fn default() -> Self {
+        use toml::Value::{Boolean, Integer, String};
+
+        Manifest {
+            brew: BrewPackages::default(),
+            git: GitSettings::default(),
+            mirror: MirrorSettings::default(),
+            vscode: VscodeSettings::default(),
+            node: NodeSettings::default(),
+            npm: NpmSettings::default(),
+            ssh: SshSettings::default(),
+            zsh: ZshSettings::default(),
+            defaults: vec![
+                DefaultsSetting::new("com.apple.finder", "AppleShowAllFiles", Boolean(true)),
+                DefaultsSetting::new("com.apple.finder", "ShowPathbar", Boolean(true)),
+                DefaultsSetting::new("com.apple.finder", "ShowStatusBar", Boolean(true)),
+                DefaultsSetting::new("NSGlobalDomain", "ApplePressAndHoldEnabled", Boolean(false)),
+                DefaultsSetting::new("NSGlobalDomain", "KeyRepeat", Integer(2)),
+                DefaultsSetting::new("NSGlobalDomain", "InitialKeyRepeat", Integer(15)),
+                DefaultsSetting::new("NSGlobalDomain", "NSAutomaticSpellingCorrectionEnabled", Boolean(false)),
+                DefaultsSetting::new("com.apple.screensaver", "askForPassword", Integer(1)),
+                DefaultsSetting::new("com.apple.screensaver", "askForPasswordDelay", Integer(0)),
+                DefaultsSetting::new("com.apple.screencapture", "location", String("~/Desktop/Screenshots".into())),
+                DefaultsSetting::new("com.apple.screencapture", "type", String("png".into())),
+            ],
+            workspace: WorkspaceSettings::default(),
+            profiles: BTreeMap::new(),
+            source: None,
+        }
+    }
+}
+
+impl Manifest {
+    /// Loads the manifest from `path`, or from the default location when no
+    /// path is given. Without a manifest file the built-in defaults are used.
+    pub fn load(path: Option<&Path>) -> Result<Self, String> {
+        match path {
+            Some(path) => Self::from_file(path),
+            None => match default_path() {
+                Some(path) if path.exists() => Self::from_file(&path),
+                _ => Ok(Manifest::default()),
+            },
+        }
+    }
+
+    fn from_file(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+        let mut manifest: Manifest = toml::from_str(&content)
+            .map_err(|e| format!("Invalid manifest {}: {}", path.display(), e))?;
+
+        manifest.source = Some(path.to_path_buf());
+        Ok(manifest)
+    }
+}
+
+/// `$XDG_CONFIG_HOME/macdevkit/macdevkit.toml`, falling back to `~/.config`.
+pub fn default_path() -> Option<PathBuf> {
+    let config_dir = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_dir.join("macdevkit").join("macdevkit.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempHome;
+
+    const SAMPLE: &str = r#"
+[brew]
+taps = ["hashicorp/tap"]
+formulae = ["jq", "hashicorp/tap/terraform"]
+
+[git]
+ignore = [".DS_Store", "node_modules/"]
+
+[git.config]
+"pull.rebase" = "true"
+
+[git.identities.work]
+name = "Jane Doe"
+email = "jane@company.com"
+dirs = ["~/Workspace/work/"]
+
+[[defaults]]
+domain = "com.apple.dock"
+key = "autohide"
+value = true
+
+[profiles.infra]
+description = "Terraform and Kubernetes"
+steps = ["brew", "devtools"]
+formulae = ["kubectl"]
+"#;
+
+    fn load(home: &TempHome, content: &str) -> Result<Manifest, String> {
+        let path = home.path.join("macdevkit.toml");
+        fs::write(&path, content).unwrap();
+        Manifest::load(Some(&path))
+    }
+
+    #[test]
+    fn parses_sample_and_keeps_defaults_for_missing_tables() {
+        let home = TempHome::new("manifest-sample");
+        let manifest = load(&home, SAMPLE).unwrap();
+
+        assert_eq!(manifest.brew.taps, ["hashicorp/tap"]);
+        assert_eq!(manifest.brew.formulae, ["jq", "hashicorp/tap/terraform"]);
+        assert_eq!(manifest.brew.casks, BrewPackages::default().casks);
+        assert_eq!(manifest.git.ignore, [".DS_Store", "node_modules/"]);
+        assert_eq!(manifest.git.config.unwrap()["pull.rebase"], "true");
+        assert_eq!(manifest.git.identities["work"].dirs, ["~/Workspace/work/"]);
+        assert_eq!(manifest.defaults.len(), 1);
+        assert_eq!(manifest.defaults[0].write_args().unwrap(), ["-bool", "true"]);
+        assert_eq!(manifest.profiles["infra"].formulae.as_deref(), Some(&["kubectl".to_string()][..]));
+        assert_eq!(manifest.zsh.theme, "powerlevel10k");
+        assert_eq!(manifest.source, Some(home.path.join("macdevkit.toml")));
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        let home = TempHome::new("manifest-unknown");
+        for content in ["[brew]\nformula = [\"jq\"]\n", "[brews]\nformulae = []\n", "[git.identities.work]\nname = \"a\"\nemail = \"b\"\ndirs = []\nmail = \"c\"\n"] {
+            let error = load(&home, content).unwrap_err();
+            assert!(error.contains("unknown field"), "{}", error);
+        }
+    }
+}
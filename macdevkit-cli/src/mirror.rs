@@ -0,0 +1,187 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::shell_profile::{ProfileBlock, Stage};
+
+/// Mirrors for networks where GitHub, ghcr.io and the public package
+/// registries are slow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum MirrorName {
+    /// Tsinghua University (mirrors.tuna.tsinghua.edu.cn)
+    Tuna,
+    /// University of Science and Technology of China (mirrors.ustc.edu.cn)
+    Ustc,
+    /// The URLs given in the `[mirror]` table of the manifest
+    Custom,
+}
+
+impl MirrorName {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MirrorName::Tuna => "tuna",
+            MirrorName::Ustc => "ustc",
+            MirrorName::Custom => "custom",
+        }
+    }
+}
+
+/// `[mirror]` table of the manifest.
+///
+/// For `tuna` and `ustc` any URL that is set replaces the mirror's own. With
+/// `name = "custom"` either all four Homebrew URLs or none of them must be
+/// set, and registries without a URL are left alone.
+///
+/// `github_proxy` replaces `https://github.com/` in Git URLs; no preset sets it.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MirrorSettings {
+    pub name: Option<MirrorName>,
+    /// Also export the mirror from the shell profiles for later shells.
+    pub persist: bool,
+    pub brew_git_remote: Option<String>,
+    pub core_git_remote: Option<String>,
+    pub bottle_domain: Option<String>,
+    pub api_domain: Option<String>,
+    pub npm_registry: Option<String>,
+    pub pypi_index: Option<String>,
+    pub goproxy: Option<String>,
+    pub crates_index: Option<String>,
+    pub github_proxy: Option<String>,
+}
+
+/// The environment Homebrew reads to fetch from a mirror instead of GitHub.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrewMirror {
+    pub name: MirrorName,
+    pub brew_git_remote: String,
+    pub core_git_remote: String,
+    pub bottle_domain: String,
+    pub api_domain: String,
+}
+
+impl BrewMirror {
+    /// The mirror selected by `settings`, or `None` to use GitHub directly.
+    pub fn from_settings(settings: &MirrorSettings) -> Result<Option<Self>, String> {
+        let Some(name) = settings.name else {
+            return Ok(None);
+        };
+
+        let preset = match name {
+            MirrorName::Tuna => Some([
+                "https://mirrors.tuna.tsinghua.edu.cn/git/homebrew/brew.git",
+                "https://mirrors.tuna.tsinghua.edu.cn/git/homebrew/homebrew-core.git",
+                "https://mirrors.tuna.tsinghua.edu.cn/homebrew-bottles",
+                "https://mirrors.tuna.tsinghua.edu.cn/homebrew-bottles/api",
+            ]),
+            MirrorName::Ustc => Some([
+                "https://mirrors.ustc.edu.cn/brew.git",
+                "https://mirrors.ustc.edu.cn/homebrew-core.git",
+                "https://mirrors.ustc.edu.cn/homebrew-bottles",
+                "https://mirrors.ustc.edu.cn/homebrew-bottles/api",
+            ]),
+            MirrorName::Custom => None,
+        };
+
+        let brew_urls = [
+            &settings.brew_git_remote,
+            &settings.core_git_remote,
+            &settings.bottle_domain,
+            &settings.api_domain,
+        ];
+        if preset.is_none() && brew_urls.iter().all(|url| url.is_none()) {
+            return Ok(None);
+        }
+
+        let url = |value: &Option<String>, key: &str, index: usize| -> Result<String, String> {
+            match (value, preset) {
+                (Some(value), _) => Ok(value.clone()),
+                (None, Some(preset)) => Ok(preset[index].to_string()),
+                (None, None) => Err(format!("The custom mirror needs `mirror.{}` in the manifest", key)),
+            }
+        };
+
+        Ok(Some(BrewMirror {
+            name,
+            brew_git_remote: url(&settings.brew_git_remote, "brew_git_remote", 0)?,
+            core_git_remote: url(&settings.core_git_remote, "core_git_remote", 1)?,
+            bottle_domain: url(&settings.bottle_domain, "bottle_domain", 2)?,
+            api_domain: url(&settings.api_domain, "api_domain", 3)?,
+        }))
+    }
+
+    /// Environment variables that point Homebrew at the mirror.
+    pub fn env(&self) -> [(&'static str, &str); 4] {
+        [
+            ("HOMEBREW_BREW_GIT_REMOTE", &self.brew_git_remote),
+            ("HOMEBREW_CORE_GIT_REMOTE", &self.core_git_remote),
+            ("HOMEBREW_BOTTLE_DOMAIN", &self.bottle_domain),
+            ("HOMEBREW_API_DOMAIN", &self.api_domain),
+        ]
+    }
+
+    /// Sets [`BrewMirror::env`] for this process, so every `brew` command
+    /// started afterwards, including the Homebrew installer, uses the mirror.
+    pub fn export(&self) {
+        for (key, value) in self.env() {
+            std::env::set_var(key, value);
+        }
+    }
+
+    /// Shell profile block that exports [`BrewMirror::env`].
+    pub fn profile_block(&self) -> ProfileBlock {
+        let mut posix = format!("# Homebrew mirror ({})", self.name.as_str());
+        let mut fish = posix.clone();
+        for (key, value) in self.env() {
+            posix.push_str(&format!("\nexport {}=\"{}\"", key, value));
+            fish.push_str(&format!("\nset -gx {} \"{}\"", key, value));
+        }
+        ProfileBlock::new("homebrew-mirror", Stage::Login, posix).fish(fish)
+    }
+}
+
+/// Package registries to point at a mirror; `None` leaves a registry alone.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Registries {
+    /// `registry` in `~/.npmrc`.
+    pub npm: Option<String>,
+    /// `index-url` in `pip.conf`.
+    pub pypi: Option<String>,
+    /// `GOPROXY`, set with `go env -w`.
+    pub goproxy: Option<String>,
+    /// Replacement for crates.io in `~/.cargo/config.toml`.
+    pub crates: Option<String>,
+    /// Git `insteadOf` base for `https://github.com/`.
+    pub github: Option<String>,
+}
+
+impl Registries {
+    pub fn new(name: MirrorName, settings: &MirrorSettings) -> Self {
+        // npm 和 Go 没有高校镜像，统一使用 npmmirror 和 goproxy.cn
+        let preset = match name {
+            MirrorName::Tuna => Registries {
+                npm: Some("https://registry.npmmirror.com".to_string()),
+                pypi: Some("https://pypi.tuna.tsinghua.edu.cn/simple".to_string()),
+                goproxy: Some("https://goproxy.cn,direct".to_string()),
+                crates: Some("sparse+https://mirrors.tuna.tsinghua.edu.cn/crates.io-index/".to_string()),
+                github: None,
+            },
+            MirrorName::Ustc => Registries {
+                npm: Some("https://registry.npmmirror.com".to_string()),
+                pypi: Some("https://mirrors.ustc.edu.cn/pypi/simple".to_string()),
+                goproxy: Some("https://goproxy.cn,direct".to_string()),
+                crates: Some("sparse+https://mirrors.ustc.edu.cn/crates.io-index/".to_string()),
+                github: None,
+            },
+            MirrorName::Custom => Registries::default(),
+        };
+
+        Registries {
+            npm: settings.npm_registry.clone().or(preset.npm),
+            pypi: settings.pypi_index.clone().or(preset.pypi),
+            goproxy: settings.goproxy.clone().or(preset.goproxy),
+            crates: settings.crates_index.clone().or(preset.crates),
+            github: settings.github_proxy.clone().or(preset.github),
+        }
+    }
+}
@@ -0,0 +1,253 @@
+//! Node.js version managers and the global npm packages installed with them.
+//!
+//! Each manager is installed with Homebrew and loaded from the shell
+//! profiles. Commands that need `node` or `npm` run in `bash` after a
+//! [prelude](NodeManager::prelude) that puts the manager's default Node.js
+//! on `PATH`, since the profiles are not read by non-interactive shells.
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+use crate::shell_profile::{ProfileBlock, Stage};
+
+/// Version installed when neither the manifest nor an `.nvmrc` names one.
+pub const LTS: &str = "lts";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeManager {
+    /// nvm, loaded as a shell function (zsh and bash only)
+    #[default]
+    Nvm,
+    /// fnm, which also switches versions on `cd`
+    Fnm,
+    /// Volta, which pins versions in package.json
+    Volta,
+    /// mise, which manages other languages too
+    Mise,
+}
+
+/// `[node]` table of the manifest.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NodeSettings {
+    pub manager: NodeManager,
+    /// `lts`, an LTS codename such as `lts/iron`, or a version such as `22`.
+    /// Without one, the `.nvmrc` in the current or home directory is used,
+    /// and then the latest LTS.
+    pub version: Option<String>,
+    /// Run `corepack enable`, which provides pnpm and yarn.
+    pub corepack: bool,
+}
+
+impl Default for NodeSettings {
+    fn default() -> Self {
+        NodeSettings {
+            manager: NodeManager::default(),
+            version: None,
+            corepack: true,
+        }
+    }
+}
+
+impl NodeManager {
+    pub fn name(self) -> &'static str {
+        match self {
+            NodeManager::Nvm => "nvm",
+            NodeManager::Fnm => "fnm",
+            NodeManager::Volta => "volta",
+            NodeManager::Mise => "mise",
+        }
+    }
+
+    /// Shell code that puts the default Node.js on `PATH` in `bash`.
+    /// `nvm_script` is the path of nvm.sh, used only by nvm.
+    pub fn prelude(self, nvm_script: &str) -> String {
+        match self {
+            NodeManager::Nvm => format!(r#"export NVM_DIR="${{NVM_DIR:-$HOME/.nvm}}"; . "{}""#, nvm_script),
+            NodeManager::Fnm => r#"eval "$(fnm env)""#.to_string(),
+            NodeManager::Volta => r#"export VOLTA_HOME="${VOLTA_HOME:-$HOME/.volta}"; export PATH="$VOLTA_HOME/bin:$PATH""#.to_string(),
+            NodeManager::Mise => r#"eval "$(mise env --shell bash)""#.to_string(),
+        }
+    }
+
+    /// Commands that install `version` and make it the default.
+    pub fn install_commands(self, version: &str) -> Vec<Vec<String>> {
+        let commands: Vec<Vec<&str>> = match self {
+            NodeManager::Nvm if version == LTS => vec![vec!["nvm", "install", "--lts"], vec!["nvm", "alias", "default", "lts/*"]],
+            NodeManager::Nvm => vec![vec!["nvm", "install", version], vec!["nvm", "alias", "default", version]],
+            NodeManager::Fnm if version == LTS => vec![vec!["fnm", "install", "--lts"], vec!["fnm", "default", "lts-latest"]],
+            NodeManager::Fnm => vec![vec!["fnm", "install", version], vec!["fnm", "default", version]],
+            // Volta 和 mise 不认识 LTS 代号，只能安装最新的 LTS
+            NodeManager::Volta | NodeManager::Mise => {
+                let tool = if is_lts(version) { "node@lts".to_string() } else { format!("node@{}", version) };
+                let command = match self {
+                    NodeManager::Volta => vec!["volta", "install"],
+                    _ => vec!["mise", "use", "--global"],
+                };
+                return vec![command.into_iter().map(str::to_string).chain([tool]).collect()];
+            }
+        };
+        commands
+            .into_iter()
+            .map(|command| command.into_iter().map(str::to_string).collect())
+            .collect()
+    }
+
+    /// Profile block that loads the manager in new shells.
+    pub fn profile_block(self, brew_prefix: &str) -> ProfileBlock {
+        match self {
+            NodeManager::Nvm => {
+                let nvm_script = format!("{}/opt/nvm/nvm.sh", brew_prefix);
+                let completion = format!("{}/opt/nvm/etc/bash_completion.d/nvm", brew_prefix);
+                let body = format!(
+                    "export NVM_DIR=\"$HOME/.nvm\"\n[ -s \"{0}\" ] && \\. \"{0}\"\n[ -s \"{1}\" ] && \\. \"{1}\"",
+                    nvm_script, completion
+                );
+                // nvm 不支持 fish，只写入 zsh 和 bash 的配置
+                ProfileBlock::new("nvm", Stage::Interactive, body.clone()).legacy(format!("\n{}\n", body))
+            }
+            NodeManager::Fnm => ProfileBlock::new("fnm", Stage::Interactive, r#"eval "$(fnm env --use-on-cd)""#)
+                .fish("fnm env --use-on-cd | source"),
+            NodeManager::Volta => ProfileBlock::new(
+                "volta",
+                Stage::Login,
+                "export VOLTA_HOME=\"$HOME/.volta\"\nexport PATH=\"$VOLTA_HOME/bin:$PATH\"",
+            )
+            .fish("set -gx VOLTA_HOME \"$HOME/.volta\"\nfish_add_path \"$VOLTA_HOME/bin\""),
+            // .bash_profile 和 .zshrc 共用同一段代码，按当前 shell 选择
+            NodeManager::Mise => ProfileBlock::new(
+                "mise",
+                Stage::Interactive,
+                "if [ -n \"$ZSH_VERSION\" ]; then\n  eval \"$(mise activate zsh)\"\nelse\n  eval \"$(mise activate bash)\"\nfi",
+            )
+            .fish("mise activate fish | source"),
+        }
+    }
+}
+
+/// Places nvm.sh is installed by the curl installer and by Homebrew.
+fn nvm_scripts(home: &str, brew_prefix: &str) -> Vec<String> {
+    let nvm_dir = std::env::var("NVM_DIR").unwrap_or_else(|_| format!("{}/.nvm", home));
+    vec![format!("{}/nvm.sh", nvm_dir), format!("{}/opt/nvm/nvm.sh", brew_prefix)]
+}
+
+/// The installed nvm.sh, if any.
+pub fn find_nvm(home: &str, brew_prefix: &str) -> Option<String> {
+    nvm_scripts(home, brew_prefix)
+        .into_iter()
+        .find(|script| std::path::Path::new(script).exists())
+}
+
+/// The installed nvm.sh, or where Homebrew will install it.
+pub fn nvm_script(home: &str, brew_prefix: &str) -> String {
+    find_nvm(home, brew_prefix).unwrap_or_else(|| format!("{}/opt/nvm/nvm.sh", brew_prefix))
+}
+
+/// True for `lts` and LTS codenames such as `lts/iron`.
+pub fn is_lts(version: &str) -> bool {
+    version == LTS || version == "lts/*" || version.starts_with("lts/")
+}
+
+/// The version in an `.nvmrc`, without comments; `lts/*` becomes `lts`.
+pub fn parse_nvmrc(content: &str) -> Option<String> {
+    let version = content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .find(|line| !line.is_empty())?;
+    Some(if version == "lts/*" { LTS.to_string() } else { version.to_string() })
+}
+
+/// True if `installed` (e.g. `v22.11.0`) satisfies `wanted`. Any version
+/// satisfies an LTS request, since the latest LTS is not known offline.
+pub fn version_matches(wanted: &str, installed: &str) -> bool {
+    if is_lts(wanted) || wanted == "node" {
+        return true;
+    }
+    let wanted = wanted.trim_start_matches('v');
+    let installed = installed.trim().trim_start_matches('v');
+    installed == wanted || installed.starts_with(&format!("{}.", wanted))
+}
+
+/// Package name of an npm spec such as `typescript@5` or `@vue/cli@latest`.
+pub fn package_name(spec: &str) -> &str {
+    match spec.rfind('@') {
+        Some(at) if at > 0 => &spec[..at],
+        _ => spec,
+    }
+}
+
+/// Names of the packages in `npm ls -g --json` output.
+pub fn installed_globals(json: &str) -> BTreeSet<String> {
+    serde_json::from_str::<Value>(json)
+        .ok()
+        .and_then(|value| value.get("dependencies").and_then(Value::as_object).cloned())
+        .map(|dependencies| dependencies.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Arguments for `bash` that run `command` after `prelude`.
+pub fn bash_args(prelude: &str, command: &[&str]) -> Vec<String> {
+    // $0 为占位名，其余参数为要执行的命令
+    let mut args = vec!["-c".to_string(), format!(r#"{} && "$@""#, prelude), "bash".to_string()];
+    args.extend(command.iter().map(|arg| arg.to_string()));
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands(manager: NodeManager, version: &str) -> Vec<String> {
+        manager.install_commands(version).iter().map(|command| command.join(" ")).collect()
+    }
+
+    #[test]
+    fn parses_nvmrc() {
+        assert_eq!(parse_nvmrc("22\n").as_deref(), Some("22"));
+        assert_eq!(parse_nvmrc("# pinned\n\n  v20.11.1  # for CI\n").as_deref(), Some("v20.11.1"));
+        assert_eq!(parse_nvmrc("lts/*\n").as_deref(), Some(LTS));
+        assert_eq!(parse_nvmrc("lts/iron").as_deref(), Some("lts/iron"));
+        assert_eq!(parse_nvmrc("# nothing\n\n"), None);
+    }
+
+    #[test]
+    fn matches_versions() {
+        assert!(version_matches("22", "v22.11.0\n"));
+        assert!(version_matches("v22.11", "v22.11.0"));
+        assert!(version_matches("22.11.0", "v22.11.0"));
+        assert!(!version_matches("2", "v22.11.0"));
+        assert!(!version_matches("20", "v22.11.0"));
+        assert!(version_matches(LTS, "v18.0.0"));
+        assert!(version_matches("lts/iron", "v22.11.0"));
+        assert!(version_matches("node", "v23.0.0"));
+    }
+
+    #[test]
+    fn strips_versions_from_package_specs() {
+        assert_eq!(package_name("typescript"), "typescript");
+        assert_eq!(package_name("typescript@5"), "typescript");
+        assert_eq!(package_name("@vue/cli"), "@vue/cli");
+        assert_eq!(package_name("@vue/cli@latest"), "@vue/cli");
+    }
+
+    #[test]
+    fn install_commands_per_manager() {
+        assert_eq!(commands(NodeManager::Nvm, LTS), ["nvm install --lts", "nvm alias default lts/*"]);
+        assert_eq!(commands(NodeManager::Nvm, "22"), ["nvm install 22", "nvm alias default 22"]);
+        assert_eq!(commands(NodeManager::Fnm, LTS), ["fnm install --lts", "fnm default lts-latest"]);
+        assert_eq!(commands(NodeManager::Fnm, "lts/iron"), ["fnm install lts/iron", "fnm default lts/iron"]);
+        assert_eq!(commands(NodeManager::Volta, "lts/iron"), ["volta install node@lts"]);
+        assert_eq!(commands(NodeManager::Mise, "22"), ["mise use --global node@22"]);
+    }
+
+    #[test]
+    fn reads_installed_globals() {
+        let json = r#"{"name":"lib","dependencies":{"npm":{"version":"10.9.0"},"@vue/cli":{"version":"5.0.8"}}}"#;
+        assert_eq!(installed_globals(json), BTreeSet::from(["npm".to_string(), "@vue/cli".to_string()]));
+        assert!(installed_globals(r#"{"name":"lib"}"#).is_empty());
+        assert!(installed_globals("npm ERR! not json").is_empty());
+    }
+}
@@ -0,0 +1,33 @@
+use clap::ValueEnum;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Format of the CLI's own results on stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+static MACHINE_READABLE: AtomicBool = AtomicBool::new(false);
+
+/// Sends human-readable messages to stderr so stdout only carries JSON.
+pub fn set_machine_readable(enabled: bool) {
+    MACHINE_READABLE.store(enabled, Ordering::Relaxed);
+}
+
+pub fn machine_readable() -> bool {
+    MACHINE_READABLE.load(Ordering::Relaxed)
+}
+
+/// Prints a human-readable line: stdout normally, stderr when the CLI
+/// emits machine-readable output.
+#[macro_export]
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if $crate::output::machine_readable() {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
@@ -0,0 +1,132 @@
+use colored::*;
+use serde::Serialize;
+use std::fmt;
+use std::sync::Mutex;
+
+/// Something a step would change on the machine.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Action {
+    Command { program: String, args: Vec<String> },
+    WriteFile { path: String, content: String },
+    AppendFile { path: String, content: String },
+    RemoveFromFile { path: String, content: String },
+    CreateDir { path: String },
+    CopyFile { from: String, to: String },
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Command { program, args } => {
+                write!(f, "{}", program)?;
+                for arg in args {
+                    write!(f, " {}", shell_quote(arg))?;
+                }
+                Ok(())
+            }
+            Action::WriteFile { path, content } => {
+                write!(f, "write {} ({} lines)", path, content.lines().count())
+            }
+            Action::AppendFile { path, content } => {
+                write!(f, "append to {}: {}", path, content.trim())
+            }
+            Action::RemoveFromFile { path, content } => {
+                write!(f, "remove from {}: {}", path, content.trim())
+            }
+            Action::CreateDir { path } => write!(f, "mkdir -p {}", shell_quote(path)),
+            Action::CopyFile { from, to } => write!(f, "cp {} {}", shell_quote(from), shell_quote(to)),
+        }
+    }
+}
+
+/// Quotes an argument so the printed command can be pasted into a shell.
+fn shell_quote(arg: &str) -> String {
+    let safe = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:@+,%~".contains(c));
+    if safe {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+#[derive(Serialize)]
+struct PlanReport<'a> {
+    dry_run: bool,
+    steps: &'a [StepPlan],
+}
+
+#[derive(Serialize)]
+struct StepPlan {
+    id: String,
+    actions: Vec<Action>,
+}
+
+/// Collects the actions of a dry run instead of executing them.
+pub struct Planner {
+    steps: Mutex<Vec<StepPlan>>,
+    echo: bool,
+}
+
+impl Planner {
+    /// With `echo` set, every action is printed as soon as it is planned.
+    pub fn new(echo: bool) -> Self {
+        Planner {
+            steps: Mutex::new(Vec::new()),
+            echo,
+        }
+    }
+
+    /// Starts a new group; following actions belong to the step `id`.
+    pub fn begin_step(&self, id: &str) {
+        self.steps.lock().unwrap().push(StepPlan {
+            id: id.to_string(),
+            actions: Vec::new(),
+        });
+    }
+
+    pub fn record(&self, action: Action) {
+        if self.echo {
+            say!("{} {}", "[dry-run]".yellow(), action);
+        }
+
+        let mut steps = self.steps.lock().unwrap();
+        if steps.is_empty() {
+            steps.push(StepPlan {
+                id: String::from("main"),
+                actions: Vec::new(),
+            });
+        }
+        steps.last_mut().unwrap().actions.push(action);
+    }
+
+    /// Adds an action to the latest group of the step `id`, for actions
+    /// planned after later steps have started.
+    pub fn record_for(&self, id: &str, action: Action) {
+        if self.echo {
+            say!("{} {}", "[dry-run]".yellow(), action);
+        }
+
+        let mut steps = self.steps.lock().unwrap();
+        match steps.iter_mut().rev().find(|step| step.id == id) {
+            Some(step) => step.actions.push(action),
+            None => steps.push(StepPlan {
+                id: id.to_string(),
+                actions: vec![action],
+            }),
+        }
+    }
+
+    /// The whole plan as JSON, grouped by step.
+    pub fn to_json(&self) -> String {
+        let steps = self.steps.lock().unwrap();
+        serde_json::to_string_pretty(&PlanReport {
+            dry_run: true,
+            steps: &steps,
+        })
+        .unwrap_or_default()
+    }
+}
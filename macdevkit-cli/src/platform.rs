@@ -0,0 +1,180 @@
+use std::fmt;
+
+use crate::script_handler::ScriptHandler;
+
+/// CPU architecture of the Mac, regardless of how the CLI was compiled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arch {
+    AppleSilicon,
+    Intel,
+}
+
+impl fmt::Display for Arch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Arch::AppleSilicon => write!(f, "Apple Silicon"),
+            Arch::Intel => write!(f, "Intel"),
+        }
+    }
+}
+
+/// The machine the steps run on, detected through the handler's
+/// [`CommandRunner`](crate::runner::CommandRunner) so it can be stubbed.
+///
+/// `std::env::consts::ARCH` only tells which target the CLI was built for:
+/// an Intel build runs on Apple Silicon under Rosetta 2, and Homebrew's
+/// installer then picks `/usr/local` instead of `/opt/homebrew`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Platform {
+    pub arch: Arch,
+    /// The CLI runs as an Intel binary under Rosetta 2.
+    pub rosetta: bool,
+    /// Prefix of the `brew` on `PATH`, or the native prefix if Homebrew is
+    /// not installed yet.
+    pub brew_prefix: String,
+    /// Product version from `sw_vers`, e.g. `14.5`.
+    pub macos_version: Option<String>,
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "macOS {} on {}", self.macos_version.as_deref().unwrap_or("(unknown version)"), self.arch)?;
+        if self.rosetta {
+            write!(f, " under Rosetta")?;
+        }
+        Ok(())
+    }
+}
+
+impl Platform {
+    pub fn detect(handler: &ScriptHandler) -> Self {
+        let query = |program: &str, args: &[&str]| {
+            handler
+                .query(program, args)
+                .ok()
+                .filter(|output| output.success)
+                .map(|output| output.stdout.trim().to_string())
+                .filter(|stdout| !stdout.is_empty())
+        };
+        // Intel Mac 上这两个 sysctl 键不存在，命令会失败
+        let sysctl = |key: &str| query("sysctl", &["-n", key]).as_deref() == Some("1");
+
+        // Rosetta 下 uname -m 报告 x86_64，因此以 sysctl 为准
+        let arch = if sysctl("hw.optional.arm64") || query("uname", &["-m"]).as_deref() == Some("arm64") {
+            Arch::AppleSilicon
+        } else {
+            Arch::Intel
+        };
+        let rosetta = sysctl("sysctl.proc_translated");
+        let macos_version = query("sw_vers", &["-productVersion"]);
+
+        let brew_prefix = handler
+            .command_exists("brew")
+            .then(|| handler.query("brew", &["--prefix"]).ok())
+            .flatten()
+            .filter(|output| output.success && !output.stdout.trim().is_empty())
+            .map(|output| output.stdout.trim().to_string())
+            .unwrap_or_else(|| native_brew_prefix(arch).to_string());
+
+        Platform {
+            arch,
+            rosetta,
+            brew_prefix,
+            macos_version,
+        }
+    }
+
+    /// Where Homebrew belongs on this Mac: `/opt/homebrew` on Apple Silicon,
+    /// `/usr/local` on Intel.
+    pub fn native_brew_prefix(&self) -> &'static str {
+        native_brew_prefix(self.arch)
+    }
+
+    /// Path of the `brew` executable under [`Platform::brew_prefix`].
+    pub fn brew(&self) -> String {
+        format!("{}/bin/brew", self.brew_prefix)
+    }
+}
+
+fn native_brew_prefix(arch: Arch) -> &'static str {
+    match arch {
+        Arch::AppleSilicon => "/opt/homebrew",
+        Arch::Intel => "/usr/local",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::runner::{CommandOutput, RecordingRunner};
+    use crate::test_support::{handler, TempHome};
+
+    fn detect(name: &str, runner: RecordingRunner) -> Platform {
+        let home = TempHome::new(name);
+        let runner = Arc::new(runner.respond("sw_vers -productVersion", CommandOutput::success("14.5\n")));
+        Platform::detect(&handler(&runner, &home))
+    }
+
+    #[test]
+    fn detects_apple_silicon() {
+        let platform = detect(
+            "platform-arm64",
+            RecordingRunner::new()
+                .respond("uname -m", CommandOutput::success("arm64\n"))
+                .respond("sysctl -n hw.optional.arm64", CommandOutput::success("1\n"))
+                .respond("sysctl -n sysctl.proc_translated", CommandOutput::success("0\n")),
+        );
+        assert_eq!(platform.arch, Arch::AppleSilicon);
+        assert!(!platform.rosetta);
+        assert_eq!(platform.brew_prefix, "/opt/homebrew");
+        assert_eq!(platform.macos_version.as_deref(), Some("14.5"));
+        assert_eq!(platform.to_string(), "macOS 14.5 on Apple Silicon");
+    }
+
+    #[test]
+    fn detects_intel() {
+        let platform = detect(
+            "platform-x86_64",
+            RecordingRunner::new()
+                .respond("uname -m", CommandOutput::success("x86_64\n"))
+                .respond("sysctl", CommandOutput::failure("sysctl: unknown oid 'hw.optional.arm64'")),
+        );
+        assert_eq!(platform.arch, Arch::Intel);
+        assert!(!platform.rosetta);
+        assert_eq!(platform.brew_prefix, "/usr/local");
+        assert_eq!(platform.brew(), "/usr/local/bin/brew");
+    }
+
+    #[test]
+    fn detects_rosetta() {
+        let platform = detect(
+            "platform-rosetta",
+            RecordingRunner::new()
+                .with_program("brew")
+                .respond("uname -m", CommandOutput::success("x86_64\n"))
+                .respond("sysctl -n hw.optional.arm64", CommandOutput::success("1\n"))
+                .respond("sysctl -n sysctl.proc_translated", CommandOutput::success("1\n"))
+                .respond("brew --prefix", CommandOutput::success("/usr/local\n")),
+        );
+        assert_eq!(platform.arch, Arch::AppleSilicon);
+        assert!(platform.rosetta);
+        // Rosetta 下装的 Homebrew 在 /usr/local，但本机应使用 /opt/homebrew
+        assert_eq!(platform.brew_prefix, "/usr/local");
+        assert_eq!(platform.native_brew_prefix(), "/opt/homebrew");
+        assert_eq!(platform.to_string(), "macOS 14.5 on Apple Silicon under Rosetta");
+    }
+
+    #[test]
+    fn falls_back_to_uname() {
+        let platform = detect(
+            "platform-uname",
+            RecordingRunner::new()
+                .respond("uname -m", CommandOutput::success("arm64\n"))
+                .respond("sysctl", CommandOutput::failure("")),
+        );
+        assert_eq!(platform.arch, Arch::AppleSilicon);
+        assert_eq!(platform.brew_prefix, "/opt/homebrew");
+    }
+}
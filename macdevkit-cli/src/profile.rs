@@ -0,0 +1,133 @@
+use serde::Deserialize;
+
+use crate::manifest::Manifest;
+
+/// A named subset of the setup, chosen with `setup --profile <name>`: the
+/// steps to run and the packages they install.
+///
+/// A package list left out keeps the manifest's list; a list that is given
+/// replaces it.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    pub description: String,
+    /// Step ids; they run after the steps they depend on, which are added
+    /// if missing.
+    pub steps: Vec<String>,
+    pub formulae: Option<Vec<String>>,
+    pub casks: Option<Vec<String>>,
+    pub npm: Option<Vec<String>>,
+    pub extensions: Option<Vec<String>>,
+}
+
+fn strings(items: &[&str]) -> Option<Vec<String>> {
+    Some(items.iter().map(|item| item.to_string()).collect())
+}
+
+fn steps(ids: &[&str]) -> Vec<String> {
+    ids.iter().map(|id| id.to_string()).collect()
+}
+
+/// Profiles that ship with the CLI. A profile of the same name in the
+/// manifest replaces the built-in one.
+pub fn builtin() -> Vec<(&'static str, Profile)> {
+    vec![
+        (
+            "frontend",
+            Profile {
+                description: "Node.js, pnpm, VS Code and browsers".to_string(),
+                steps: steps(&[
+                    "xcode", "brew", "mirror", "git", "ssh", "vscode", "node", "zsh", "devtools", "apps", "workspace",
+                ]),
+                formulae: strings(&["jq", "ripgrep", "fd", "gh"]),
+                casks: strings(&["google-chrome", "firefox", "figma"]),
+                npm: strings(&["pnpm", "typescript", "eslint", "prettier"]),
+                extensions: strings(&[
+                    "dbaeumer.vscode-eslint",
+                    "esbenp.prettier-vscode",
+                    "ms-vscode.vscode-typescript-next",
+                    "eamodio.gitlens",
+                ]),
+            },
+        ),
+        (
+            "backend",
+            Profile {
+                description: "Go, PostgreSQL, Redis and Docker".to_string(),
+                steps: steps(&[
+                    "xcode", "brew", "mirror", "git", "ssh", "vscode", "zsh", "docker", "devtools", "apps", "workspace",
+                ]),
+                formulae: strings(&["go", "postgresql@16", "redis", "jq", "ripgrep", "httpie", "gh"]),
+                casks: strings(&["postman"]),
+                npm: None,
+                extensions: strings(&["golang.go", "ms-azuretools.vscode-docker", "eamodio.gitlens"]),
+            },
+        ),
+        (
+            "mobile",
+            Profile {
+                description: "CocoaPods, fastlane and Android Studio".to_string(),
+                steps: steps(&[
+                    "xcode", "brew", "mirror", "git", "ssh", "vscode", "node", "zsh", "devtools", "apps", "workspace",
+                ]),
+                formulae: strings(&["cocoapods", "fastlane", "watchman", "gh"]),
+                casks: strings(&["android-studio", "zulu@17"]),
+                npm: strings(&["yarn"]),
+                extensions: None,
+            },
+        ),
+        (
+            "data",
+            Profile {
+                description: "Python, DuckDB, PostgreSQL and Jupyter".to_string(),
+                steps: steps(&[
+                    "xcode", "brew", "mirror", "git", "ssh", "vscode", "zsh", "docker", "devtools", "apps", "workspace",
+                ]),
+                formulae: strings(&["python@3.12", "pyenv", "duckdb", "postgresql@16", "jq"]),
+                casks: strings(&["dbeaver-community"]),
+                npm: None,
+                extensions: strings(&["ms-python.python", "ms-toolsai.jupyter"]),
+            },
+        ),
+    ]
+}
+
+impl Manifest {
+    /// The profile called `name`, from the manifest or the built-in ones.
+    pub fn profile(&self, name: &str) -> Option<Profile> {
+        if let Some(profile) = self.profiles.get(name) {
+            return Some(profile.clone());
+        }
+        builtin()
+            .into_iter()
+            .find(|(builtin_name, _)| *builtin_name == name)
+            .map(|(_, profile)| profile)
+    }
+
+    /// Names of every profile, built-in ones first.
+    pub fn profile_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = builtin().into_iter().map(|(name, _)| name.to_string()).collect();
+        for name in self.profiles.keys() {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+        names
+    }
+
+    /// Replaces the package lists that `profile` sets.
+    pub fn apply_profile(&mut self, profile: &Profile) {
+        if let Some(formulae) = &profile.formulae {
+            self.brew.formulae = formulae.clone();
+        }
+        if let Some(casks) = &profile.casks {
+            self.brew.casks = casks.clone();
+        }
+        if let Some(npm) = &profile.npm {
+            self.npm.globals = npm.clone();
+        }
+        if let Some(extensions) = &profile.extensions {
+            self.vscode.extensions = extensions.clone();
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, MultiSelect, Password, Select};
+use serde::Deserialize;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
+use crate::error::SetupError;
+
+/// How yes/no questions are answered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Confirmation {
+    /// Ask on the terminal.
+    Ask,
+    /// `--yes`: answer yes without asking.
+    Yes,
+    /// `--no`: answer no without asking.
+    No,
+}
+
+/// Values for prompts, read from the file passed with `--answers`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Answers {
+    pub git: GitAnswers,
+    pub ssh: SshAnswers,
+    /// Whether to restart at the end of the setup; see
+    /// [`Prompter::restart_answer`].
+    pub restart: Option<bool>,
+
+    /// File the answers were read from.
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GitAnswers {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    /// Passphrase for a new GPG signing key; empty for none.
+    pub gpg_passphrase: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SshAnswers {
+    /// Passphrase for new SSH keys; empty for none.
+    pub passphrase: Option<String>,
+}
+
+impl Answers {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+        let mut answers: Answers = toml::from_str(&content)
+            .map_err(|e| format!("Invalid answers file {}: {}", path.display(), e))?;
+
+        answers.source = Some(path.to_path_buf());
+        Ok(answers)
+    }
+}
+
+/// Asks the user questions, or answers them from `--yes`/`--no` and the
+/// answers file.
+///
+/// When stdin is not a terminal, a question without an answer is an error
+/// instead of a prompt that would wait forever.
+pub struct Prompter {
+    confirmation: Confirmation,
+    answers: Answers,
+    terminal: bool,
+}
+
+impl Default for Prompter {
+    fn default() -> Self {
+        Prompter::new(Confirmation::Ask, Answers::default())
+    }
+}
+
+impl Prompter {
+    pub fn new(confirmation: Confirmation, answers: Answers) -> Self {
+        Prompter {
+            confirmation,
+            answers,
+            terminal: std::io::stdin().is_terminal(),
+        }
+    }
+
+    /// Overrides the detected terminal, e.g. to test the non-interactive path.
+    pub fn with_terminal(mut self, terminal: bool) -> Self {
+        self.terminal = terminal;
+        self
+    }
+
+    pub fn answers(&self) -> &Answers {
+        &self.answers
+    }
+
+    /// Preset answer to the restart question at the end of the setup:
+    /// the answers file if it says, otherwise no under `--yes` or `--no`.
+    pub fn restart_answer(&self) -> Option<bool> {
+        match (self.answers.restart, self.confirmation) {
+            (Some(restart), _) => Some(restart),
+            (None, Confirmation::Ask) => None,
+            (None, _) => Some(false),
+        }
+    }
+
+    /// Asks a yes/no question. `answer` takes precedence over `--yes`/`--no`.
+    pub fn confirm(&self, prompt: &str, default: bool, answer: Option<bool>) -> Result<bool, SetupError> {
+        if let Some(answer) = answer {
+            return Ok(answer);
+        }
+
+        match self.confirmation {
+            Confirmation::Yes => Ok(true),
+            Confirmation::No => Ok(false),
+            Confirmation::Ask => {
+                self.require_terminal(prompt, "pass --yes or --no")?;
+                Confirm::with_theme(&ColorfulTheme::default())
+                    .with_prompt(prompt)
+                    .default(default)
+                    .interact()
+                    .map_err(aborted)
+            }
+        }
+    }
+
+    /// Asks for a line of text, unless `answer` already holds it. `key` names
+    /// the answers file entry, e.g. `git.name`.
+    pub fn input(&self, prompt: &str, key: &str, answer: Option<&str>) -> Result<String, SetupError> {
+        if let Some(answer) = answer {
+            return Ok(answer.to_string());
+        }
+
+        self.require_terminal(prompt, &format!("set `{}` in the --answers file", key))?;
+        Input::<String>::with_theme(&ColorfulTheme::default())
+            .with_prompt(prompt)
+            .interact_text()
+            .map_err(aborted)
+    }
+
+    /// Asks for a secret twice without echoing it, unless `answer` already
+    /// holds it. An empty answer is allowed.
+    pub fn password(&self, prompt: &str, key: &str, answer: Option<&str>) -> Result<String, SetupError> {
+        if let Some(answer) = answer {
+            return Ok(answer.to_string());
+        }
+
+        self.require_terminal(prompt, &format!("set `{}` in the --answers file", key))?;
+        Password::with_theme(&ColorfulTheme::default())
+            .with_prompt(prompt)
+            .with_confirmation("Repeat it", "The entries do not match")
+            .allow_empty_password(true)
+            .interact()
+            .map_err(aborted)
+    }
+
+    /// Lets the user pick one of `items` and returns its index.
+    pub fn select(&self, prompt: &str, items: &[&str]) -> Result<usize, SetupError> {
+        self.require_terminal(prompt, "run a subcommand such as `setup --yes` instead")?;
+        Select::with_theme(&ColorfulTheme::default())
+            .with_prompt(prompt)
+            .default(0)
+            .items(items)
+            .interact()
+            .map_err(aborted)
+    }
+
+    /// Lets the user tick any of `items`, starting from `defaults`, and
+    /// returns the indices ticked. `--yes` takes the defaults and `--no`
+    /// ticks nothing.
+    pub fn choose(&self, prompt: &str, items: &[&str], defaults: &[bool]) -> Result<Vec<usize>, SetupError> {
+        match self.confirmation {
+            Confirmation::Yes => Ok((0..items.len()).filter(|&i| defaults.get(i) == Some(&true)).collect()),
+            Confirmation::No => Ok(Vec::new()),
+            Confirmation::Ask => {
+                self.require_terminal(prompt, "pass --yes or --no")?;
+                MultiSelect::with_theme(&ColorfulTheme::default())
+                    .with_prompt(prompt)
+                    .items(items)
+                    .defaults(defaults)
+                    .interact()
+                    .map_err(aborted)
+            }
+        }
+    }
+
+    fn require_terminal(&self, prompt: &str, remedy: &str) -> Result<(), SetupError> {
+        if self.terminal {
+            Ok(())
+        } else {
+            Err(SetupError::Aborted(format!(
+                "Cannot ask \"{}\": stdin is not a terminal; {}",
+                prompt, remedy
+            )))
+        }
+    }
+}
+
+/// A prompt that ended without an answer, e.g. on Ctrl-C.
+fn aborted(error: dialoguer::Error) -> SetupError {
+    SetupError::Aborted(format!("Prompt cancelled: {}", error))
+}
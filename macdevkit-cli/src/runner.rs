@@ -0,0 +1,290 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use which::which;
+
+/// Captured result of a command run by a [`CommandRunner`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandOutput {
+    pub success: bool,
+    /// Exit code, or `None` if the command was ended by a signal.
+    pub code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl CommandOutput {
+    pub fn success(stdout: &str) -> Self {
+        CommandOutput {
+            success: true,
+            code: Some(0),
+            stdout: stdout.to_string(),
+            stderr: String::new(),
+        }
+    }
+
+    pub fn failure(stderr: &str) -> Self {
+        CommandOutput {
+            success: false,
+            code: Some(1),
+            stdout: String::new(),
+            stderr: stderr.to_string(),
+        }
+    }
+}
+
+/// How a command exited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExitStatus {
+    /// Exit code, or `None` if the command was ended by a signal.
+    pub code: Option<i32>,
+}
+
+impl ExitStatus {
+    pub fn success(self) -> bool {
+        self.code == Some(0)
+    }
+}
+
+impl From<std::process::ExitStatus> for ExitStatus {
+    fn from(status: std::process::ExitStatus) -> Self {
+        ExitStatus { code: status.code() }
+    }
+}
+
+/// Which stream a line of command output was written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// Starts external programs on behalf of [`ScriptHandler`](crate::script_handler::ScriptHandler).
+///
+/// All process spawning goes through this trait so the steps can be
+/// exercised without a Mac by swapping in a [`RecordingRunner`].
+pub trait CommandRunner: Send + Sync {
+    /// Runs a command with inherited stdio.
+    fn status(&self, program: &str, args: &[&str]) -> io::Result<ExitStatus>;
+
+    /// Runs a command and captures its output.
+    fn output(&self, program: &str, args: &[&str]) -> io::Result<CommandOutput>;
+
+    /// Returns true if `program` can be found on `PATH`.
+    fn exists(&self, program: &str) -> bool;
+
+    /// Runs a command, passing each line of its output to `on_line` as it
+    /// is written.
+    ///
+    /// The default implementation captures the output with
+    /// [`CommandRunner::output`] and replays it once the command exits.
+    fn stream(
+        &self,
+        program: &str,
+        args: &[&str],
+        on_line: &(dyn Fn(OutputStream, &str) + Sync),
+    ) -> io::Result<ExitStatus> {
+        let output = self.output(program, args)?;
+        output.stdout.lines().for_each(|line| on_line(OutputStream::Stdout, line));
+        output.stderr.lines().for_each(|line| on_line(OutputStream::Stderr, line));
+        Ok(ExitStatus { code: output.code })
+    }
+
+    /// Like [`CommandRunner::stream`], but writes `input` to the command's
+    /// stdin and then closes it.
+    fn stream_input(
+        &self,
+        program: &str,
+        args: &[&str],
+        input: &str,
+        on_line: &(dyn Fn(OutputStream, &str) + Sync),
+    ) -> io::Result<ExitStatus>;
+}
+
+/// Runs commands on the real system.
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn status(&self, program: &str, args: &[&str]) -> io::Result<ExitStatus> {
+        Command::new(program).args(args).status().map(ExitStatus::from)
+    }
+
+    fn output(&self, program: &str, args: &[&str]) -> io::Result<CommandOutput> {
+        let output = Command::new(program).args(args).output()?;
+        Ok(CommandOutput {
+            success: output.status.success(),
+            code: output.status.code(),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        })
+    }
+
+    fn exists(&self, program: &str) -> bool {
+        which(program).is_ok()
+    }
+
+    fn stream(
+        &self,
+        program: &str,
+        args: &[&str],
+        on_line: &(dyn Fn(OutputStream, &str) + Sync),
+    ) -> io::Result<ExitStatus> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        std::thread::scope(|scope| {
+            scope.spawn(|| forward_lines(stdout, OutputStream::Stdout, on_line));
+            forward_lines(stderr, OutputStream::Stderr, on_line);
+        });
+
+        child.wait().map(ExitStatus::from)
+    }
+
+    fn stream_input(
+        &self,
+        program: &str,
+        args: &[&str],
+        input: &str,
+        on_line: &(dyn Fn(OutputStream, &str) + Sync),
+    ) -> io::Result<ExitStatus> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        // 写完后关闭 stdin，程序读到 EOF 才不会一直等待
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(input.as_bytes())?;
+        }
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        std::thread::scope(|scope| {
+            scope.spawn(|| forward_lines(stdout, OutputStream::Stdout, on_line));
+            forward_lines(stderr, OutputStream::Stderr, on_line);
+        });
+
+        child.wait().map(ExitStatus::from)
+    }
+}
+
+fn forward_lines(pipe: Option<impl Read>, stream: OutputStream, on_line: &(dyn Fn(OutputStream, &str) + Sync)) {
+    let Some(pipe) = pipe else {
+        return;
+    };
+    for line in BufReader::new(pipe).lines().map_while(Result::ok) {
+        on_line(stream, &line);
+    }
+}
+
+/// A single command seen by a [`RecordingRunner`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invocation {
+    pub program: String,
+    pub args: Vec<String>,
+    /// What was written to stdin, if anything.
+    pub stdin: Option<String>,
+}
+
+impl Invocation {
+    /// The invocation as one space-separated string, e.g. `brew install jq`.
+    pub fn command_line(&self) -> String {
+        std::iter::once(self.program.as_str())
+            .chain(self.args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Fake runner that records every invocation and answers with canned output.
+///
+/// Responses are matched by command-line prefix, most recently added first;
+/// unmatched commands succeed with empty output. Programs are only reported
+/// as present on `PATH` after [`RecordingRunner::with_program`].
+#[derive(Default)]
+pub struct RecordingRunner {
+    invocations: Mutex<Vec<Invocation>>,
+    responses: Vec<(String, CommandOutput)>,
+    programs: Vec<String>,
+}
+
+impl RecordingRunner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answers commands starting with `prefix` (e.g. `"xcode-select -p"`) with `output`.
+    pub fn respond(mut self, prefix: &str, output: CommandOutput) -> Self {
+        self.responses.push((prefix.to_string(), output));
+        self
+    }
+
+    /// Makes [`CommandRunner::exists`] report `program` as installed.
+    pub fn with_program(mut self, program: &str) -> Self {
+        self.programs.push(program.to_string());
+        self
+    }
+
+    /// Everything run so far, in order.
+    pub fn invocations(&self) -> Vec<Invocation> {
+        self.invocations.lock().unwrap().clone()
+    }
+
+    /// Command lines run so far, in order.
+    pub fn command_lines(&self) -> Vec<String> {
+        self.invocations().iter().map(Invocation::command_line).collect()
+    }
+
+    fn record(&self, program: &str, args: &[&str], stdin: Option<&str>) -> CommandOutput {
+        let invocation = Invocation {
+            program: program.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            stdin: stdin.map(str::to_string),
+        };
+        let command_line = invocation.command_line();
+        self.invocations.lock().unwrap().push(invocation);
+
+        self.responses
+            .iter()
+            .rev()
+            .find(|(prefix, _)| command_line.starts_with(prefix.as_str()))
+            .map(|(_, output)| output.clone())
+            .unwrap_or_else(|| CommandOutput::success(""))
+    }
+}
+
+impl CommandRunner for RecordingRunner {
+    fn status(&self, program: &str, args: &[&str]) -> io::Result<ExitStatus> {
+        Ok(ExitStatus {
+            code: self.record(program, args, None).code,
+        })
+    }
+
+    fn output(&self, program: &str, args: &[&str]) -> io::Result<CommandOutput> {
+        Ok(self.record(program, args, None))
+    }
+
+    fn stream_input(
+        &self,
+        program: &str,
+        args: &[&str],
+        input: &str,
+        on_line: &(dyn Fn(OutputStream, &str) + Sync),
+    ) -> io::Result<ExitStatus> {
+        let output = self.record(program, args, Some(input));
+        output.stdout.lines().for_each(|line| on_line(OutputStream::Stdout, line));
+        output.stderr.lines().for_each(|line| on_line(OutputStream::Stderr, line));
+        Ok(ExitStatus { code: output.code })
+    }
+
+    fn exists(&self, program: &str) -> bool {
+        self.programs.iter().any(|known| known == program)
+    }
+}
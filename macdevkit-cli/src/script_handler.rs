@@ -1,437 +1,1262 @@
-use std::process::Command;
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::Path;
 use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use clap::ArgMatches;
 use colored::*;
 
+use crate::changes::{Change, ChangeLog, PackageKind, PreviousDefault};
+use crate::error::SetupError;
+use crate::events::{Event, EventStream, RunSummary, StepSummary};
+use crate::install;
+use crate::journal::{self, Journal, StepStatus};
+use crate::managed_block;
+use crate::node;
+use crate::manifest::Manifest;
+use crate::plan::{Action, Planner};
+use crate::platform::Platform;
+use crate::prompt::{Answers, Prompter};
+use crate::runner::{CommandOutput, CommandRunner, ExitStatus, OutputStream};
+use crate::steps::Step;
+
+/// Journal error of a step whose packages are still to be installed.
+const PACKAGES_PENDING: &str = "Packages not installed yet";
+/// Error of a step whose packages failed to install.
+const PACKAGES_FAILED: &str = "Failed to install packages";
+
 pub struct ScriptHandler {
-    wrapper_script_path: String,
+    manifest: Manifest,
+    runner: Arc<dyn CommandRunner>,
+    planner: Option<Planner>,
+    journal: Option<Mutex<Journal>>,
+    changes: Option<Mutex<ChangeLog>>,
+    prompter: Prompter,
+    platform: Mutex<Option<Arc<Platform>>>,
+    script: Option<PathBuf>,
+    events: Option<EventStream>,
+    summary: Mutex<RunSummary>,
+    /// Home directory used instead of `$HOME`.
+    home: Option<String>,
+    /// Commands run by each unfinished step, for the summary.
+    commands: Mutex<BTreeMap<String, Vec<String>>>,
+    current_step: Mutex<Option<String>>,
 }
 
 impl ScriptHandler {
-    pub fn new() -> Self {
-        // Primary path: Use the wrapper script that's copied during build
-        let primary_path = format!("{}/init_wrapper.sh", env!("OUT_DIR"));
-        
-        // 记录脚本路径用于调试
-       // println!("{}", format!("Script path: {}", primary_path).cyan());
-        
+    /// Every command the steps run goes through `runner`.
+    pub fn new(manifest: Manifest, runner: Arc<dyn CommandRunner>) -> Self {
         ScriptHandler {
-            wrapper_script_path: primary_path,
-        }
-    }
-
-    pub fn run_section(&self, section: &str) -> Result<bool, String> {
-        println!("{}", format!("\n==== Running {} Section ====\n", section.to_uppercase()).blue());
-        
-        // 检查脚本是否存在
-        let script_path = Path::new(&self.wrapper_script_path);
-        
-        if script_path.exists() {
-            // 如果脚本存在，使用脚本
-       //     println!("{}", format!("Using script: {}", self.wrapper_script_path).cyan());
-            
-            // 确保脚本是可执行的
-            #[cfg(unix)]
-            {
-                let _ = Command::new("chmod")
-                    .args(["+x", &self.wrapper_script_path])
-                    .status()
-                    .map_err(|e| format!("Failed to set script permissions: {}", e));
+            manifest,
+            runner,
+            planner: None,
+            journal: None,
+            changes: None,
+            prompter: Prompter::default(),
+            platform: Mutex::new(None),
+            script: None,
+            events: None,
+            summary: Mutex::new(RunSummary::default()),
+            home: None,
+            commands: Mutex::new(BTreeMap::new()),
+            current_step: Mutex::new(None),
+        }
+    }
+
+    /// Uses `home` instead of `$HOME`, e.g. a temporary directory in tests.
+    pub fn with_home(mut self, home: impl Into<String>) -> Self {
+        self.home = Some(home.into());
+        self
+    }
+
+    /// Records commands that change the system in `planner` instead of
+    /// executing them.
+    pub fn with_planner(mut self, planner: Planner) -> Self {
+        self.planner = Some(planner);
+        self
+    }
+
+    /// Runs every section with `bash <script> <step id>` instead of the
+    /// built-in step.
+    pub fn with_script(mut self, script: PathBuf) -> Self {
+        self.script = Some(script);
+        self
+    }
+
+    /// Records the outcome of every step in `journal`.
+    pub fn with_journal(mut self, journal: Journal) -> Self {
+        self.journal = Some(Mutex::new(journal));
+        self
+    }
+
+    /// Records what each step changes in `changes`, so it can be undone.
+    pub fn with_changes(mut self, changes: ChangeLog) -> Self {
+        self.changes = Some(Mutex::new(changes));
+        self
+    }
+
+    /// Answers questions with `prompter`, e.g. from `--yes` or an answers file.
+    pub fn with_prompter(mut self, prompter: Prompter) -> Self {
+        self.prompter = prompter;
+        self
+    }
+
+    /// Streams step progress and command output to `events` as it happens.
+    pub fn with_events(mut self, events: EventStream) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Outcome of every step run or skipped so far.
+    pub fn summary(&self) -> RunSummary {
+        self.summary.lock().unwrap().clone()
+    }
+
+    fn emit(&self, event: Event) {
+        if let Some(events) = &self.events {
+            events.emit(&event);
+        }
+    }
+
+    /// Adds a step's outcome to the summary and reports it as finished.
+    fn finish(&self, id: &str, status: StepStatus, started: Instant, error: Option<String>) {
+        let duration_ms = started.elapsed().as_millis() as u64;
+        let commands = self.commands.lock().unwrap().remove(id).unwrap_or_default();
+
+        self.emit(Event::StepFinished {
+            step: id.to_string(),
+            status,
+            duration_ms,
+            error: error.clone(),
+        });
+        self.summary.lock().unwrap().steps.push(StepSummary {
+            id: id.to_string(),
+            status,
+            duration_ms,
+            commands,
+            error,
+        });
+    }
+
+    /// Adds the package install to a step already in the summary.
+    fn finish_packages(&self, id: &str, status: StepStatus, error: Option<String>) {
+        let commands = self.commands.lock().unwrap().remove(id).unwrap_or_default();
+        let mut summary = self.summary.lock().unwrap();
+        if let Some(step) = summary.steps.iter_mut().rev().find(|step| step.id == id) {
+            step.commands.extend(commands);
+            step.status = status;
+            step.error = step.error.take().or(error);
+        }
+    }
+
+    /// Outcome of the step in the journal, if it has run before.
+    pub fn journal_status(&self, id: &str) -> Option<StepStatus> {
+        let journal = self.journal.as_ref()?;
+        journal.lock().unwrap().status(id)
+    }
+
+    /// Forgets the previous run so a new full setup starts from scratch.
+    pub fn reset_journal(&self, profile: Option<&str>) {
+        if let Some(journal) = &self.journal {
+            let mut journal = journal.lock().unwrap();
+            *journal = Journal::new(journal.path().to_path_buf());
+            journal.profile = profile.map(str::to_string);
+        }
+    }
+
+    /// Records that the user chose not to run `step`.
+    pub fn skip_section(&self, step: &dyn Step) {
+        self.record(step.id(), StepStatus::Skipped, journal::now(), None);
+        self.finish(step.id(), StepStatus::Skipped, Instant::now(), None);
+    }
+
+    fn record(&self, id: &str, status: StepStatus, started_at: u64, error: Option<String>) {
+        if self.planner.is_some() {
+            return;
+        }
+
+        if let Some(journal) = &self.journal {
+            let mut journal = journal.lock().unwrap();
+            journal.record(id, status, started_at, error);
+            if let Err(e) = journal.save() {
+                say!("{}", format!("Warning: {}", e).yellow());
             }
-            
-            // 运行脚本并传递部分参数
-            let output = Command::new(&self.wrapper_script_path)
-                .arg(section.to_lowercase())
-                .status()
-                .map_err(|e| format!("Failed to execute script: {}", e))?;
-            
-            return Ok(output.success());
-        } else {
-            // 如果脚本不存在，直接在Rust中实现相应功能
-        //    println!("{}", "Using built-in implementation...".cyan());
-            return self.handle_section_internally(section);
-        }
-    }
-    
-    // 在Rust代码中直接实现各个部分的功能
-    fn handle_section_internally(&self, section: &str) -> Result<bool, String> {
-        match section.to_lowercase().as_str() {
-            "xcode" => self.handle_xcode_section(),
-            "brew" => self.handle_brew_section(),
-            "git" => self.handle_git_section(),
-            "ssh" => self.handle_ssh_section(),
-            "vscode" => self.handle_vscode_section(),
-            "node" => self.handle_node_section(),
-            "iterm" => self.handle_iterm_section(),
-            "zsh" => self.handle_zsh_section(),
-            "docker" => self.handle_docker_section(),
-            "devtools" => self.handle_devtools_section(),
-            "apps" => self.handle_apps_section(),
-            "macos" => self.handle_macos_section(),
-            "workspace" => self.handle_workspace_section(),
-            _ => Err(format!("Unknown section: {}", section)),
-        }
-    }
-    
-    // 实现各个部分的功能
-    fn handle_xcode_section(&self) -> Result<bool, String> {
-        println!("{}", "\n==== Installing Xcode Command Line Tools ====\n".blue());
-        
-        // 检查xcode command line tools是否已安装
-        let xcode_select_check = Command::new("xcode-select")
-            .arg("-p")
-            .output()
-            .map_err(|e| format!("Failed to check xcode-select: {}", e))?;
-        
-        if xcode_select_check.status.success() {
-            println!("{}", "✓ Xcode Command Line Tools already installed".green());
-            println!("{}", "Xcode Command Line Tools installation completed".green());
-            return Ok(true);
-        } else {
-            println!("{}", "Installing Xcode Command Line Tools...".cyan());
-            
-            // 触发安装
-            let install_result = Command::new("xcode-select")
-                .args(["--install"])
-                .status()
-                .map_err(|e| format!("Failed to run xcode-select --install: {}", e))?;
-            
-            if install_result.success() {
-                println!("{}", "Xcode Command Line Tools installation triggered".green());
-                println!("Please wait for the installation to complete.");
-                println!("{}", "Xcode Command Line Tools installation completed".green());
-                return Ok(true);
+        }
+    }
+
+    /// Runs one of the step's extra subcommands; changes it makes are
+    /// recorded under the step.
+    pub fn run_subcommand(&self, step: &dyn Step, name: &str, matches: &ArgMatches) -> Result<bool, SetupError> {
+        if let Some(planner) = &self.planner {
+            planner.begin_step(step.id());
+        }
+        *self.current_step.lock().unwrap() = Some(step.id().to_string());
+
+        step.run_subcommand(self, name, matches)
+    }
+
+    /// Runs a step, installs its packages and records its outcome in the
+    /// journal and the summary.
+    pub fn run_section(&self, step: &dyn Step) -> Result<bool, SetupError> {
+        self.run_sections(&[step]).pop().unwrap_or(Ok(true))
+    }
+
+    /// Runs `steps` one after another, then installs the packages of all of
+    /// them together; see [`crate::install`]. Each step is recorded and
+    /// reported as soon as it has run; steps whose packages then fail are
+    /// marked as failed again after the install.
+    pub fn run_sections(&self, steps: &[&dyn Step]) -> Vec<Result<bool, SetupError>> {
+        let mut results = Vec::new();
+        let mut pending = Vec::new();
+        let mut batches = Vec::new();
+        for &step in steps {
+            let started_at = journal::now();
+            let instant = Instant::now();
+            self.commands.lock().unwrap().remove(step.id());
+            self.emit(Event::StepStarted {
+                step: step.id().to_string(),
+            });
+
+            let result = self.execute_section(step);
+            let (status, error) = match &result {
+                Ok(true) => (StepStatus::Done, None),
+                Ok(false) => (StepStatus::Failed, None),
+                Err(e) => (StepStatus::Failed, Some(e.to_string())),
+            };
+            // 用户脚本自行负责安装软件包
+            let packages = match &result {
+                Ok(true) if self.script.is_none() => step.packages(self),
+                _ => Vec::new(),
+            };
+            if packages.iter().all(|batch| batch.names.is_empty()) {
+                self.record(step.id(), status, started_at, error.clone());
             } else {
-                return Err("Failed to install Xcode Command Line Tools".to_string());
+                // 软件包装完前中断时，resume 会重新运行该步骤
+                self.record(step.id(), StepStatus::Failed, started_at, Some(PACKAGES_PENDING.to_string()));
+                pending.push((step.id(), started_at));
+                batches.extend(packages);
             }
+            self.finish(step.id(), status, instant, error);
+            results.push(result);
         }
-    }
-    
-    fn handle_brew_section(&self) -> Result<bool, String> {
-        println!("{}", "\n==== Installing Homebrew ====\n".blue());
-        
-        // 检查Homebrew是否已安装
-        let brew_check = Command::new("which")
-            .arg("brew")
-            .output()
-            .map_err(|e| format!("Failed to check brew: {}", e))?;
-        
-        if brew_check.status.success() {
-            println!("{}", "✓ Homebrew already installed".green());
-            
-            // 更新Homebrew
-            let _ = Command::new("brew")
-                .arg("update")
-                .status()
-                .map_err(|e| format!("Failed to update Homebrew: {}", e))?;
-            
-            println!("{}", "Homebrew updated".green());
-            return Ok(true);
-        } else {
-            println!("{}", "Installing Homebrew...".cyan());
-            
-            // 安装Homebrew
-            let install_cmd = r#"/bin/bash -c "$(curl -fsSL https://raw.githubusercontent.com/Homebrew/install/HEAD/install.sh)""#;
-            
-            let install_result = Command::new("bash")
-                .args(["-c", install_cmd])
-                .status()
-                .map_err(|e| format!("Failed to install Homebrew: {}", e))?;
-            
-            if install_result.success() {
-                println!("{}", "Homebrew installed".green());
-                
-                // 在Apple Silicon Mac上添加Homebrew到PATH
-                if std::env::consts::ARCH == "aarch64" {
-                    println!("Adding Homebrew to PATH for Apple Silicon Mac...");
-                    
-                    // 将Homebrew添加到zprofile
-                    let home = std::env::var("HOME").unwrap_or_else(|_| String::from("."));
-                    let zprofile_path = format!("{}/.zprofile", home);
-                    
-                    let profile_content = "\neval \"$(/opt/homebrew/bin/brew shellenv)\"\n";
-                    
-                    // 使用标准文件操作方式追加内容
-                    match std::fs::OpenOptions::new()
-                        .create(true)
-                        .append(true)
-                        .open(&zprofile_path)
-                    {
-                        Ok(mut file) => {
-                            match file.write_all(profile_content.as_bytes()) {
-                                Ok(_) => {
-                                    println!("{}", "Homebrew added to PATH for Apple Silicon Mac".green());
-                                },
-                                Err(e) => {
-                                    println!("{}", format!("Warning: Could not update .zprofile: {}", e).yellow());
-                                }
-                            }
-                        },
-                        Err(e) => {
-                            println!("{}", format!("Warning: Could not open .zprofile: {}", e).yellow());
-                        }
-                    }
+
+        if pending.is_empty() {
+            return results;
+        }
+
+        let instant = Instant::now();
+        self.emit(Event::InstallStarted {
+            steps: pending.iter().map(|(id, _)| id.to_string()).collect(),
+        });
+        let installed = install::install(self, batches);
+        let mut failed = Vec::new();
+        for (id, started_at) in pending {
+            let success = installed.get(id).copied().unwrap_or(true);
+            let error = (!success).then(|| PACKAGES_FAILED.to_string());
+            let status = if success { StepStatus::Done } else { StepStatus::Failed };
+            self.record(id, status, started_at, error.clone());
+            self.finish_packages(id, status, error);
+            if !success {
+                failed.push(id.to_string());
+                if let Some(index) = steps.iter().position(|step| step.id() == id) {
+                    results[index] = Ok(false);
                 }
-                
-                return Ok(true);
-            } else {
-                return Err("Failed to install Homebrew".to_string());
             }
         }
+        self.emit(Event::InstallFinished {
+            duration_ms: instant.elapsed().as_millis() as u64,
+            failed,
+        });
+        results
     }
-    
-    fn handle_git_section(&self) -> Result<bool, String> {
-        println!("{}", "\n==== Installing and configuring Git ====\n".blue());
-        
-        // 检查Git是否已安装
-        let git_check = Command::new("which")
-            .arg("git")
-            .output()
-            .map_err(|e| format!("Failed to check git: {}", e))?;
-        
-        if !git_check.status.success() {
-            println!("{}", "Installing Git...".cyan());
-            
-            // 使用Homebrew安装Git
-            let install_result = Command::new("brew")
-                .args(["install", "git"])
-                .status()
-                .map_err(|e| format!("Failed to install Git: {}", e))?;
-            
-            if !install_result.success() {
-                return Err("Failed to install Git".to_string());
+
+    fn execute_section(&self, step: &dyn Step) -> Result<bool, SetupError> {
+        say!("{}", format!("\n==== Running {} Section ====\n", step.id().to_uppercase()).blue());
+
+        *self.current_step.lock().unwrap() = Some(step.id().to_string());
+
+        if let Some(planner) = &self.planner {
+            planner.begin_step(step.id());
+        }
+
+        if let Some(source) = &self.manifest.source {
+            say!("{}", format!("Using manifest {}", source.display()).cyan());
+        }
+
+        // 用户脚本以步骤名为第一个参数，退出码0表示成功
+        if let Some(script) = &self.script {
+            say!("{}", format!("Using script {}", script.display()).cyan());
+            return self.run("bash", &[&script.to_string_lossy(), step.id()]);
+        }
+
+        step.apply(self)
+    }
+
+    /// Adds a change to the record of the step that is currently running.
+    pub fn record_change(&self, change: Change) {
+        let current_step = self.current_step.lock().unwrap().clone();
+        if let Some(step) = current_step {
+            self.record_change_for(&step, change);
+        }
+    }
+
+    /// Adds a change to the record of `step`.
+    pub fn record_change_for(&self, step: &str, change: Change) {
+        if self.planner.is_some() {
+            return;
+        }
+
+        if let Some(changes) = &self.changes {
+            let mut changes = changes.lock().unwrap();
+            changes.record(step, change);
+            if let Err(e) = changes.save() {
+                say!("{}", format!("Warning: {}", e).yellow());
             }
-            
-            println!("{}", "Git installed".green());
-        } else {
-            println!("{}", "✓ Git already installed".green());
-        }
-        
-        // 简单的Git配置检查
-        let git_user_name = Command::new("git")
-            .args(["config", "--global", "user.name"])
-            .output()
-            .map_err(|e| format!("Failed to check git config: {}", e))?;
-        
-        if git_user_name.stdout.is_empty() {
-            println!("Git needs to be configured");
-            println!("{}", "Git configuration should be done manually.".yellow());
-            println!("Please run: git config --global user.name \"Your Name\"");
-            println!("Please run: git config --global user.email \"your.email@example.com\"");
-        } else {
-            println!("{}", "✓ Git already configured".green());
         }
-        
-        println!("{}", "Git setup completed".green());
-        Ok(true)
     }
-    
-    // 其他部分的实现（简化版，可根据需要扩展）
-    fn handle_ssh_section(&self) -> Result<bool, String> {
-        println!("{}", "\n==== SSH Key Generation ====\n".blue());
-        println!("{}", "This feature requires interactive input and is better performed via the original script.".yellow());
-        println!("Please run the original script directly for this feature.");
+
+    /// Reverts the changes recorded for `step`, newest first.
+    pub fn undo_section(&self, step: &dyn Step) -> Result<bool, SetupError> {
+        say!("{}", format!("\n==== Undoing {} ====\n", step.title()).blue());
+
+        let changes = match &self.changes {
+            Some(changes) => changes.lock().unwrap().changes(step.id()).to_vec(),
+            None => Vec::new(),
+        };
+
+        if changes.is_empty() {
+            say!("{}", format!("No recorded changes for {}", step.id()).yellow());
+            return Ok(true);
+        }
+
+        // 逐项撤销，已撤销的立即从记录中删除，失败的留待下次并在最后一并报告
+        let mut failures = Vec::new();
+        for change in changes.iter().rev() {
+            match self.revert(change) {
+                Ok(true) => self.forget_change(step.id(), change)?,
+                Ok(false) => failures.push(change.to_string()),
+                Err(e) => failures.push(format!("{}: {}", change, e)),
+            }
+        }
+
+        if !failures.is_empty() {
+            say!("{}", format!("Could not revert {} change(s) of {}:", failures.len(), step.id()).red());
+            for failure in failures.iter() {
+                say!("{}", format!("  {}", failure).red());
+            }
+            return Ok(false);
+        }
+
+        if self.planner.is_none() {
+            if let Some(journal) = &self.journal {
+                let mut journal = journal.lock().unwrap();
+                journal.forget(step.id());
+                journal.save()?;
+            }
+        }
+
         Ok(true)
     }
-    
-    fn handle_vscode_section(&self) -> Result<bool, String> {
-        println!("{}", "\n==== Installing Visual Studio Code ====\n".blue());
-        
-        // 检查VS Code是否已安装
-        let vscode_check = Command::new("which")
-            .arg("code")
-            .output()
-            .map_err(|e| format!("Failed to check VS Code: {}", e))?;
-        
-        if vscode_check.status.success() {
-            println!("{}", "✓ VS Code already installed".green());
-        } else {
-            println!("{}", "Installing VS Code...".cyan());
-            
-            // 使用Homebrew安装VS Code
-            let install_result = Command::new("brew")
-                .args(["install", "--cask", "visual-studio-code"])
-                .status()
-                .map_err(|e| format!("Failed to install VS Code: {}", e))?;
-            
-            if install_result.success() {
-                println!("{}", "VS Code installed".green());
-            } else {
-                return Err("Failed to install VS Code".to_string());
+
+    fn forget_change(&self, step: &str, change: &Change) -> Result<(), SetupError> {
+        if self.planner.is_some() {
+            return Ok(());
+        }
+
+        if let Some(changes) = &self.changes {
+            let mut changes = changes.lock().unwrap();
+            changes.remove(step, change);
+            changes.save()?;
+        }
+        Ok(())
+    }
+
+    fn revert(&self, change: &Change) -> Result<bool, SetupError> {
+        match change {
+            Change::DefaultsWrite { domain, key, previous: Some(previous) } => {
+                say!("Restoring {} {}...", domain, key);
+                self.run("defaults", &["write", domain, key, &previous.value_type, &previous.value])
+            }
+            Change::DefaultsWrite { domain, key, previous: None } => {
+                say!("Removing {} {}...", domain, key);
+                self.run("defaults", &["delete", domain, key])
+            }
+            Change::FileAppend { path, content } => {
+                say!("Removing added lines from {}...", path);
+                self.cut(path, content).map(|_| true)
+            }
+            Change::FileRemove { path, content } => {
+                say!("Restoring removed lines in {}...", path);
+                self.append(path, content).map(|_| true)
+            }
+            Change::LineReplace { path, previous, line } => {
+                say!("Restoring {} in {}...", previous, path);
+                self.swap_line(path, line, previous)
+            }
+            Change::PackageInstall {
+                kind: PackageKind::Npm,
+                name,
+                node_manager: Some(manager),
+            } => {
+                say!("Uninstalling {}...", name);
+                // 在安装时所用的 Node.js 中卸载
+                let nvm_script = node::nvm_script(&self.home_dir(), &self.platform().brew_prefix);
+                let args = node::bash_args(&manager.prelude(&nvm_script), &["npm", "uninstall", "-g", name]);
+                let args: Vec<&str> = args.iter().map(String::as_str).collect();
+                let success = self.run("bash", &args)?;
+                if !success {
+                    say!("{}", format!("Failed to uninstall {}", name).red());
+                }
+                Ok(success)
+            }
+            Change::PackageInstall { kind, name, .. } => {
+                say!("Uninstalling {}...", name);
+                let args: &[&str] = match kind {
+                    PackageKind::Formula => &["uninstall", name],
+                    PackageKind::Cask => &["uninstall", "--cask", name],
+                    PackageKind::Npm => &["uninstall", "-g", name],
+                    PackageKind::VscodeExtension => &["--uninstall-extension", name],
+                    PackageKind::Mas => &["uninstall", name],
+                };
+                let program = match kind {
+                    PackageKind::Formula | PackageKind::Cask => "brew",
+                    PackageKind::Npm => "npm",
+                    PackageKind::VscodeExtension => "code",
+                    PackageKind::Mas => "mas",
+                };
+                let success = self.run(program, args)?;
+                if !success {
+                    say!("{}", format!("Failed to uninstall {}", name).red());
+                }
+                Ok(success)
+            }
+            Change::ManagedBlock { path, name, previous } => {
+                say!("Restoring the {} block in {}...", name, path);
+                let current = self.read_file(path)?;
+                let updated = match previous {
+                    Some(previous) => managed_block::upsert(&current, name, previous),
+                    None => managed_block::remove(&current, name),
+                };
+                self.write_file(path, &updated).map(|_| true)
+            }
+            Change::GoEnv { key, previous } => {
+                say!("Restoring go env {}...", key);
+                match previous {
+                    Some(previous) => self.run("go", &["env", "-w", &format!("{}={}", key, previous)]),
+                    None => self.run("go", &["env", "-u", key]),
+                }
+            }
+            Change::GitConfig { key, previous } => {
+                say!("Restoring git config {}...", key);
+                match previous {
+                    Some(previous) => self.run("git", &["config", "--global", key, previous]),
+                    None => self.run("git", &["config", "--global", "--unset", key]),
+                }
             }
         }
-        
-        println!("{}", "VS Code setup completed".green());
-        Ok(true)
     }
-    
-    fn handle_node_section(&self) -> Result<bool, String> {
-        println!("{}", "\n==== Node.js Setup ====\n".blue());
-        println!("{}", "This feature requires interactive input and is better performed via the original script.".yellow());
-        println!("Please run the original script directly for this feature.");
-        Ok(true)
+
+    pub fn planner(&self) -> Option<&Planner> {
+        self.planner.as_ref()
+    }
+
+    /// Runs a read-only command and captures its output. Queries are
+    /// executed even in dry-run mode so the plan reflects the machine.
+    pub fn query(&self, program: &str, args: &[&str]) -> Result<CommandOutput, SetupError> {
+        self.runner.output(program, args).map_err(|e| SetupError::spawn(program, e))
+    }
+
+    /// Runs a command that changes the system and returns whether it
+    /// succeeded. In dry-run mode the command is only recorded.
+    pub fn run(&self, program: &str, args: &[&str]) -> Result<bool, SetupError> {
+        self.run_status(program, args).map(ExitStatus::success)
     }
-    
-    fn handle_iterm_section(&self) -> Result<bool, String> {
-        println!("{}", "\n==== Installing iTerm2 ====\n".blue());
-        
-        // 使用Homebrew安装iTerm2
-        let install_result = Command::new("brew")
-            .args(["install", "--cask", "iterm2"])
-            .status()
-            .map_err(|e| format!("Failed to install iTerm2: {}", e))?;
-        
-        if install_result.success() {
-            println!("{}", "iTerm2 installed".green());
+
+    /// Like [`ScriptHandler::run`], but an unsuccessful exit is an error.
+    pub fn run_checked(&self, program: &str, args: &[&str]) -> Result<(), SetupError> {
+        let status = self.run_status(program, args)?;
+        if status.success() {
+            Ok(())
         } else {
-            return Err("Failed to install iTerm2".to_string());
+            Err(SetupError::CommandFailed {
+                command: command_line(program, args),
+                code: status.code,
+            })
         }
-        
-        Ok(true)
     }
-    
-    fn handle_zsh_section(&self) -> Result<bool, String> {
-        println!("{}", "\n==== Installing Oh My Zsh ====\n".blue());
-        
-        // 检查Oh My Zsh是否已安装
-        let home = std::env::var("HOME").unwrap_or_else(|_| String::from("."));
-        let omz_path = format!("{}/.oh-my-zsh", home);
-        
-        if Path::new(&omz_path).exists() {
-            println!("{}", "✓ Oh My Zsh already installed".green());
+
+    /// Runs a command that changes the system and captures its output, e.g.
+    /// an API request. In dry-run mode the command is only recorded and
+    /// succeeds with no output.
+    pub fn run_output(&self, program: &str, args: &[&str]) -> Result<CommandOutput, SetupError> {
+        if let Some(planner) = &self.planner {
+            planner.record(Action::Command {
+                program: program.to_string(),
+                args: args.iter().map(|arg| arg.to_string()).collect(),
+            });
+            return Ok(CommandOutput::success(""));
+        }
+
+        let step = self.current_step.lock().unwrap().clone();
+        self.note_command(step.as_deref(), program, args);
+        self.runner.output(program, args).map_err(|e| SetupError::spawn(program, e))
+    }
+
+    /// Like [`ScriptHandler::run_checked`], but writes `input` to the
+    /// command's stdin, so a secret such as a passphrase never shows up in
+    /// the process list, the plan, the summary or events. Output is shown
+    /// only if the command fails.
+    pub fn run_checked_input(&self, program: &str, args: &[&str], input: &str) -> Result<(), SetupError> {
+        if let Some(planner) = &self.planner {
+            planner.record(Action::Command {
+                program: program.to_string(),
+                args: args.iter().map(|arg| arg.to_string()).collect(),
+            });
+            return Ok(());
+        }
+
+        // 输出中可能有口令提示，成功时不显示
+        let step = self.current_step.lock().unwrap().clone();
+        let lines = Mutex::new(Vec::new());
+        let status = self.stream_command(step.as_deref(), program, args, args, Some(input), &|_, line| {
+            lines.lock().unwrap().push(line.to_string());
+        })?;
+        if status.success() {
+            return Ok(());
+        }
+        for line in lines.into_inner().unwrap() {
+            say!("{}", line.dimmed());
+        }
+        Err(SetupError::CommandFailed {
+            command: command_line(program, args),
+            code: status.code,
+        })
+    }
+
+    fn run_status(&self, program: &str, args: &[&str]) -> Result<ExitStatus, SetupError> {
+        self.run_status_shown(program, args, args)
+    }
+
+    /// Runs `program` with `args`, showing it with `shown` instead.
+    fn run_status_shown(&self, program: &str, args: &[&str], shown: &[&str]) -> Result<ExitStatus, SetupError> {
+        if let Some(planner) = &self.planner {
+            planner.record(Action::Command {
+                program: program.to_string(),
+                args: shown.iter().map(|arg| arg.to_string()).collect(),
+            });
+            return Ok(ExitStatus { code: Some(0) });
+        }
+
+        let step = self.current_step.lock().unwrap().clone();
+        if self.events.is_none() {
+            // stdout 用于输出 JSON，子进程的输出转到 stderr
+            if crate::output::machine_readable() {
+                return self.stream_command(step.as_deref(), program, args, shown, None, &|_, line| eprintln!("{}", line));
+            }
+            self.note_command(step.as_deref(), program, shown);
+            return self.runner.status(program, args).map_err(|e| SetupError::spawn(program, e));
+        }
+
+        self.stream_command(step.as_deref(), program, args, shown, None, &|_, _| {})
+    }
+
+    /// Runs a command that changes the system for `step`, passing each line
+    /// of its output to `on_line` instead of the terminal. In dry-run mode
+    /// the command is only recorded.
+    pub fn run_logged(
+        &self,
+        step: &str,
+        program: &str,
+        args: &[&str],
+        on_line: &(dyn Fn(OutputStream, &str) + Sync),
+    ) -> Result<bool, SetupError> {
+        if let Some(planner) = &self.planner {
+            planner.record_for(
+                step,
+                Action::Command {
+                    program: program.to_string(),
+                    args: args.iter().map(|arg| arg.to_string()).collect(),
+                },
+            );
+            return Ok(true);
+        }
+
+        self.stream_command(Some(step), program, args, args, None, on_line)
+            .map(ExitStatus::success)
+    }
+
+    /// Adds a command to the summary of `step`, and returns it as a line.
+    fn note_command(&self, step: Option<&str>, program: &str, args: &[&str]) -> String {
+        let command = command_line(program, args);
+        self.commands
+            .lock()
+            .unwrap()
+            .entry(step.unwrap_or_default().to_string())
+            .or_default()
+            .push(command.clone());
+        command
+    }
+
+    fn stream_command(
+        &self,
+        step: Option<&str>,
+        program: &str,
+        args: &[&str],
+        shown: &[&str],
+        input: Option<&str>,
+        on_line: &(dyn Fn(OutputStream, &str) + Sync),
+    ) -> Result<ExitStatus, SetupError> {
+        let command = self.note_command(step, program, shown);
+        let on_line = |stream, line: &str| {
+            on_line(stream, line);
+            if let Some(events) = &self.events {
+                events.emit(&Event::CommandOutput {
+                    step: step.map(str::to_string),
+                    command: command.clone(),
+                    stream,
+                    line: line.to_string(),
+                });
+            }
+        };
+        match input {
+            Some(input) => self.runner.stream_input(program, args, input, &on_line),
+            None => self.runner.stream(program, args, &on_line),
+        }
+        .map_err(|e| SetupError::spawn(program, e))
+    }
+
+    /// Replaces the file at `path` with `content`.
+    pub fn write_file(&self, path: &str, content: &str) -> Result<(), SetupError> {
+        if let Some(planner) = &self.planner {
+            planner.record(Action::WriteFile {
+                path: path.to_string(),
+                content: content.to_string(),
+            });
+            return Ok(());
+        }
+
+        fs::write(path, content).map_err(|e| SetupError::io(format!("Could not write {}", path), e))
+    }
+
+    /// Content of the file at `path`, or an empty string if it does not exist.
+    pub fn read_file(&self, path: &str) -> Result<String, SetupError> {
+        match fs::read_to_string(path) {
+            Ok(content) => Ok(content),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+            Err(e) => Err(SetupError::io(format!("Could not read {}", path), e)),
+        }
+    }
+
+    /// Sets the [managed block](managed_block) `name` in the file at `path`
+    /// to `body`, creating the file if needed.
+    pub fn write_block(&self, path: &str, name: &str, body: &str) -> Result<(), SetupError> {
+        self.set_block(path, name, body, managed_block::upsert)
+    }
+
+    /// Like [`ScriptHandler::write_block`], but a new block goes at the
+    /// start of the file; see [`managed_block::upsert_first`].
+    pub fn write_block_first(&self, path: &str, name: &str, body: &str) -> Result<(), SetupError> {
+        self.set_block(path, name, body, managed_block::upsert_first)
+    }
+
+    fn set_block(
+        &self,
+        path: &str,
+        name: &str,
+        body: &str,
+        upsert: fn(&str, &str, &str) -> String,
+    ) -> Result<(), SetupError> {
+        let current = self.read_file(path)?;
+        let previous = managed_block::find(&current, name).map(str::to_string);
+        if previous.as_deref() == Some(body.trim_end_matches('\n')) {
+            return Ok(());
+        }
+
+        self.write_file(path, &upsert(&current, name, body))?;
+        self.record_change(Change::ManagedBlock {
+            path: path.to_string(),
+            name: name.to_string(),
+            previous,
+        });
+        Ok(())
+    }
+
+    /// Copies the file at `path` to `<path>.macdevkit.bak` unless a backup
+    /// already exists, so the first backup keeps the original.
+    pub fn backup_file(&self, path: &str) -> Result<(), SetupError> {
+        let backup = format!("{}.macdevkit.bak", path);
+        if !Path::new(path).exists() || Path::new(&backup).exists() {
+            return Ok(());
+        }
+
+        if let Some(planner) = &self.planner {
+            planner.record(Action::CopyFile {
+                from: path.to_string(),
+                to: backup,
+            });
+            return Ok(());
+        }
+
+        fs::copy(path, &backup).map_err(|e| SetupError::io(format!("Could not back up {}", path), e))?;
+        Ok(())
+    }
+
+    /// Appends `content` to the file at `path`, creating it if needed.
+    pub fn append_to_file(&self, path: &str, content: &str) -> Result<(), SetupError> {
+        self.append(path, content)?;
+        self.record_change(Change::FileAppend {
+            path: path.to_string(),
+            content: content.to_string(),
+        });
+        Ok(())
+    }
+
+    fn append(&self, path: &str, content: &str) -> Result<(), SetupError> {
+        if let Some(planner) = &self.planner {
+            planner.record(Action::AppendFile {
+                path: path.to_string(),
+                content: content.to_string(),
+            });
+            return Ok(());
+        }
+
+        // 使用标准文件操作方式追加内容
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| SetupError::io(format!("Could not open {}", path), e))?;
+        file.write_all(content.as_bytes())
+            .map_err(|e| SetupError::io(format!("Could not update {}", path), e))
+    }
+
+    /// Removes the last occurrence of `content` from `path`, remembering it
+    /// for `undo`.
+    pub fn remove_from_file(&self, path: &str, content: &str) -> Result<(), SetupError> {
+        if self.cut(path, content)? {
+            self.record_change(Change::FileRemove {
+                path: path.to_string(),
+                content: content.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Removes the last occurrence of `content` from `path`; returns false
+    /// if the file does not contain it.
+    fn cut(&self, path: &str, content: &str) -> Result<bool, SetupError> {
+        if let Some(planner) = &self.planner {
+            planner.record(Action::RemoveFromFile {
+                path: path.to_string(),
+                content: content.to_string(),
+            });
             return Ok(true);
         }
-        
-        println!("{}", "Installing Oh My Zsh...".cyan());
-        println!("{}", "This requires running a curl command and is better performed via the original script.".yellow());
-        println!("To install Oh My Zsh, please run:");
-        println!("sh -c \"$(curl -fsSL https://raw.githubusercontent.com/ohmyzsh/ohmyzsh/master/tools/install.sh)\"");
-        
+
+        let current = match fs::read_to_string(path) {
+            Ok(current) => current,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(SetupError::io(format!("Could not read {}", path), e)),
+        };
+
+        let Some(index) = current.rfind(content) else {
+            return Ok(false);
+        };
+        let updated = format!("{}{}", &current[..index], &current[index + content.len()..]);
+        fs::write(path, updated).map_err(|e| SetupError::io(format!("Could not update {}", path), e))?;
         Ok(true)
     }
-    
-    fn handle_docker_section(&self) -> Result<bool, String> {
-        println!("{}", "\n==== Installing Docker ====\n".blue());
-        
-        // 使用Homebrew安装Docker
-        let install_result = Command::new("brew")
-            .args(["install", "--cask", "docker"])
-            .status()
-            .map_err(|e| format!("Failed to install Docker: {}", e))?;
-        
-        if install_result.success() {
-            println!("{}", "Docker installed".green());
-            println!("Please launch Docker Desktop to complete the setup.");
-        } else {
-            return Err("Failed to install Docker".to_string());
+
+    /// Replaces the first line of the file at `path` that equals `from` with
+    /// `to`, remembering the old line for `undo`. Returns false if no line
+    /// matches.
+    pub fn replace_line(&self, path: &str, from: &str, to: &str) -> Result<bool, SetupError> {
+        let replaced = self.swap_line(path, from, to)?;
+        if replaced {
+            self.record_change(Change::LineReplace {
+                path: path.to_string(),
+                previous: from.to_string(),
+                line: to.to_string(),
+            });
+        }
+        Ok(replaced)
+    }
+
+    fn swap_line(&self, path: &str, from: &str, to: &str) -> Result<bool, SetupError> {
+        let current = self.read_file(path)?;
+        let mut replaced = false;
+        let lines: Vec<&str> = current
+            .lines()
+            .map(|line| {
+                if !replaced && line == from {
+                    replaced = true;
+                    to
+                } else {
+                    line
+                }
+            })
+            .collect();
+        if !replaced {
+            return Ok(false);
+        }
+
+        let mut updated = lines.join("\n");
+        if current.ends_with('\n') {
+            updated.push('\n');
         }
-        
+        self.write_file(path, &updated)?;
         Ok(true)
     }
-    
-    fn handle_devtools_section(&self) -> Result<bool, String> {
-        println!("{}", "\n==== Installing Developer Tools ====\n".blue());
-        
-        // 简单的开发工具安装列表
-        let tools = [
-            "jq", "ripgrep", "fd", "bat", "exa", "httpie", "htop"
-        ];
-        
-        for tool in tools.iter() {
-            println!("Installing {}...", tool);
-            
-            let install_result = Command::new("brew")
-                .args(["install", tool])
-                .status()
-                .map_err(|e| format!("Failed to install {}: {}", tool, e))?;
-            
-            if install_result.success() {
-                println!("{}", format!("✓ {} installed", tool).green());
-            } else {
-                println!("{}", format!("Failed to install {}", tool).red());
+
+    /// Writes a `defaults` value, remembering the previous one for `undo`.
+    pub fn defaults_write(&self, domain: &str, key: &str, value_type: &str, value: &str) -> Result<bool, SetupError> {
+        let previous = self.defaults_read(domain, key);
+        let success = self.run("defaults", &["write", domain, key, value_type, value])?;
+
+        match previous {
+            Some(previous) if success => self.record_change(Change::DefaultsWrite {
+                domain: domain.to_string(),
+                key: key.to_string(),
+                previous,
+            }),
+            None if success => {
+                say!("{}", format!("Warning: previous value of {} {} cannot be restored by undo", domain, key).yellow());
             }
+            _ => {}
         }
-        
-        println!("{}", "Developer tools installation completed".green());
-        Ok(true)
+        Ok(success)
     }
-    
-    fn handle_apps_section(&self) -> Result<bool, String> {
-        println!("{}", "\n==== Installing Applications ====\n".blue());
-        
-        println!("{}", "This feature requires interactive selection and is better performed via the original script.".yellow());
-        println!("Please run the original script directly for this feature.");
-        
-        Ok(true)
+
+    /// Sets a global Git config key, remembering the previous value for `undo`.
+    pub fn git_config_global(&self, key: &str, value: &str) -> Result<bool, SetupError> {
+        let output = self.query("git", &["config", "--global", "--get", key])?;
+        let previous = output.success.then(|| output.stdout.trim_end_matches('\n').to_string());
+        if previous.as_deref() == Some(value) {
+            return Ok(true);
+        }
+
+        let success = self.run("git", &["config", "--global", key, value])?;
+        if success {
+            self.record_change(Change::GitConfig {
+                key: key.to_string(),
+                previous,
+            });
+        }
+        Ok(success)
     }
-    
-    fn handle_macos_section(&self) -> Result<bool, String> {
-        println!("{}", "\n==== Configuring macOS Settings ====\n".blue());
-        
-        // 示例：配置Finder显示隐藏文件
-        println!("Setting Finder to show hidden files...");
-        let _ = Command::new("defaults")
-            .args(["write", "com.apple.finder", "AppleShowAllFiles", "-bool", "true"])
-            .status();
-        
-        println!("Setting Finder to show path bar...");
-        let _ = Command::new("defaults")
-            .args(["write", "com.apple.finder", "ShowPathbar", "-bool", "true"])
-            .status();
-        
-        println!("Setting Finder to show status bar...");
-        let _ = Command::new("defaults")
-            .args(["write", "com.apple.finder", "ShowStatusBar", "-bool", "true"])
-            .status();
-        
-        println!("Disabling press-and-hold for keys in favor of key repeat...");
-        let _ = Command::new("defaults")
-            .args(["write", "NSGlobalDomain", "ApplePressAndHoldEnabled", "-bool", "false"])
-            .status();
-        
-        println!("Setting a faster keyboard repeat rate...");
-        let _ = Command::new("defaults")
-            .args(["write", "NSGlobalDomain", "KeyRepeat", "-int", "2"])
-            .status();
-        
-        let _ = Command::new("defaults")
-            .args(["write", "NSGlobalDomain", "InitialKeyRepeat", "-int", "15"])
-            .status();
-        
-        println!("Disabling auto-correct...");
-        let _ = Command::new("defaults")
-            .args(["write", "NSGlobalDomain", "NSAutomaticSpellingCorrectionEnabled", "-bool", "false"])
-            .status();
-        
-        println!("Restarting Finder to apply changes...");
-        let _ = Command::new("killall")
-            .arg("Finder")
-            .status();
-        
-        let _ = Command::new("killall")
-            .arg("SystemUIServer")
-            .status();
-        
-        println!("{}", "macOS settings configured".green());
-        Ok(true)
+
+    /// Sets a Go environment variable with `go env -w`, remembering the
+    /// previous value for `undo`.
+    pub fn go_env_write(&self, key: &str, value: &str) -> Result<bool, SetupError> {
+        // go env 总会输出默认值，只有写在 GOENV 文件里的才是用户设置的
+        let goenv = self.query("go", &["env", "GOENV"])?.stdout.trim().to_string();
+        let prefix = format!("{}=", key);
+        let previous = self
+            .read_file(&goenv)?
+            .lines()
+            .find_map(|line| line.strip_prefix(&prefix).map(str::to_string));
+        if previous.as_deref() == Some(value) {
+            return Ok(true);
+        }
+
+        let success = self.run("go", &["env", "-w", &format!("{}={}", key, value)])?;
+        if success {
+            self.record_change(Change::GoEnv {
+                key: key.to_string(),
+                previous,
+            });
+        }
+        Ok(success)
     }
-    
-    fn handle_workspace_section(&self) -> Result<bool, String> {
-        println!("{}", "\n==== Creating Development Workspace ====\n".blue());
-        
-        let home = std::env::var("HOME").unwrap_or_else(|_| String::from("."));
-        let workspace_path = format!("{}/Workspace", home);
-        
-        // 创建Workspace目录
-        match fs::create_dir_all(&workspace_path) {
-            Ok(_) => {
-                println!("{}", format!("✓ Created workspace directory at {}", workspace_path).green());
-                Ok(true)
-            },
-            Err(e) => {
-                Err(format!("Failed to create workspace directory: {}", e))
+
+    /// Current value of a `defaults` key: `Some(None)` if the key is not set,
+    /// `None` if its value has a type that `defaults write` cannot restore.
+    fn defaults_read(&self, domain: &str, key: &str) -> Option<Option<PreviousDefault>> {
+        let type_output = self.query("defaults", &["read-type", domain, key]).ok()?;
+        if !type_output.success {
+            return Some(None);
+        }
+
+        // 输出格式为 "Type is boolean"
+        let value_type = match type_output.stdout.trim().trim_start_matches("Type is ") {
+            "boolean" => "-bool",
+            "integer" => "-int",
+            "float" => "-float",
+            "string" => "-string",
+            _ => return None,
+        };
+
+        let value_output = self.query("defaults", &["read", domain, key]).ok()?;
+        let value = value_output.stdout.trim_end_matches('\n').to_string();
+        let value = match (value_type, value.as_str()) {
+            ("-bool", "1") => "true".to_string(),
+            ("-bool", "0") => "false".to_string(),
+            _ => value,
+        };
+
+        Some(Some(PreviousDefault {
+            value_type: value_type.to_string(),
+            value,
+        }))
+    }
+
+    pub fn create_dir_all(&self, path: &str) -> Result<(), SetupError> {
+        if let Some(planner) = &self.planner {
+            planner.record(Action::CreateDir {
+                path: path.to_string(),
+            });
+            return Ok(());
+        }
+
+        fs::create_dir_all(path).map_err(|e| SetupError::io(format!("Could not create {}", path), e))
+    }
+
+    /// Asks a yes/no question; see [`Prompter::confirm`].
+    pub fn confirm(&self, prompt: &str, default: bool, answer: Option<bool>) -> Result<bool, SetupError> {
+        self.prompter.confirm(prompt, default, answer)
+    }
+
+    /// Lets the user tick some of `items`; see [`Prompter::choose`].
+    pub fn choose(&self, prompt: &str, items: &[&str], defaults: &[bool]) -> Result<Vec<usize>, SetupError> {
+        self.prompter.choose(prompt, items, defaults)
+    }
+
+    /// Asks for a line of text; see [`Prompter::input`]. A dry run does not
+    /// ask and plans with the placeholder `<key>` instead.
+    pub fn input(&self, prompt: &str, key: &str, answer: Option<&str>) -> Result<String, SetupError> {
+        if self.planner.is_some() && answer.is_none() {
+            return Ok(format!("<{}>", key));
+        }
+        self.prompter.input(prompt, key, answer)
+    }
+
+    /// Asks for a secret; see [`Prompter::password`]. A dry run does not ask
+    /// and plans with an empty secret instead.
+    pub fn password(&self, prompt: &str, key: &str, answer: Option<&str>) -> Result<String, SetupError> {
+        if self.planner.is_some() && answer.is_none() {
+            return Ok(String::new());
+        }
+        self.prompter.password(prompt, key, answer)
+    }
+
+    /// Lets the user pick one of `items`; see [`Prompter::select`].
+    pub fn select(&self, prompt: &str, items: &[&str]) -> Result<usize, SetupError> {
+        self.prompter.select(prompt, items)
+    }
+
+    /// Preset answer to the restart question; see [`Prompter::restart_answer`].
+    pub fn restart_answer(&self) -> Option<bool> {
+        self.prompter.restart_answer()
+    }
+
+    /// Values from the `--answers` file.
+    pub fn answers(&self) -> &Answers {
+        self.prompter.answers()
+    }
+
+    pub fn command_exists(&self, command: &str) -> bool {
+        self.runner.exists(command)
+    }
+
+    /// The Mac the steps run on, detected on first use.
+    pub fn platform(&self) -> Arc<Platform> {
+        if let Some(platform) = self.platform.lock().unwrap().as_ref() {
+            return platform.clone();
+        }
+        let platform = Arc::new(Platform::detect(self));
+        *self.platform.lock().unwrap() = Some(platform.clone());
+        platform
+    }
+
+    /// Puts the native Homebrew's `bin` first on `PATH` after Homebrew has
+    /// been installed by this run, so later steps find `brew` and what it
+    /// installs without a new shell, and detects the platform again.
+    pub fn use_installed_brew(&self) {
+        if self.planner.is_some() {
+            return;
+        }
+
+        let bin = format!("{}/bin", self.platform().native_brew_prefix());
+        let path = std::env::var("PATH").unwrap_or_default();
+        if !path.split(':').any(|dir| dir == bin) {
+            std::env::set_var("PATH", format!("{}:{}", bin, path));
+        }
+        *self.platform.lock().unwrap() = None;
+    }
+
+    pub fn home_dir(&self) -> String {
+        if let Some(home) = &self.home {
+            return home.clone();
+        }
+        std::env::var("HOME").unwrap_or_else(|_| String::from("."))
+    }
+
+    /// Returns true if the formula (or cask) is already installed.
+    pub fn brew_installed(&self, name: &str, cask: bool) -> bool {
+        let kind = if cask { "--cask" } else { "--formula" };
+        self.query("brew", &["list", kind, "--versions", name])
+            .map(|output| output.success)
+            .unwrap_or(false)
+    }
+
+    /// Installs a formula (or cask) unless it is already installed.
+    pub fn brew_install(&self, name: &str, cask: bool) -> Result<bool, SetupError> {
+        if self.brew_installed(name, cask) {
+            say!("{}", format!("✓ {} already installed", name).green());
+            return Ok(true);
+        }
+
+        say!("Installing {}...", name);
+
+        let args: &[&str] = if cask {
+            &["install", "--cask", name]
+        } else {
+            &["install", name]
+        };
+        let success = self.run("brew", args)?;
+
+        if success {
+            self.report_installed(name);
+            self.record_change(Change::PackageInstall {
+                kind: if cask { PackageKind::Cask } else { PackageKind::Formula },
+                name: name.to_string(),
+                node_manager: None,
+            });
+        } else {
+            say!("{}", format!("Failed to install {}", name).red());
+        }
+        Ok(success)
+    }
+
+    /// Reports that `names` were installed, or in a dry run that they
+    /// would be.
+    pub fn report_installed(&self, names: &str) {
+        if self.planner.is_some() {
+            say!("{}", format!("Would install {}", names).cyan());
+        } else {
+            say!("{}", format!("✓ {} installed", names).green());
+        }
+    }
+
+    pub fn brew_tap(&self, tap: &str) -> Result<bool, SetupError> {
+        say!("Tapping {}...", tap);
+        let tapped = self.run("brew", &["tap", tap])?;
+
+        if !tapped {
+            say!("{}", format!("Failed to tap {}", tap).red());
+        }
+        Ok(tapped)
+    }
+
+    /// Installs a Mac App Store app with `mas`, installing `mas` first if needed.
+    pub fn mas_install(&self, name: &str, id: u64) -> Result<bool, SetupError> {
+        if !self.command_exists("mas") && !self.brew_install("mas", false)? {
+            return Err("Failed to install mas".into());
+        }
+
+        let id = id.to_string();
+        let already_installed = self
+            .query("mas", &["list"])
+            .map(|output| output.stdout.lines().any(|line| line.split_whitespace().next() == Some(id.as_str())))
+            .unwrap_or(false);
+        if already_installed {
+            say!("{}", format!("✓ {} already installed", name).green());
+            return Ok(true);
+        }
+
+        say!("Installing {} from the App Store...", name);
+        let success = self.run("mas", &["install", &id])?;
+
+        if success {
+            self.report_installed(name);
+            self.record_change(Change::PackageInstall {
+                kind: PackageKind::Mas,
+                name: id,
+                node_manager: None,
+            });
+        } else {
+            say!("{}", format!("Failed to install {}", name).red());
+        }
+        Ok(success)
+    }
+
+    pub fn manifest(&self) -> &Manifest {
+        &self.manifest
+    }
+
+    /// Expands a leading `~/` to the home directory.
+    pub fn expand_home(&self, path: &str) -> String {
+        match path.strip_prefix("~/") {
+            Some(rest) => format!("{}/{}", self.home_dir(), rest),
+            None if path == "~" => self.home_dir(),
+            None => path.to_string(),
+        }
+    }
+}
+
+/// A command as it would be typed in a shell, for messages and reports.
+fn command_line(program: &str, args: &[&str]) -> String {
+    Action::Command {
+        program: program.to_string(),
+        args: args.iter().map(|arg| arg.to_string()).collect(),
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::install::Batch;
+    use crate::runner::RecordingRunner;
+    use crate::test_support::{handler, TempHome};
+
+    /// A step that installs `package` with `fake-install`, and remembers
+    /// how the journal saw the step before it.
+    struct Fake {
+        id: &'static str,
+        package: Option<&'static str>,
+        seen: Mutex<Option<StepStatus>>,
+    }
+
+    impl Fake {
+        fn new(id: &'static str, package: Option<&'static str>) -> Self {
+            Fake {
+                id,
+                package,
+                seen: Mutex::new(None),
             }
         }
     }
-} 
\ No newline at end of file
+
+    impl Step for Fake {
+        fn id(&self) -> &'static str {
+            self.id
+        }
+
+        fn title(&self) -> &'static str {
+            self.id
+        }
+
+        fn description(&self) -> &'static str {
+            self.id
+        }
+
+        fn check(&self, _handler: &ScriptHandler) -> bool {
+            false
+        }
+
+        fn apply(&self, handler: &ScriptHandler) -> Result<bool, SetupError> {
+            *self.seen.lock().unwrap() = handler.journal_status("first");
+            Ok(true)
+        }
+
+        fn packages(&self, _handler: &ScriptHandler) -> Vec<Batch> {
+            self.package
+                .map(|name| {
+                    Batch::new(self.id, PackageKind::Formula, vec![name.to_string()], |names| {
+                        ("fake-install".to_string(), names.to_vec())
+                    })
+                })
+                .into_iter()
+                .collect()
+        }
+    }
+
+    /// Events written to a buffer the test can read.
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Buffer {
+        fn events(&self) -> Vec<serde_json::Value> {
+            let content = String::from_utf8(self.0.lock().unwrap().clone()).unwrap();
+            content.lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+        }
+    }
+
+    #[test]
+    fn steps_are_recorded_before_the_package_install() {
+        let home = TempHome::new("run-sections");
+        let runner = Arc::new(RecordingRunner::new().respond("fake-install", CommandOutput::failure("no such formula")));
+        let buffer = Buffer::default();
+        let handler = handler(&runner, &home)
+            .with_journal(Journal::new(home.path.join("journal.json")))
+            .with_events(EventStream::new(Box::new(buffer.clone())));
+        let (first, second) = (Fake::new("first", Some("jq")), Fake::new("second", None));
+
+        let results = handler.run_sections(&[&first, &second]);
+        assert!(matches!(results[..], [Ok(false), Ok(true)]));
+
+        // 第二步运行时，第一步已在日志中，软件包未装时 resume 会重新运行它
+        assert_eq!(*second.seen.lock().unwrap(), Some(StepStatus::Failed));
+        assert_eq!(handler.journal_status("first"), Some(StepStatus::Failed));
+        assert_eq!(handler.journal_status("second"), Some(StepStatus::Done));
+
+        let events: Vec<_> = buffer
+            .events()
+            .into_iter()
+            .filter(|event| event["event"] != "command-output")
+            .map(|event| format!("{} {}", event["event"].as_str().unwrap(), event["step"].as_str().unwrap_or("")))
+            .collect();
+        assert_eq!(
+            events,
+            [
+                "step-started first",
+                "step-finished first",
+                "step-started second",
+                "step-finished second",
+                "install-started ",
+                "install-finished ",
+            ]
+        );
+        let install = buffer.events().pop().unwrap();
+        assert_eq!(install["failed"], serde_json::json!(["first"]));
+
+        let summary = handler.summary();
+        assert_eq!(summary.steps[0].status, StepStatus::Failed);
+        assert_eq!(summary.steps[0].commands, ["fake-install jq"]);
+        assert_eq!(summary.steps[1].status, StepStatus::Done);
+    }
+
+    #[test]
+    fn undo_goes_on_after_a_failure_and_keeps_only_what_failed() {
+        let home = TempHome::new("undo-partial");
+        let runner = Arc::new(RecordingRunner::new().respond("go env", CommandOutput::failure("go: not found")));
+        let mut changes = ChangeLog::open(home.path.join("changes.json")).unwrap();
+        let go_env = Change::GoEnv {
+            key: "GOPROXY".to_string(),
+            previous: None,
+        };
+        changes.record("first", Change::GitConfig {
+            key: "pull.rebase".to_string(),
+            previous: None,
+        });
+        changes.record("first", go_env.clone());
+        changes.record("first", Change::GitConfig {
+            key: "fetch.prune".to_string(),
+            previous: None,
+        });
+        let handler = handler(&runner, &home).with_changes(changes);
+
+        assert!(!handler.undo_section(&Fake::new("first", None)).unwrap());
+        let lines = runner.command_lines();
+        assert!(lines.contains(&"git config --global --unset pull.rebase".to_string()));
+        assert!(lines.contains(&"git config --global --unset fetch.prune".to_string()));
+
+        let saved = ChangeLog::open(home.path.join("changes.json")).unwrap();
+        assert_eq!(saved.changes("first"), [go_env]);
+    }
+}
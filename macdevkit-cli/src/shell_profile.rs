@@ -0,0 +1,202 @@
+//! Shell startup files and the blocks MacDevKit keeps in them.
+//!
+//! Every concern, such as putting Homebrew on `PATH` or loading nvm, owns
+//! one [managed block](crate::managed_block) per file. Writing the block
+//! again rewrites it in place, so rerunning a step never duplicates lines.
+
+use std::path::Path;
+
+use crate::error::SetupError;
+use crate::managed_block;
+use crate::script_handler::ScriptHandler;
+
+/// A shell startup file MacDevKit edits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShellProfile {
+    Zprofile,
+    Zshrc,
+    BashProfile,
+    FishConfig,
+}
+
+impl ShellProfile {
+    pub fn path(self, home: &str) -> String {
+        match self {
+            ShellProfile::Zprofile => format!("{}/.zprofile", home),
+            ShellProfile::Zshrc => format!("{}/.zshrc", home),
+            ShellProfile::BashProfile => format!("{}/.bash_profile", home),
+            ShellProfile::FishConfig => format!("{}/.config/fish/config.fish", home),
+        }
+    }
+
+    pub fn is_fish(self) -> bool {
+        self == ShellProfile::FishConfig
+    }
+
+    /// Zsh is the macOS default, so its files are created if needed; bash
+    /// and fish files are only edited if they already exist.
+    fn create_if_missing(self) -> bool {
+        matches!(self, ShellProfile::Zprofile | ShellProfile::Zshrc)
+    }
+}
+
+/// When the shell needs to run a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Once per login shell: `PATH` and environment variables.
+    Login,
+    /// In every interactive shell: shell functions such as nvm.
+    Interactive,
+}
+
+impl Stage {
+    /// macOS terminals start bash as a login shell, so `.bash_profile`
+    /// serves both stages.
+    fn profiles(self) -> &'static [ShellProfile] {
+        match self {
+            Stage::Login => &[ShellProfile::Zprofile, ShellProfile::BashProfile, ShellProfile::FishConfig],
+            Stage::Interactive => &[ShellProfile::Zshrc, ShellProfile::BashProfile, ShellProfile::FishConfig],
+        }
+    }
+}
+
+/// A block of shell code and the startup files it belongs in.
+#[derive(Debug, Clone)]
+pub struct ProfileBlock {
+    pub name: &'static str,
+    pub stage: Stage,
+    /// Body for zsh and bash.
+    pub posix: String,
+    /// Body for fish, `None` if the block has no fish equivalent.
+    pub fish: Option<String>,
+    /// Text that earlier versions appended without markers; it is removed
+    /// when the block is written.
+    pub legacy: Vec<String>,
+}
+
+impl ProfileBlock {
+    pub fn new(name: &'static str, stage: Stage, posix: impl Into<String>) -> Self {
+        ProfileBlock {
+            name,
+            stage,
+            posix: posix.into(),
+            fish: None,
+            legacy: Vec::new(),
+        }
+    }
+
+    pub fn fish(mut self, body: impl Into<String>) -> Self {
+        self.fish = Some(body.into());
+        self
+    }
+
+    pub fn legacy(mut self, content: impl Into<String>) -> Self {
+        self.legacy.push(content.into());
+        self
+    }
+}
+
+/// Writes `block` to the startup files for its stage, backing up each file
+/// before MacDevKit first changes it. Returns the files written.
+pub fn write(handler: &ScriptHandler, block: &ProfileBlock) -> Result<Vec<String>, SetupError> {
+    let home = handler.home_dir();
+    let mut written = Vec::new();
+
+    for &profile in block.stage.profiles() {
+        let path = profile.path(&home);
+        let body = match (profile.is_fish(), &block.fish) {
+            (false, _) => &block.posix,
+            (true, Some(fish)) => fish,
+            (true, None) => continue,
+        };
+        if !profile.create_if_missing() && !Path::new(&path).exists() {
+            continue;
+        }
+
+        handler.backup_file(&path)?;
+        // 只在第一次写入时清理旧版本追加的内容，之后同样的文本已在块内
+        let current = handler.read_file(&path)?;
+        if managed_block::find(&current, block.name).is_none() {
+            for legacy in block.legacy.iter().filter(|legacy| current.contains(legacy.as_str())) {
+                handler.remove_from_file(&path, legacy)?;
+            }
+        }
+        handler.write_block(&path, block.name, body)?;
+        written.push(path);
+    }
+
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::runner::RecordingRunner;
+    use crate::test_support::{handler, TempHome};
+
+    fn read(home: &TempHome, relative: &str) -> String {
+        std::fs::read_to_string(home.join(relative)).unwrap()
+    }
+
+    #[test]
+    fn rerunning_leaves_one_block_per_file() {
+        let home = TempHome::new("profile-rerun");
+        let handler = handler(&Arc::new(RecordingRunner::new()), &home);
+        let login = ProfileBlock::new("homebrew", Stage::Login, "eval \"$(/opt/homebrew/bin/brew shellenv)\"");
+        let interactive = ProfileBlock::new("nvm", Stage::Interactive, "source nvm.sh");
+
+        for _ in 0..2 {
+            assert_eq!(write(&handler, &login).unwrap(), [home.join(".zprofile")]);
+            assert_eq!(write(&handler, &interactive).unwrap(), [home.join(".zshrc")]);
+        }
+        assert_eq!(read(&home, ".zprofile").matches("# >>> macdevkit homebrew >>>").count(), 1);
+        assert_eq!(read(&home, ".zprofile").matches("brew shellenv").count(), 1);
+        assert_eq!(read(&home, ".zshrc").matches("# >>> macdevkit nvm >>>").count(), 1);
+        assert!(!read(&home, ".zshrc").contains("homebrew"));
+    }
+
+    #[test]
+    fn backup_is_made_once_before_the_first_change() {
+        let home = TempHome::new("profile-backup");
+        std::fs::write(home.join(".zshrc"), "export EDITOR=vim\n").unwrap();
+        let handler = handler(&Arc::new(RecordingRunner::new()), &home);
+
+        write(&handler, &ProfileBlock::new("nvm", Stage::Interactive, "source nvm.sh")).unwrap();
+        write(&handler, &ProfileBlock::new("nvm", Stage::Interactive, "source other.sh")).unwrap();
+        assert_eq!(read(&home, ".zshrc.macdevkit.bak"), "export EDITOR=vim\n");
+        assert!(read(&home, ".zshrc").contains("source other.sh"));
+        // .zprofile 原本不存在，无需备份
+        write(&handler, &ProfileBlock::new("homebrew", Stage::Login, "eval")).unwrap();
+        assert!(!std::path::Path::new(&home.join(".zprofile.macdevkit.bak")).exists());
+    }
+
+    #[test]
+    fn bash_and_fish_files_are_only_edited_if_they_exist() {
+        let home = TempHome::new("profile-shells");
+        std::fs::create_dir_all(home.join(".config/fish")).unwrap();
+        std::fs::write(home.join(".config/fish/config.fish"), "").unwrap();
+        let handler = handler(&Arc::new(RecordingRunner::new()), &home);
+        let block = ProfileBlock::new("homebrew", Stage::Login, "eval \"$(brew shellenv)\"").fish("eval (brew shellenv)");
+
+        assert_eq!(write(&handler, &block).unwrap(), [home.join(".zprofile"), home.join(".config/fish/config.fish")]);
+        assert!(read(&home, ".config/fish/config.fish").contains("eval (brew shellenv)"));
+        assert!(!std::path::Path::new(&home.join(".bash_profile")).exists());
+    }
+
+    #[test]
+    fn legacy_lines_are_replaced_by_the_block() {
+        let home = TempHome::new("profile-legacy");
+        std::fs::write(home.join(".zprofile"), "export A=1\n\neval \"$(brew shellenv)\"\n").unwrap();
+        let handler = handler(&Arc::new(RecordingRunner::new()), &home);
+        let block = ProfileBlock::new("homebrew", Stage::Login, "eval \"$(brew shellenv)\"").legacy("\neval \"$(brew shellenv)\"\n");
+
+        write(&handler, &block).unwrap();
+        write(&handler, &block).unwrap();
+        let zprofile = read(&home, ".zprofile");
+        assert!(zprofile.starts_with("export A=1\n"));
+        assert_eq!(zprofile.matches("brew shellenv").count(), 1);
+        assert_eq!(managed_block::find(&zprofile, "homebrew"), Some("eval \"$(brew shellenv)\""));
+    }
+}
@@ -0,0 +1,434 @@
+//! Commit signing with an SSH key or a GPG key.
+//!
+//! With SSH, Git signs with the public key file of a key from the `ssh`
+//! step and verifies signatures against an `allowed_signers` file. With GPG,
+//! `gpg-agent` asks for the passphrase through `pinentry-mac`.
+
+use clap::ValueEnum;
+use colored::*;
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::error::SetupError;
+use crate::git::{self, GitIdentity};
+use crate::script_handler::ScriptHandler;
+use crate::shell_profile::{self, ProfileBlock, Stage};
+use crate::ssh::SshKey;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SigningMethod {
+    /// Sign with an SSH key (Git 2.34 or later)
+    Ssh,
+    /// Sign with a GPG key
+    Gpg,
+}
+
+/// `allowed_signers` under the Git config directory.
+pub fn allowed_signers_path(git_config_dir: &str) -> String {
+    format!("{}/allowed_signers", git_config_dir)
+}
+
+/// A line of `allowed_signers`: `email` may sign Git objects with
+/// `public_key`.
+pub fn allowed_signer(email: &str, public_key: &str) -> String {
+    let key: Vec<&str> = public_key.split_whitespace().take(2).collect();
+    format!("{} namespaces=\"git\" {}", email, key.join(" "))
+}
+
+/// Fingerprint of the first secret key in `gpg --list-secret-keys
+/// --with-colons` output.
+pub fn first_fingerprint(colons: &str) -> Option<String> {
+    let mut in_secret_key = false;
+    for line in colons.lines() {
+        let fields: Vec<&str> = line.split(':').collect();
+        match fields.first() {
+            Some(&"sec") => in_secret_key = true,
+            // 子密钥的 fpr 行不算
+            Some(&"ssb") => in_secret_key = false,
+            Some(&"fpr") if in_secret_key => return fields.get(9).map(|fpr| fpr.to_string()),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Block name in `allowed_signers` and `gpg-agent.conf`.
+const BLOCK: &str = "signing";
+
+/// Sets up signing with `method`. `key` picks the key: an SSH key name or
+/// a GPG key id; by default the first SSH key, or the GPG key for the Git
+/// email, generated if there is none. `import` is a GPG key file to import
+/// first.
+pub fn set_up(
+    handler: &ScriptHandler,
+    method: SigningMethod,
+    key: Option<&str>,
+    import: Option<&str>,
+) -> Result<bool, SetupError> {
+    let all_set = match method {
+        SigningMethod::Ssh => set_up_ssh(handler, key)?,
+        SigningMethod::Gpg => set_up_gpg(handler, key, import)?,
+    };
+    for (key, value) in [("commit.gpgsign", "true"), ("tag.gpgSign", "true")] {
+        if !handler.git_config_global(key, value)? {
+            return Err(format!("Failed to set {}", key).into());
+        }
+    }
+    say!("{}", "✓ Commits and tags are signed".green());
+    Ok(all_set)
+}
+
+/// True if Git signs commits with `method` and has a signing key.
+pub fn is_configured(handler: &ScriptHandler, method: SigningMethod) -> bool {
+    let format = global_config(handler, "gpg.format");
+    let format_matches = match method {
+        SigningMethod::Ssh => format.as_deref() == Some("ssh"),
+        SigningMethod::Gpg => format.is_none() || format.as_deref() == Some("openpgp"),
+    };
+    format_matches
+        && global_config(handler, "commit.gpgsign").as_deref() == Some("true")
+        && global_config(handler, "user.signingkey").is_some()
+}
+
+/// True if the default SSH signing key exists. The `ssh` step, which runs
+/// after the `git` step, sets up signing when it generates the key.
+pub fn has_ssh_key(handler: &ScriptHandler) -> bool {
+    Path::new(&public_key_path(&handler.manifest().ssh.key(None), &handler.home_dir())).exists()
+}
+
+/// The `user.signingkey` value for `identity`, `None` if it signs with the
+/// global key.
+pub fn identity_key(handler: &ScriptHandler, method: SigningMethod, identity: &GitIdentity) -> Option<String> {
+    let key = identity.signing_key.as_deref()?;
+    Some(match method {
+        SigningMethod::Ssh => public_key_path(&handler.manifest().ssh.key(Some(key)), &handler.home_dir()),
+        SigningMethod::Gpg => key.to_string(),
+    })
+}
+
+/// Points Git at the public key of an SSH key and lists the Git emails
+/// with their keys in `allowed_signers`, so `git log --show-signature`
+/// can verify local commits.
+fn set_up_ssh(handler: &ScriptHandler, key: Option<&str>) -> Result<bool, SetupError> {
+    require_ssh_signing(handler)?;
+
+    let home = handler.home_dir();
+    let key = handler.manifest().ssh.key(key);
+    let public_key_path = public_key_path(&key, &home);
+    if !Path::new(&public_key_path).exists() && handler.planner().is_none() {
+        return Err(SetupError::Config(format!(
+            "No SSH key at {}; run `macdevkit-cli ssh` first",
+            public_key_path
+        )));
+    }
+
+    // 全局邮箱用默认密钥，各身份用自己的密钥
+    let mut signers = Vec::new();
+    if let Some(email) = global_config(handler, "user.email") {
+        signers.push((email, public_key_path.clone()));
+    }
+    for identity in handler.manifest().git.identities.values() {
+        let path = identity_key(handler, SigningMethod::Ssh, identity).unwrap_or_else(|| public_key_path.clone());
+        signers.push((identity.email.clone(), path));
+    }
+    let lines: Vec<String> = signers
+        .iter()
+        .filter_map(|(email, path)| {
+            let public_key = std::fs::read_to_string(path).ok()?;
+            Some(allowed_signer(email, &public_key))
+        })
+        .collect();
+
+    let allowed_signers = allowed_signers_path(&git::config_dir(&home));
+    if let Some(dir) = Path::new(&allowed_signers).parent() {
+        handler.create_dir_all(&dir.to_string_lossy())?;
+    }
+    handler.write_block(&allowed_signers, BLOCK, &lines.join("\n"))?;
+    say!("{}", format!("✓ Updated {}", allowed_signers).green());
+
+    let mut all_set = true;
+    for (key, value) in [
+        ("gpg.format", "ssh"),
+        ("user.signingkey", public_key_path.as_str()),
+        ("gpg.ssh.allowedSignersFile", allowed_signers.as_str()),
+    ] {
+        all_set &= handler.git_config_global(key, value)?;
+    }
+    say!("{}", format!("✓ Signing with {}", public_key_path).green());
+    say!(
+        "{}",
+        "Register the key for signing on your Git host, e.g. `macdevkit-cli ssh publish --provider github --signing`".cyan()
+    );
+    Ok(all_set)
+}
+
+/// Git learned to sign with SSH keys in 2.34.
+fn require_ssh_signing(handler: &ScriptHandler) -> Result<(), SetupError> {
+    let output = handler.query("git", &["--version"])?;
+    // 形如 "git version 2.39.3 (Apple Git-146)"
+    let version = output.stdout.split_whitespace().nth(2).unwrap_or_default().to_string();
+    let mut parts = version.split('.').map(|part| part.parse::<u32>().unwrap_or(0));
+    let (major, minor) = (parts.next().unwrap_or(0), parts.next().unwrap_or(0));
+    if (major, minor) < (2, 34) {
+        return Err(SetupError::Config(format!(
+            "Git {} cannot sign with SSH keys; upgrade to 2.34 or later with `brew install git`",
+            version
+        )));
+    }
+    Ok(())
+}
+
+/// Installs GnuPG with pinentry-mac, imports or generates a key and points
+/// Git at it.
+fn set_up_gpg(handler: &ScriptHandler, key: Option<&str>, import: Option<&str>) -> Result<bool, SetupError> {
+    for formula in ["gnupg", "pinentry-mac"] {
+        if !handler.brew_install(formula, false)? {
+            return Err(format!("Failed to install {}", formula).into());
+        }
+    }
+    configure_agent(handler)?;
+
+    if let Some(file) = import {
+        say!("Importing {}...", file);
+        handler.run_checked("gpg", &["--batch", "--import", file])?;
+    }
+
+    let fingerprint = match key {
+        Some(key) => key.to_string(),
+        None => {
+            let email = global_config(handler, "user.email")
+                .ok_or_else(|| SetupError::Config("Git has no user.email; run `macdevkit-cli git` first".to_string()))?;
+            match secret_key(handler, &email) {
+                Some(fingerprint) => {
+                    say!("{}", format!("✓ GPG key for {} already exists", email).green());
+                    fingerprint
+                }
+                None => generate_gpg_key(handler, &email)?,
+            }
+        }
+    };
+
+    let gpg = format!("{}/bin/gpg", handler.platform().brew_prefix);
+    let mut all_set = true;
+    for (key, value) in [
+        ("gpg.format", "openpgp"),
+        ("gpg.program", gpg.as_str()),
+        ("user.signingkey", fingerprint.as_str()),
+    ] {
+        all_set &= handler.git_config_global(key, value)?;
+    }
+    say!("{}", format!("✓ Signing with GPG key {}", fingerprint).green());
+    say!(
+        "{}",
+        format!("Add the public key to your Git host: gpg --armor --export {} | pbcopy", fingerprint).cyan()
+    );
+    Ok(all_set)
+}
+
+/// Lets `gpg-agent` ask for passphrases with pinentry-mac, which can save
+/// them in the keychain, and tells GPG the terminal to use otherwise.
+fn configure_agent(handler: &ScriptHandler) -> Result<(), SetupError> {
+    let gnupg = format!("{}/.gnupg", handler.home_dir());
+    if !Path::new(&gnupg).is_dir() {
+        handler.create_dir_all(&gnupg)?;
+        // gpg 拒绝使用其他用户可读的目录
+        handler.run_checked("chmod", &["700", &gnupg])?;
+    }
+    let pinentry = format!("pinentry-program {}/bin/pinentry-mac", handler.platform().brew_prefix);
+    handler.write_block(&format!("{}/gpg-agent.conf", gnupg), BLOCK, &pinentry)?;
+    // 重启 agent 使配置生效，agent 未运行时也会成功
+    handler.run("gpgconf", &["--kill", "gpg-agent"])?;
+
+    let block = ProfileBlock::new("gpg", Stage::Interactive, "export GPG_TTY=$(tty)").fish("set -gx GPG_TTY (tty)");
+    shell_profile::write(handler, &block)?;
+    say!("{}", "✓ gpg-agent uses pinentry-mac".green());
+    Ok(())
+}
+
+/// Generates an Ed25519 signing key for the Git name and `email`. Returns
+/// its fingerprint.
+fn generate_gpg_key(handler: &ScriptHandler, email: &str) -> Result<String, SetupError> {
+    let name = global_config(handler, "user.name").unwrap_or_else(|| email.to_string());
+    let user_id = format!("{} <{}>", name, email);
+    let passphrase = handler.password(
+        "Passphrase for the GPG key (empty for none)",
+        "git.gpg_passphrase",
+        handler.answers().git.gpg_passphrase.as_deref(),
+    )?;
+
+    say!("Generating a GPG key for {}...", user_id);
+    // 口令从 stdin 读入，不出现在 ps 可见的参数中
+    handler.run_checked_input(
+        "gpg",
+        &[
+            "--batch",
+            "--pinentry-mode",
+            "loopback",
+            "--passphrase-fd",
+            "0",
+            "--quick-generate-key",
+            &user_id,
+            "ed25519",
+            "sign",
+            "2y",
+        ],
+        &format!("{}\n", passphrase),
+    )?;
+
+    // dry-run 时密钥不存在，计划中显示占位符
+    Ok(secret_key(handler, email).unwrap_or_else(|| "<new key fingerprint>".to_string()))
+}
+
+/// Fingerprint of the secret key for `email`, if GPG has one.
+fn secret_key(handler: &ScriptHandler, email: &str) -> Option<String> {
+    handler
+        .query("gpg", &["--list-secret-keys", "--with-colons", email])
+        .ok()
+        .filter(|output| output.success)
+        .and_then(|output| first_fingerprint(&output.stdout))
+}
+
+fn public_key_path(key: &SshKey, home: &str) -> String {
+    format!("{}.pub", key.path(home))
+}
+
+fn global_config(handler: &ScriptHandler, key: &str) -> Option<String> {
+    handler
+        .query("git", &["config", "--global", key])
+        .ok()
+        .map(|output| output.stdout.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::manifest::Manifest;
+    use crate::prompt::{Answers, GitAnswers};
+    use crate::runner::{CommandOutput, RecordingRunner};
+    use crate::ssh;
+    use crate::test_support::{handler_with, TempHome};
+
+    const DEFAULT_PUBLIC: &str = "ssh-ed25519 AAAAdefault ada@example.com\n";
+    const WORK_PUBLIC: &str = "ssh-ed25519 AAAAwork jane@company.com\n";
+
+    fn identity(email: &str, signing_key: Option<&str>) -> GitIdentity {
+        GitIdentity {
+            name: "Jane Doe".to_string(),
+            email: email.to_string(),
+            dirs: vec!["~/Workspace/work/".to_string()],
+            signing_key: signing_key.map(str::to_string),
+        }
+    }
+
+    /// A home with a default and a `work` key, and identities signing with
+    /// each of them.
+    fn ssh_setup(name: &str) -> (TempHome, Arc<RecordingRunner>, ScriptHandler) {
+        let home = TempHome::new(name);
+        std::fs::create_dir_all(home.join(".ssh")).unwrap();
+        std::fs::write(home.join(".ssh/id_ed25519.pub"), DEFAULT_PUBLIC).unwrap();
+        std::fs::write(home.join(".ssh/id_ed25519_work.pub"), WORK_PUBLIC).unwrap();
+
+        let mut manifest = Manifest::default();
+        manifest.ssh.keys = vec![SshKey::new(ssh::DEFAULT_KEY), SshKey::new("work")];
+        manifest.git.identities.insert("work".to_string(), identity("jane@company.com", Some("work")));
+        manifest.git.identities.insert("oss".to_string(), identity("jane@users.noreply.github.com", None));
+        let runner = Arc::new(
+            RecordingRunner::new()
+                .respond("git --version", CommandOutput::success("git version 2.39.3 (Apple Git-146)\n"))
+                .respond("git config --global user.email", CommandOutput::success("ada@example.com\n")),
+        );
+        let handler = handler_with(manifest, &runner, &home, Answers::default());
+        (home, runner, handler)
+    }
+
+    #[test]
+    fn ssh_signing_points_git_at_the_key_and_allowed_signers() {
+        let (home, runner, handler) = ssh_setup("signing-ssh");
+        assert!(set_up(&handler, SigningMethod::Ssh, None, None).unwrap());
+
+        let allowed_signers = allowed_signers_path(&git::config_dir(&home.as_str()));
+        let lines = runner.command_lines();
+        for expected in [
+            "git config --global gpg.format ssh".to_string(),
+            format!("git config --global user.signingkey {}", home.join(".ssh/id_ed25519.pub")),
+            format!("git config --global gpg.ssh.allowedSignersFile {}", allowed_signers),
+            "git config --global commit.gpgsign true".to_string(),
+            "git config --global tag.gpgSign true".to_string(),
+        ] {
+            assert!(lines.contains(&expected), "missing {}", expected);
+        }
+
+        let content = std::fs::read_to_string(&allowed_signers).unwrap();
+        let mut signers: Vec<_> = crate::managed_block::find(&content, BLOCK).unwrap().lines().collect();
+        signers.sort();
+        assert_eq!(
+            signers,
+            [
+                "ada@example.com namespaces=\"git\" ssh-ed25519 AAAAdefault",
+                "jane@company.com namespaces=\"git\" ssh-ed25519 AAAAwork",
+                "jane@users.noreply.github.com namespaces=\"git\" ssh-ed25519 AAAAdefault",
+            ]
+        );
+
+        // 再次运行不会重复写入
+        assert!(set_up(&handler, SigningMethod::Ssh, None, None).unwrap());
+        assert_eq!(std::fs::read_to_string(&allowed_signers).unwrap(), content);
+    }
+
+    #[test]
+    fn identities_sign_with_their_own_key() {
+        let (home, _runner, handler) = ssh_setup("signing-identities");
+        let identities = &handler.manifest().git.identities;
+
+        assert_eq!(
+            identity_key(&handler, SigningMethod::Ssh, &identities["work"]),
+            Some(home.join(".ssh/id_ed25519_work.pub"))
+        );
+        assert_eq!(identity_key(&handler, SigningMethod::Ssh, &identities["oss"]), None);
+        assert_eq!(
+            identity_key(&handler, SigningMethod::Gpg, &identity("a@b.c", Some("ABCD1234"))).as_deref(),
+            Some("ABCD1234")
+        );
+    }
+
+    #[test]
+    fn ssh_signing_needs_a_key_and_git_2_34() {
+        let (home, _runner, handler) = ssh_setup("signing-missing");
+        std::fs::remove_file(home.join(".ssh/id_ed25519.pub")).unwrap();
+        assert!(set_up(&handler, SigningMethod::Ssh, None, None).is_err());
+
+        let runner = Arc::new(RecordingRunner::new().respond("git --version", CommandOutput::success("git version 2.30.1\n")));
+        let handler = handler_with(Manifest::default(), &runner, &home, Answers::default());
+        assert!(set_up(&handler, SigningMethod::Ssh, Some("work"), None).is_err());
+        assert!(!runner.command_lines().iter().any(|line| line.starts_with("git config --global gpg.format")));
+    }
+
+    #[test]
+    fn gpg_passphrase_goes_to_stdin() {
+        let home = TempHome::new("signing-gpg");
+        let answers = Answers {
+            git: GitAnswers {
+                name: Some("Ada".to_string()),
+                gpg_passphrase: Some("s3cret".to_string()),
+                ..GitAnswers::default()
+            },
+            ..Answers::default()
+        };
+        let runner = Arc::new(RecordingRunner::new());
+        let handler = handler_with(Manifest::default(), &runner, &home, answers);
+
+        generate_gpg_key(&handler, "ada@example.com").unwrap();
+        let generate = runner
+            .invocations()
+            .into_iter()
+            .find(|invocation| invocation.args.iter().any(|arg| arg == "--quick-generate-key"))
+            .unwrap();
+        assert!(!generate.args.iter().any(|arg| arg.contains("s3cret")));
+        assert!(generate.args.windows(2).any(|pair| pair == ["--passphrase-fd", "0"]));
+        assert_eq!(generate.stdin.as_deref(), Some("s3cret\n"));
+    }
+}
@@ -0,0 +1,145 @@
+//! SSH keys and the `~/.ssh/config` entries that use them.
+//!
+//! Every key lives at `~/.ssh/id_ed25519` or `~/.ssh/id_ed25519_<name>` and,
+//! if it is for particular hosts, has its own [managed block](crate::managed_block)
+//! with a `Host` entry. The `ssh` block holds a `Host *` entry that adds keys
+//! to the agent and the macOS keychain. SSH uses the first value it finds
+//! for each option, so the blocks go at the top of the file.
+
+use serde::Deserialize;
+
+/// Block with the `Host *` entry.
+pub const DEFAULTS_BLOCK: &str = "ssh";
+
+/// Name of the key used for every host that has no key of its own.
+pub const DEFAULT_KEY: &str = "default";
+
+/// `[ssh]` table of the manifest.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SshSettings {
+    /// Ask for a passphrase for new keys; without one they are unencrypted.
+    pub passphrase: bool,
+    /// Store passphrases in the macOS keychain.
+    pub keychain: bool,
+    /// Keys for particular hosts. Without any, one key is used everywhere.
+    pub keys: Vec<SshKey>,
+}
+
+impl Default for SshSettings {
+    fn default() -> Self {
+        SshSettings {
+            passphrase: true,
+            keychain: true,
+            keys: Vec::new(),
+        }
+    }
+}
+
+impl SshSettings {
+    /// The configured keys, or the default key if none are.
+    pub fn keys(&self) -> Vec<SshKey> {
+        if self.keys.is_empty() {
+            vec![SshKey::new(DEFAULT_KEY)]
+        } else {
+            self.keys.clone()
+        }
+    }
+
+    /// The key called `name`, or the first key if `name` is `None`.
+    pub fn key(&self, name: Option<&str>) -> SshKey {
+        let keys = self.keys();
+        match name {
+            Some(name) => keys.into_iter().find(|key| key.name == name).unwrap_or_else(|| SshKey::new(name)),
+            None => keys.into_iter().next().unwrap_or_else(|| SshKey::new(DEFAULT_KEY)),
+        }
+    }
+}
+
+/// An Ed25519 key and the hosts it is for, e.g. `[[ssh.keys]]` with
+/// `name = "work"` and `hosts = ["gitlab.example.com"]`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SshKey {
+    pub name: String,
+    /// `Host` patterns; none for the default key.
+    #[serde(default)]
+    pub hosts: Vec<String>,
+    /// Real host name, for an alias such as `github-work`.
+    pub hostname: Option<String>,
+    pub user: Option<String>,
+    /// Key comment; defaults to the Git email.
+    pub comment: Option<String>,
+}
+
+impl SshKey {
+    pub fn new(name: &str) -> Self {
+        SshKey {
+            name: name.to_string(),
+            hosts: Vec::new(),
+            hostname: None,
+            user: None,
+            comment: None,
+        }
+    }
+
+    /// Private key path under `home`; the public key adds `.pub`.
+    pub fn path(&self, home: &str) -> String {
+        if self.name == DEFAULT_KEY {
+            format!("{}/.ssh/id_ed25519", home)
+        } else {
+            format!("{}/.ssh/id_ed25519_{}", home, self.name)
+        }
+    }
+
+    /// Name of the key's managed block in `~/.ssh/config`.
+    pub fn block_name(&self) -> String {
+        format!("{} {}", DEFAULTS_BLOCK, self.name)
+    }
+
+    /// `Host` entry for the key, `None` if it is not for particular hosts.
+    pub fn host_entry(&self, home: &str) -> Option<String> {
+        if self.hosts.is_empty() {
+            return None;
+        }
+
+        let mut lines = vec![format!("Host {}", self.hosts.join(" "))];
+        if let Some(hostname) = &self.hostname {
+            lines.push(format!("  HostName {}", hostname));
+        }
+        if let Some(user) = &self.user {
+            lines.push(format!("  User {}", user));
+        }
+        lines.push(format!("  IdentityFile {}", tilde(&self.path(home), home)));
+        // 只使用这把密钥，避免 agent 中的其他密钥先被服务器拒绝
+        lines.push("  IdentitiesOnly yes".to_string());
+        Some(lines.join("\n"))
+    }
+}
+
+/// The `Host *` entry, with the keys that are not for particular hosts.
+pub fn defaults_entry(settings: &SshSettings, home: &str) -> String {
+    let mut lines = vec!["Host *".to_string(), "  AddKeysToAgent yes".to_string()];
+    if settings.keychain {
+        // UseKeychain 只有 Apple 的 OpenSSH 认识
+        lines.push("  IgnoreUnknown UseKeychain".to_string());
+        lines.push("  UseKeychain yes".to_string());
+    }
+    for key in settings.keys().iter().filter(|key| key.hosts.is_empty()) {
+        lines.push(format!("  IdentityFile {}", tilde(&key.path(home), home)));
+    }
+    lines.join("\n")
+}
+
+/// `~/.ssh/config` under `home`.
+pub fn config_path(home: &str) -> String {
+    format!("{}/.ssh/config", home)
+}
+
+/// `path` with `home` written as `~`, as is usual in `~/.ssh/config`.
+fn tilde(path: &str, home: &str) -> String {
+    match path.strip_prefix(home) {
+        Some(rest) if rest.starts_with('/') => format!("~{}", rest),
+        _ => path.to_string(),
+    }
+}
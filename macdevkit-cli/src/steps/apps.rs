@@ -0,0 +1,48 @@
+use colored::*;
+
+use super::Step;
+use crate::error::SetupError;
+use crate::install::Batch;
+use crate::script_handler::ScriptHandler;
+
+pub struct Apps;
+
+impl Step for Apps {
+    fn id(&self) -> &'static str {
+        "apps"
+    }
+
+    fn title(&self) -> &'static str {
+        "Install useful applications"
+    }
+
+    fn description(&self) -> &'static str {
+        "Installs the Homebrew casks listed in the manifest, such as browsers, note-taking and productivity tools."
+    }
+
+    fn dependencies(&self) -> &'static [&'static str] {
+        &["brew"]
+    }
+
+    fn check(&self, handler: &ScriptHandler) -> bool {
+        let casks = &handler.manifest().brew.casks;
+        casks.iter().all(|cask| handler.brew_installed(cask, true))
+    }
+
+    fn apply(&self, _handler: &ScriptHandler) -> Result<bool, SetupError> {
+        say!("{}", "\n==== Installing Applications ====\n".blue());
+        Ok(true)
+    }
+
+    fn packages(&self, handler: &ScriptHandler) -> Vec<Batch> {
+        let missing = handler
+            .manifest()
+            .brew
+            .casks
+            .iter()
+            .filter(|cask| !handler.brew_installed(cask, true))
+            .cloned()
+            .collect();
+        vec![Batch::brew(self.id(), missing, true)]
+    }
+}
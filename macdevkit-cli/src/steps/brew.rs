@@ -0,0 +1,407 @@
+use clap::{value_parser, Arg, ArgAction, ArgMatches};
+use colored::*;
+use std::fs;
+use std::path::PathBuf;
+
+use super::Step;
+use crate::brewfile::{self, Entry};
+use crate::doctor::Diagnosis;
+use crate::error::SetupError;
+use crate::install::{self, Batch};
+use crate::mirror::BrewMirror;
+use crate::platform::Arch;
+use crate::script_handler::ScriptHandler;
+use crate::shell_profile::{self, ProfileBlock, Stage};
+
+pub struct Brew;
+
+impl Brew {
+    /// Taps, formulae and casks listed in the manifest.
+    fn managed_entries(&self, handler: &ScriptHandler) -> Vec<Entry> {
+        let brew = &handler.manifest().brew;
+        let taps = brew.taps.iter().cloned().map(Entry::Tap);
+        let formulae = brew.formulae.iter().cloned().map(Entry::Brew);
+        let casks = brew.casks.iter().cloned().map(Entry::Cask);
+        taps.chain(formulae).chain(casks).collect()
+    }
+
+    /// Everything installed on this Mac: taps, formulae installed on
+    /// request, casks and App Store apps.
+    fn installed_entries(&self, handler: &ScriptHandler) -> Result<Vec<Entry>, SetupError> {
+        let list = |program: &str, args: &[&str]| -> Result<Vec<String>, SetupError> {
+            let output = handler.query(program, args)?;
+            if !output.success {
+                return Err(SetupError::CommandFailed {
+                    command: format!("{} {}", program, args.join(" ")),
+                    code: output.code,
+                });
+            }
+            Ok(output.stdout.lines().map(str::trim).filter(|line| !line.is_empty()).map(String::from).collect())
+        };
+
+        let mut entries = Vec::new();
+        entries.extend(list("brew", &["tap"])?.into_iter().map(Entry::Tap));
+        entries.extend(list("brew", &["leaves", "--installed-on-request"])?.into_iter().map(Entry::Brew));
+        entries.extend(list("brew", &["list", "--cask", "-1"])?.into_iter().map(Entry::Cask));
+
+        if handler.command_exists("mas") {
+            // mas list 的输出格式为 "497799835  Xcode  (15.0)"
+            for line in list("mas", &["list"])? {
+                let Some((id, rest)) = line.split_once(char::is_whitespace) else {
+                    continue;
+                };
+                let name = rest.trim();
+                let name = name.rsplit_once(" (").map_or(name, |(name, _)| name).trim();
+                if let Ok(id) = id.parse() {
+                    entries.push(Entry::Mas { name: name.to_string(), id });
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    fn export(&self, handler: &ScriptHandler, matches: &ArgMatches) -> Result<bool, SetupError> {
+        let entries = if matches.get_flag("installed") {
+            self.installed_entries(handler)?
+        } else {
+            self.managed_entries(handler)
+        };
+        let content = brewfile::render(&entries);
+
+        match matches.get_one::<PathBuf>("file") {
+            Some(path) => {
+                handler.write_file(&path.to_string_lossy(), &content)?;
+                say!("{}", format!("✓ Wrote {} entries to {}", entries.len(), path.display()).green());
+            }
+            None => print!("{}", content),
+        }
+        Ok(true)
+    }
+
+    fn import(&self, handler: &ScriptHandler, matches: &ArgMatches) -> Result<bool, SetupError> {
+        let path = matches
+            .get_one::<PathBuf>("brewfile")
+            .ok_or("Missing Brewfile path")?;
+        let content = fs::read_to_string(path)
+            .map_err(|e| SetupError::io(format!("Failed to read {}", path.display()), e))?;
+        let (entries, warnings) = brewfile::parse(&content)?;
+
+        for warning in warnings {
+            say!("{}", format!("Warning: {}", warning).yellow());
+        }
+
+        say!("{}", format!("\n==== Importing {} ====\n", path.display()).blue());
+
+        let mut all_installed = true;
+        let (mut formulae, mut casks) = (Vec::new(), Vec::new());
+        for entry in entries.iter() {
+            match entry {
+                Entry::Tap(name) => all_installed &= handler.brew_tap(name)?,
+                Entry::Brew(name) | Entry::Cask(name) => {
+                    let cask = matches!(entry, Entry::Cask(_));
+                    if handler.brew_installed(name, cask) {
+                        say!("{}", format!("✓ {} already installed", name).green());
+                    } else if cask {
+                        casks.push(name.clone());
+                    } else {
+                        formulae.push(name.clone());
+                    }
+                }
+                Entry::Mas { .. } => {}
+            }
+        }
+
+        // 与 devtools 相同，公式和 cask 各用一条 brew install 批量安装
+        let batches = vec![Batch::brew(self.id(), formulae, false), Batch::brew(self.id(), casks, true)];
+        all_installed &= install::install(handler, batches).values().all(|&installed| installed);
+
+        for entry in entries.iter() {
+            if let Entry::Mas { name, id } = entry {
+                all_installed &= handler.mas_install(name, *id)?;
+            }
+        }
+
+        say!("{}", "Brewfile import completed".green());
+        Ok(all_installed)
+    }
+
+    /// Exports the mirror from the shell profiles.
+    fn persist_mirror(&self, handler: &ScriptHandler, mirror: &BrewMirror) -> Result<(), SetupError> {
+        for path in shell_profile::write(handler, &mirror.profile_block())? {
+            say!("{}", format!("✓ Homebrew mirror set in {}", path).green());
+        }
+        Ok(())
+    }
+
+    /// Loads `brew shellenv` from the shell profiles. On Apple Silicon this
+    /// puts Homebrew on `PATH`; on Intel `/usr/local/bin` already is, but
+    /// shellenv also sets `HOMEBREW_PREFIX` and `MANPATH`.
+    fn add_to_path(&self, handler: &ScriptHandler) -> Result<(), SetupError> {
+        let brew = handler.platform().brew();
+        let block = ProfileBlock::new("homebrew", Stage::Login, format!(r#"eval "$({} shellenv)""#, brew))
+            .fish(format!("eval ({} shellenv)", brew))
+            .legacy(format!("\neval \"$({} shellenv)\"\n", brew));
+        for path in shell_profile::write(handler, &block)? {
+            say!("{}", format!("✓ Homebrew added to PATH in {}", path).green());
+        }
+        Ok(())
+    }
+}
+
+impl Step for Brew {
+    fn id(&self) -> &'static str {
+        "brew"
+    }
+
+    fn title(&self) -> &'static str {
+        "Install Homebrew"
+    }
+
+    fn description(&self) -> &'static str {
+        "Homebrew is the package manager for macOS and the basis for installing most of the tools in later steps."
+    }
+
+    fn dependencies(&self) -> &'static [&'static str] {
+        &["xcode"]
+    }
+
+    fn check(&self, handler: &ScriptHandler) -> bool {
+        handler.command_exists("brew")
+    }
+
+    fn diagnose(&self, handler: &ScriptHandler) -> Diagnosis {
+        if !self.check(handler) {
+            return Diagnosis::fail("brew not found on PATH", "run `macdevkit-cli brew`");
+        }
+
+        // Apple Silicon 上 Homebrew 应安装在 /opt/homebrew，Intel 上为 /usr/local
+        let expected = handler.platform().native_brew_prefix();
+        match handler.query("brew", &["--prefix"]) {
+            Ok(output) if output.success && output.stdout.trim() == expected => {
+                Diagnosis::pass(format!("brew at {}", expected))
+            }
+            Ok(output) if output.success => Diagnosis::warn(
+                format!("brew prefix is {}, expected {}", output.stdout.trim(), expected),
+                format!("reinstall Homebrew under {} and put {}/bin first on PATH", expected, expected),
+            ),
+            _ => Diagnosis::fail("`brew --prefix` failed", "run `brew doctor`"),
+        }
+    }
+
+    fn subcommands(&self) -> Vec<clap::Command> {
+        vec![
+            clap::Command::new("export")
+                .about("Write the formulae, casks and taps from the manifest as a Brewfile")
+                .arg(
+                    Arg::new("file")
+                        .short('o')
+                        .long("file")
+                        .value_name("FILE")
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Write to FILE instead of stdout"),
+                )
+                .arg(
+                    Arg::new("installed")
+                        .long("installed")
+                        .action(ArgAction::SetTrue)
+                        .help("Export everything installed on this Mac instead of the manifest"),
+                ),
+            clap::Command::new("import")
+                .about("Install the taps, formulae, casks and App Store apps listed in a Brewfile")
+                .arg(
+                    Arg::new("brewfile")
+                        .value_name("BREWFILE")
+                        .required(true)
+                        .value_parser(value_parser!(PathBuf)),
+                ),
+        ]
+    }
+
+    fn run_subcommand(&self, handler: &ScriptHandler, name: &str, matches: &ArgMatches) -> Result<bool, SetupError> {
+        match name {
+            "export" => self.export(handler, matches),
+            "import" => self.import(handler, matches),
+            _ => Err(format!("Unknown subcommand: brew {}", name).into()),
+        }
+    }
+
+    fn apply(&self, handler: &ScriptHandler) -> Result<bool, SetupError> {
+        say!("{}", "\n==== Installing Homebrew ====\n".blue());
+
+        // 镜像的环境变量已在启动时导出，这里只负责提示和写入 shell 配置
+        let mirror = BrewMirror::from_settings(&handler.manifest().mirror).map_err(SetupError::Config)?;
+        if let Some(mirror) = &mirror {
+            say!("{}", format!("Using Homebrew mirror {} ({})", mirror.name.as_str(), mirror.bottle_domain).cyan());
+            if handler.manifest().mirror.persist {
+                self.persist_mirror(handler, mirror)?;
+            }
+        }
+
+        if self.check(handler) {
+            say!("{}", "✓ Homebrew already installed".green());
+
+            // 更新Homebrew
+            let _ = handler.run("brew", &["update"])?;
+
+            say!("{}", "Homebrew updated".green());
+            self.add_to_path(handler)?;
+            return Ok(true);
+        }
+
+        say!("{}", "Installing Homebrew...".cyan());
+
+        // 安装Homebrew
+        let install_cmd = r#"/bin/bash -c "$(curl -fsSL https://raw.githubusercontent.com/Homebrew/install/HEAD/install.sh)""#;
+
+        // 在Rosetta下运行时安装脚本会把Homebrew装到/usr/local，需切换到arm64
+        let platform = handler.platform();
+        if platform.arch == Arch::AppleSilicon && platform.rosetta {
+            say!("{}", "Running under Rosetta; installing the native Apple Silicon Homebrew".yellow());
+            handler.run_checked("arch", &["-arm64", "bash", "-c", install_cmd])?;
+        } else {
+            handler.run_checked("bash", &["-c", install_cmd])?;
+        }
+
+        say!("{}", "Homebrew installed".green());
+        handler.use_installed_brew();
+
+        // 添加Homebrew到PATH
+        if let Err(e) = self.add_to_path(handler) {
+            say!("{}", format!("Warning: {}", e).yellow());
+        }
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::changes::ChangeLog;
+    use crate::runner::{CommandOutput, RecordingRunner};
+    use crate::test_support::{handler, TempHome};
+
+    const INSTALL: &str = r#"bash -c /bin/bash -c "$(curl -fsSL https://raw.githubusercontent.com/Homebrew/install/HEAD/install.sh)""#;
+
+    #[test]
+    fn apply_updates_and_loads_installed_brew() {
+        let home = TempHome::new("brew-installed");
+        let runner = Arc::new(
+            RecordingRunner::new()
+                .with_program("brew")
+                .respond("sysctl -n hw.optional.arm64", CommandOutput::success("1\n"))
+                .respond("brew --prefix", CommandOutput::success("/opt/homebrew\n")),
+        );
+
+        assert!(Brew.apply(&handler(&runner, &home)).unwrap());
+        assert!(runner.command_lines().contains(&"brew update".to_string()));
+        let zprofile = std::fs::read_to_string(home.join(".zprofile")).unwrap();
+        assert!(zprofile.contains(r#"eval "$(/opt/homebrew/bin/brew shellenv)""#));
+    }
+
+    #[test]
+    fn apply_installs_native_brew_under_rosetta() {
+        let home = TempHome::new("brew-rosetta");
+        let runner = Arc::new(
+            RecordingRunner::new()
+                .respond("sysctl -n hw.optional.arm64", CommandOutput::success("1\n"))
+                .respond("sysctl -n sysctl.proc_translated", CommandOutput::success("1\n")),
+        );
+
+        assert!(Brew.apply(&handler(&runner, &home)).unwrap());
+        assert!(runner.command_lines().contains(&format!("arch -arm64 {}", INSTALL)));
+        let zprofile = std::fs::read_to_string(home.join(".zprofile")).unwrap();
+        assert!(zprofile.contains("/opt/homebrew/bin/brew shellenv"));
+    }
+
+    #[test]
+    fn installed_brew_is_found_by_later_steps() {
+        let home = TempHome::new("brew-path");
+        let runner = Arc::new(RecordingRunner::new().respond("sysctl -n hw.optional.arm64", CommandOutput::success("1\n")));
+        let handler = handler(&runner, &home);
+
+        assert!(Brew.apply(&handler).unwrap());
+        let path = std::env::var("PATH").unwrap();
+        assert!(path.split(':').any(|dir| dir == "/opt/homebrew/bin"));
+
+        // 安装后重新检测平台，写入 shellenv 时用的是新装 brew 的前缀
+        let detections = runner.command_lines().iter().filter(|line| line.starts_with("sw_vers")).count();
+        assert_eq!(detections, 2);
+    }
+
+    #[test]
+    fn apply_installs_brew_on_intel() {
+        let home = TempHome::new("brew-intel");
+        let runner = Arc::new(RecordingRunner::new().respond("sysctl", CommandOutput::failure("unknown oid")));
+
+        assert!(Brew.apply(&handler(&runner, &home)).unwrap());
+        let lines = runner.command_lines();
+        assert!(lines.contains(&INSTALL.to_string()));
+        assert!(!lines.iter().any(|line| line.starts_with("arch ")));
+        let zprofile = std::fs::read_to_string(home.join(".zprofile")).unwrap();
+        assert!(zprofile.contains("/usr/local/bin/brew shellenv"));
+    }
+
+    #[test]
+    fn undo_restores_legacy_shellenv_line() {
+        let home = TempHome::new("brew-legacy");
+        let original = "export EDITOR=vim\n\neval \"$(/opt/homebrew/bin/brew shellenv)\"\n";
+        std::fs::write(home.join(".zprofile"), original).unwrap();
+        let runner = Arc::new(
+            RecordingRunner::new()
+                .with_program("brew")
+                .respond("sysctl -n hw.optional.arm64", CommandOutput::success("1\n"))
+                .respond("brew --prefix", CommandOutput::success("/opt/homebrew\n")),
+        );
+        let changes = ChangeLog::open(home.path.join("changes.json")).unwrap();
+        let handler = handler(&runner, &home).with_changes(changes);
+
+        assert!(handler.run_section(&Brew).unwrap());
+        let zprofile = std::fs::read_to_string(home.join(".zprofile")).unwrap();
+        assert_eq!(zprofile.matches("brew shellenv").count(), 1);
+
+        assert!(handler.undo_section(&Brew).unwrap());
+        assert_eq!(std::fs::read_to_string(home.join(".zprofile")).unwrap(), original);
+    }
+
+    #[test]
+    fn import_installs_missing_packages_in_batches() {
+        let home = TempHome::new("brew-import");
+        let brewfile = home.join("Brewfile");
+        std::fs::write(
+            &brewfile,
+            "tap \"homebrew/cask-fonts\"\nbrew \"jq\"\nbrew \"wget\"\nbrew \"fd\"\ncask \"firefox\"\nmas \"Xcode\", id: 497799835\nvscode \"eamodio.gitlens\"\n",
+        )
+        .unwrap();
+        let runner = Arc::new(
+            RecordingRunner::new()
+                .with_program("brew")
+                .with_program("mas")
+                .respond("brew list", CommandOutput::failure(""))
+                .respond("brew list --formula --versions jq", CommandOutput::success("jq 1.7.1\n")),
+        );
+        let handler = handler(&runner, &home);
+
+        let command = Brew.subcommands().into_iter().find(|command| command.get_name() == "import").unwrap();
+        let matches = command.try_get_matches_from(["import", brewfile.as_str()]).unwrap();
+        assert!(handler.run_subcommand(&Brew, "import", &matches).unwrap());
+
+        let installs: Vec<_> = runner
+            .command_lines()
+            .into_iter()
+            .filter(|line| line.starts_with("brew tap ") || line.contains(" install "))
+            .collect();
+        assert_eq!(
+            installs,
+            [
+                "brew tap homebrew/cask-fonts",
+                "brew install wget fd",
+                "brew install --cask firefox",
+                "mas install 497799835",
+            ]
+        );
+    }
+}
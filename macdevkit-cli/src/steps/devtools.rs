@@ -0,0 +1,60 @@
+use colored::*;
+
+use super::Step;
+use crate::error::SetupError;
+use crate::install::Batch;
+use crate::script_handler::ScriptHandler;
+
+pub struct DevTools;
+
+impl Step for DevTools {
+    fn id(&self) -> &'static str {
+        "devtools"
+    }
+
+    fn title(&self) -> &'static str {
+        "Install additional developer tools"
+    }
+
+    fn description(&self) -> &'static str {
+        "Installs the Homebrew taps and formulae listed in the manifest, by default jq, ripgrep, fd, bat and httpie."
+    }
+
+    fn dependencies(&self) -> &'static [&'static str] {
+        &["brew"]
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        &["dev-tools"]
+    }
+
+    fn check(&self, handler: &ScriptHandler) -> bool {
+        let brew = &handler.manifest().brew;
+        brew.formulae.iter().all(|formula| handler.brew_installed(formula, false))
+    }
+
+    fn apply(&self, handler: &ScriptHandler) -> Result<bool, SetupError> {
+        say!("{}", "\n==== Installing Developer Tools ====\n".blue());
+
+        let brew = &handler.manifest().brew;
+
+        let mut all_set = true;
+        for tap in brew.taps.iter() {
+            all_set &= handler.brew_tap(tap)?;
+        }
+
+        Ok(all_set)
+    }
+
+    fn packages(&self, handler: &ScriptHandler) -> Vec<Batch> {
+        let missing = handler
+            .manifest()
+            .brew
+            .formulae
+            .iter()
+            .filter(|formula| !handler.brew_installed(formula, false))
+            .cloned()
+            .collect();
+        vec![Batch::brew(self.id(), missing, false)]
+    }
+}
@@ -0,0 +1,63 @@
+use colored::*;
+
+use super::Step;
+use crate::doctor::Diagnosis;
+use crate::error::SetupError;
+use crate::script_handler::ScriptHandler;
+
+pub struct Docker;
+
+impl Step for Docker {
+    fn id(&self) -> &'static str {
+        "docker"
+    }
+
+    fn title(&self) -> &'static str {
+        "Install Docker"
+    }
+
+    fn description(&self) -> &'static str {
+        "Docker lets you develop, ship and run applications in containers together with all of their dependencies."
+    }
+
+    fn dependencies(&self) -> &'static [&'static str] {
+        &["brew"]
+    }
+
+    fn check(&self, handler: &ScriptHandler) -> bool {
+        handler.command_exists("docker")
+    }
+
+    fn diagnose(&self, handler: &ScriptHandler) -> Diagnosis {
+        if !self.check(handler) {
+            return Diagnosis::fail("docker not found on PATH", "run `macdevkit-cli docker`");
+        }
+
+        let reachable = handler
+            .query("docker", &["info"])
+            .map(|output| output.success)
+            .unwrap_or(false);
+        if reachable {
+            Diagnosis::pass("Docker daemon reachable")
+        } else {
+            Diagnosis::warn("Docker daemon not reachable", "start Docker Desktop (`open -a Docker`)")
+        }
+    }
+
+    fn apply(&self, handler: &ScriptHandler) -> Result<bool, SetupError> {
+        say!("{}", "\n==== Installing Docker ====\n".blue());
+
+        if self.check(handler) {
+            say!("{}", "✓ Docker already installed".green());
+            return Ok(true);
+        }
+
+        // 使用Homebrew安装Docker
+        if !handler.brew_install("docker", true)? {
+            return Err("Failed to install Docker".into());
+        }
+        say!("Please launch Docker Desktop to complete the setup.");
+
+        Ok(true)
+    }
+}
@@ -0,0 +1,439 @@
+use clap::{Arg, ArgMatches};
+use colored::*;
+use std::path::{Path, PathBuf};
+
+use super::Step;
+use crate::doctor::Diagnosis;
+use crate::error::SetupError;
+use crate::git;
+use crate::managed_block;
+use crate::script_handler::ScriptHandler;
+use crate::signing::{self, SigningMethod};
+
+/// Name of the managed block in include files and the global gitignore.
+const BLOCK: &str = "git";
+
+pub struct Git;
+
+impl Git {
+    fn has_user_name(&self, handler: &ScriptHandler) -> bool {
+        self.global_config(handler, "user.name").is_some()
+    }
+
+    /// A global git setting, or `None` if it is unset or empty.
+    fn global_config(&self, handler: &ScriptHandler, key: &str) -> Option<String> {
+        handler
+            .query("git", &["config", "--global", key])
+            .ok()
+            .map(|output| output.stdout.trim().to_string())
+            .filter(|value| !value.is_empty())
+    }
+
+    /// Writes the global config from the manifest. Without a `[git.config]`
+    /// table only keys that are not set yet are filled in.
+    fn write_config(&self, handler: &ScriptHandler) -> Result<bool, SetupError> {
+        let settings = &handler.manifest().git;
+        let (config, enforce) = match &settings.config {
+            Some(config) => (config.clone(), true),
+            None => (git::default_config(), false),
+        };
+
+        let mut all_set = true;
+        for (key, value) in config.iter() {
+            if !enforce && self.global_config(handler, key).is_some() {
+                continue;
+            }
+            if !handler.git_config_global(key, value)? {
+                say!("{}", format!("Failed to set {}", key).red());
+                all_set = false;
+            }
+        }
+        Ok(all_set)
+    }
+
+    /// Writes an include file per identity and the `includeIf` rules that
+    /// load it; with `signing`, each identity's own signing key too.
+    fn write_identities(&self, handler: &ScriptHandler, signing: Option<SigningMethod>) -> Result<bool, SetupError> {
+        let home = handler.home_dir();
+        let mut all_set = true;
+        for (id, identity) in handler.manifest().git.identities.iter() {
+            let path = git::include_path(id, &home);
+            if let Some(dir) = Path::new(&path).parent() {
+                handler.create_dir_all(&dir.to_string_lossy())?;
+            }
+            let signing_key = signing.and_then(|method| signing::identity_key(handler, method, identity));
+            handler.write_block(&path, BLOCK, &identity.include_body(signing_key.as_deref()))?;
+
+            for key in identity.include_keys() {
+                all_set &= handler.git_config_global(&key, &path)?;
+            }
+            say!("{}", format!("✓ Identity {} ({}) for {}", id, identity.email, identity.dirs.join(", ")).green());
+        }
+        Ok(all_set)
+    }
+
+    /// The global gitignore: `core.excludesFile` if set, otherwise the file
+    /// Git reads by default.
+    fn ignore_path(&self, handler: &ScriptHandler) -> String {
+        match self.global_config(handler, "core.excludesFile") {
+            // 相对路径按主目录解析，而非当前目录
+            Some(path) => match handler.expand_home(&path) {
+                path if Path::new(&path).is_absolute() => path,
+                path => format!("{}/{}", handler.home_dir(), path),
+            },
+            None => format!("{}/ignore", git::config_dir(&handler.home_dir())),
+        }
+    }
+
+    fn write_ignore(&self, handler: &ScriptHandler) -> Result<(), SetupError> {
+        let path = self.ignore_path(handler);
+        if let Some(dir) = Path::new(&path).parent() {
+            handler.create_dir_all(&dir.to_string_lossy())?;
+        }
+        handler.write_block(&path, BLOCK, &handler.manifest().git.ignore().join("\n"))?;
+        say!("{}", format!("✓ Updated {}", path).green());
+        Ok(())
+    }
+
+    /// Identities whose include file or `includeIf` rules are missing.
+    fn stale_identities(&self, handler: &ScriptHandler) -> Vec<String> {
+        let home = handler.home_dir();
+        let signing = handler.manifest().git.signing;
+        handler
+            .manifest()
+            .git
+            .identities
+            .iter()
+            .filter(|(id, identity)| {
+                let path = git::include_path(id, &home);
+                let content = handler.read_file(&path).unwrap_or_default();
+                let signing_key = signing.and_then(|method| signing::identity_key(handler, method, identity));
+                managed_block::find(&content, BLOCK) != Some(identity.include_body(signing_key.as_deref()).as_str())
+                    || identity
+                        .include_keys()
+                        .iter()
+                        .any(|key| self.global_config(handler, key).as_deref() != Some(path.as_str()))
+            })
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
+    /// Sets up commit signing and rewrites the identities with their own
+    /// signing keys.
+    fn signing(&self, handler: &ScriptHandler, matches: &ArgMatches) -> Result<bool, SetupError> {
+        let method = matches
+            .get_one::<SigningMethod>("method")
+            .copied()
+            .or(handler.manifest().git.signing)
+            .unwrap_or(SigningMethod::Ssh);
+        let key = matches.get_one::<String>("key").map(String::as_str);
+        let import = matches.get_one::<String>("import").map(String::as_str);
+
+        let mut all_set = signing::set_up(handler, method, key, import)?;
+        all_set &= self.write_identities(handler, Some(method))?;
+        Ok(all_set)
+    }
+
+    /// Reports which identity Git uses for the repository at `path`.
+    fn which(&self, handler: &ScriptHandler, path: &Path) -> Result<bool, SetupError> {
+        let path = path
+            .canonicalize()
+            .map_err(|e| SetupError::io(format!("Could not open {}", path.display()), e))?;
+        let path_str = path.to_string_lossy();
+        let home = handler.home_dir();
+        // HOME 可能是符号链接，按真实路径比较
+        let home = PathBuf::from(&home)
+            .canonicalize()
+            .map(|home| home.to_string_lossy().into_owned())
+            .unwrap_or(home);
+
+        let matches = handler.manifest().git.identities_for(&path_str, &home);
+        match matches.last() {
+            Some((id, identity)) => {
+                say!("{}", format!("Identity {}: {} <{}>", id, identity.name, identity.email).green());
+                for (shadowed, _) in matches.iter().rev().skip(1) {
+                    say!("{}", format!("  (overrides {}, which also matches)", shadowed).dimmed());
+                }
+            }
+            None => say!("{}", "No identity matches; the global user.name and user.email apply".cyan()),
+        }
+
+        // 仓库内可能有本地配置覆盖身份，以 git 实际读到的值为准
+        let effective = |key: &str| {
+            handler
+                .query("git", &["-C", &path_str, "config", "--show-origin", "--get", key])
+                .ok()
+                .filter(|output| output.success)
+                .map(|output| output.stdout.trim().to_string())
+        };
+        match (effective("user.name"), effective("user.email")) {
+            (Some(name), Some(email)) => {
+                let value = |line: &str| line.split('\t').nth(1).unwrap_or(line).to_string();
+                let origin = |line: &str| line.split('\t').next().unwrap_or_default().trim_start_matches("file:").to_string();
+                println!("{} <{}>", value(&name), value(&email));
+                say!("{}", format!("  from {}", origin(&email)).dimmed());
+            }
+            _ => say!("{}", "Git has no user.name and user.email for this path".yellow()),
+        }
+        Ok(true)
+    }
+}
+
+impl Step for Git {
+    fn id(&self) -> &'static str {
+        "git"
+    }
+
+    fn title(&self) -> &'static str {
+        "Install and configure Git"
+    }
+
+    fn description(&self) -> &'static str {
+        "Git is the version control system for tracking source code changes. This step installs Git, sets your global name and email, the identities for particular directories, a global gitignore and sensible defaults."
+    }
+
+    fn dependencies(&self) -> &'static [&'static str] {
+        &["brew"]
+    }
+
+    fn check(&self, handler: &ScriptHandler) -> bool {
+        handler.command_exists("git")
+            && self.has_user_name(handler)
+            && self.stale_identities(handler).is_empty()
+            && handler
+                .manifest()
+                .git
+                .signing
+                .is_none_or(|method| signing::is_configured(handler, method))
+    }
+
+    fn diagnose(&self, handler: &ScriptHandler) -> Diagnosis {
+        if !handler.command_exists("git") {
+            return Diagnosis::fail("git not found on PATH", "run `macdevkit-cli git`");
+        }
+
+        let missing: Vec<&str> = ["user.name", "user.email"]
+            .into_iter()
+            .filter(|key| self.global_config(handler, key).is_none())
+            .collect();
+        if !missing.is_empty() {
+            return Diagnosis::fail(
+                format!("{} not set", missing.join(" and ")),
+                format!("run `git config --global {} <value>`", missing[0]),
+            );
+        }
+
+        let stale = self.stale_identities(handler);
+        if !stale.is_empty() {
+            return Diagnosis::warn(format!("identity {} not set up", stale.join(", ")), "run `macdevkit-cli git`");
+        }
+        match handler.manifest().git.signing {
+            Some(method) if !signing::is_configured(handler, method) => {
+                Diagnosis::warn("commit signing not set up", "run `macdevkit-cli git signing`")
+            }
+            Some(_) => Diagnosis::pass("user.name and user.email set, commits signed"),
+            None => Diagnosis::pass("user.name and user.email set"),
+        }
+    }
+
+    fn subcommands(&self) -> Vec<clap::Command> {
+        vec![
+            clap::Command::new("which")
+                .about("Show which Git identity applies to a directory")
+                .arg(
+                    Arg::new("path")
+                        .value_name("PATH")
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .help("Directory to check [default: the current directory]"),
+                ),
+            clap::Command::new("signing")
+                .about("Sign commits and tags with an SSH or GPG key")
+                .arg(
+                    Arg::new("method")
+                        .long("method")
+                        .value_name("METHOD")
+                        .value_parser(clap::value_parser!(SigningMethod))
+                        .help("Kind of key [default: git.signing from the manifest, or ssh]"),
+                )
+                .arg(
+                    Arg::new("key")
+                        .long("key")
+                        .value_name("KEY")
+                        .help("SSH key name or GPG key id [default: the first SSH key, or the GPG key for your Git email]"),
+                )
+                .arg(
+                    Arg::new("import")
+                        .long("import")
+                        .value_name("FILE")
+                        .help("Import a GPG secret key from FILE before signing with it"),
+                ),
+        ]
+    }
+
+    fn run_subcommand(&self, handler: &ScriptHandler, name: &str, matches: &ArgMatches) -> Result<bool, SetupError> {
+        match name {
+            "which" => {
+                let path = matches.get_one::<PathBuf>("path").cloned().unwrap_or_else(|| PathBuf::from("."));
+                self.which(handler, &path)
+            }
+            "signing" => self.signing(handler, matches),
+            _ => Err(format!("Unknown subcommand: git {}", name).into()),
+        }
+    }
+
+    fn apply(&self, handler: &ScriptHandler) -> Result<bool, SetupError> {
+        say!("{}", "\n==== Installing and configuring Git ====\n".blue());
+
+        // 检查Git是否已安装
+        if handler.command_exists("git") {
+            say!("{}", "✓ Git already installed".green());
+        } else {
+            say!("{}", "Installing Git...".cyan());
+
+            // 使用Homebrew安装Git
+            if !handler.brew_install("git", false)? {
+                return Err("Failed to install Git".into());
+            }
+        }
+
+        let mut all_set = true;
+        if self.has_user_name(handler) {
+            say!("{}", "✓ Git already configured".green());
+        } else {
+            // Let user enter git configuration
+            let answers = &handler.answers().git;
+            let git_username = handler.input("Enter your Git username", "git.name", answers.name.as_deref())?;
+            all_set &= handler.git_config_global("user.name", &git_username)?;
+
+            let git_email = handler.input("Enter your Git email", "git.email", answers.email.as_deref())?;
+            all_set &= handler.git_config_global("user.email", &git_email)?;
+
+            say!("{}", "Git configured".green());
+        }
+
+        // 按清单设置默认配置、目录身份和全局 gitignore
+        all_set &= self.write_config(handler)?;
+        let signing = handler.manifest().git.signing;
+        all_set &= self.write_identities(handler, signing)?;
+        self.write_ignore(handler)?;
+
+        match signing {
+            // SSH 密钥由 ssh 步骤生成，届时再设置签名
+            Some(SigningMethod::Ssh) if !signing::has_ssh_key(handler) => {
+                say!("{}", "Commit signing is set up by the ssh step once the key exists".cyan());
+            }
+            Some(method) => all_set &= signing::set_up(handler, method, None, None)?,
+            None => {}
+        }
+
+        say!("{}", "Git setup completed".green());
+        Ok(all_set)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::manifest::Manifest;
+    use crate::plan::Planner;
+    use crate::prompt::{Answers, GitAnswers};
+    use crate::runner::{CommandOutput, RecordingRunner};
+    use crate::test_support::{handler_with, TempHome};
+
+    fn answers() -> Answers {
+        Answers {
+            git: GitAnswers {
+                name: Some("Ada".to_string()),
+                email: Some("ada@example.com".to_string()),
+                gpg_passphrase: None,
+            },
+            ..Answers::default()
+        }
+    }
+
+    #[test]
+    fn apply_sets_identity_defaults_and_ignore() {
+        let home = TempHome::new("git-fresh");
+        let runner = Arc::new(RecordingRunner::new().with_program("git"));
+        let handler = handler_with(Manifest::default(), &runner, &home, answers());
+
+        assert!(Git.apply(&handler).unwrap());
+        let lines = runner.command_lines();
+        for expected in [
+            "git config --global user.name Ada",
+            "git config --global user.email ada@example.com",
+            "git config --global init.defaultBranch main",
+            "git config --global pull.rebase false",
+        ] {
+            assert!(lines.contains(&expected.to_string()), "missing {}", expected);
+        }
+        assert!(!lines.iter().any(|line| line.starts_with("brew ")));
+
+        let ignore = std::fs::read_to_string(format!("{}/ignore", git::config_dir(&home.as_str()))).unwrap();
+        assert!(ignore.contains(".DS_Store"));
+    }
+
+    #[test]
+    fn apply_keeps_existing_settings() {
+        let home = TempHome::new("git-configured");
+        let runner = Arc::new(
+            RecordingRunner::new()
+                .with_program("git")
+                .respond("git config --global user.name", CommandOutput::success("Ada\n"))
+                .respond("git config --global init.defaultBranch", CommandOutput::success("master\n"))
+                .respond("git config --global core.editor", CommandOutput::success("vim\n"))
+                .respond("git config --global pull.rebase", CommandOutput::success("true\n"))
+                .respond("git config --global fetch.prune", CommandOutput::success("false\n")),
+        );
+        let handler = handler_with(Manifest::default(), &runner, &home, Answers::default());
+
+        assert!(Git.apply(&handler).unwrap());
+        let writes: Vec<_> = runner
+            .invocations()
+            .into_iter()
+            .filter(|invocation| invocation.args.len() == 4 && invocation.args[..2] == ["config", "--global"] && invocation.args[2] != "--get")
+            .collect();
+        assert!(writes.is_empty(), "unexpected {:?}", writes);
+    }
+
+    #[test]
+    fn relative_excludes_file_is_under_home() {
+        let home = TempHome::new("git-excludes");
+        let runner = Arc::new(
+            RecordingRunner::new()
+                .with_program("git")
+                .respond("git config --global core.excludesFile", CommandOutput::success(".gitignore_global\n")),
+        );
+        let handler = handler_with(Manifest::default(), &runner, &home, answers());
+
+        assert!(Git.apply(&handler).unwrap());
+        let ignore = std::fs::read_to_string(home.join(".gitignore_global")).unwrap();
+        assert!(ignore.contains(".DS_Store"));
+        assert!(!Path::new(".gitignore_global").exists());
+    }
+
+    #[test]
+    fn apply_installs_missing_git() {
+        let home = TempHome::new("git-missing");
+        let runner = Arc::new(RecordingRunner::new().respond("brew list", CommandOutput::failure("")));
+        let handler = handler_with(Manifest::default(), &runner, &home, answers());
+
+        assert!(Git.apply(&handler).unwrap());
+        assert!(runner.command_lines().contains(&"brew install git".to_string()));
+    }
+
+    #[test]
+    fn dry_run_plans_with_placeholders() {
+        let home = TempHome::new("git-dry-run");
+        let runner = Arc::new(RecordingRunner::new().with_program("git"));
+        let handler = handler_with(Manifest::default(), &runner, &home, Answers::default()).with_planner(Planner::new(false));
+
+        assert!(Git.apply(&handler).unwrap());
+        let plan = handler.planner().unwrap().to_json();
+        assert!(plan.contains("<git.name>"));
+        assert!(plan.contains("<git.email>"));
+        assert!(!runner.command_lines().iter().any(|line| line.contains("<git.name>")));
+    }
+}
@@ -0,0 +1,46 @@
+use colored::*;
+use std::path::Path;
+
+use super::Step;
+use crate::error::SetupError;
+use crate::script_handler::ScriptHandler;
+
+pub struct Iterm;
+
+impl Step for Iterm {
+    fn id(&self) -> &'static str {
+        "iterm"
+    }
+
+    fn title(&self) -> &'static str {
+        "Install iTerm2"
+    }
+
+    fn description(&self) -> &'static str {
+        "iTerm2 replaces the default Terminal app with split panes, search, autocomplete and more customization."
+    }
+
+    fn dependencies(&self) -> &'static [&'static str] {
+        &["brew"]
+    }
+
+    fn check(&self, _handler: &ScriptHandler) -> bool {
+        Path::new("/Applications/iTerm.app").exists()
+    }
+
+    fn apply(&self, handler: &ScriptHandler) -> Result<bool, SetupError> {
+        say!("{}", "\n==== Installing iTerm2 ====\n".blue());
+
+        if self.check(handler) {
+            say!("{}", "✓ iTerm2 already installed".green());
+            return Ok(true);
+        }
+
+        // 使用Homebrew安装iTerm2
+        if !handler.brew_install("iterm2", true)? {
+            return Err("Failed to install iTerm2".into());
+        }
+
+        Ok(true)
+    }
+}
@@ -0,0 +1,111 @@
+use colored::*;
+
+use super::Step;
+use crate::error::SetupError;
+use crate::script_handler::ScriptHandler;
+
+pub struct MacOS;
+
+impl Step for MacOS {
+    fn id(&self) -> &'static str {
+        "macos"
+    }
+
+    fn title(&self) -> &'static str {
+        "Configure macOS settings"
+    }
+
+    fn description(&self) -> &'static str {
+        "Applies the macOS defaults listed in the manifest, by default developer-friendly Finder, keyboard repeat and auto-correct settings."
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        &["mac-os"]
+    }
+
+    fn check(&self, handler: &ScriptHandler) -> bool {
+        handler.manifest().defaults.iter().all(|setting| {
+            let expected = match &setting.value {
+                toml::Value::Boolean(b) => (*b as i32).to_string(),
+                toml::Value::String(s) => handler.expand_home(s),
+                other => setting.write_args().map(|[_, value]| value).unwrap_or_else(|_| other.to_string()),
+            };
+            handler
+                .query("defaults", &["read", &setting.domain, &setting.key])
+                .map(|output| output.stdout.trim() == expected)
+                .unwrap_or(false)
+        })
+    }
+
+    fn apply(&self, handler: &ScriptHandler) -> Result<bool, SetupError> {
+        say!("{}", "\n==== Configuring macOS Settings ====\n".blue());
+
+        let mut failed = Vec::new();
+        for setting in handler.manifest().defaults.iter() {
+            say!("Setting {} {}...", setting.domain, setting.key);
+            let [value_type, value] = setting.write_args()?;
+
+            // 字符串值中的 ~/ 展开为主目录，截图目录需要事先存在
+            let value = if value_type == "-string" { handler.expand_home(&value) } else { value };
+            if setting.domain == "com.apple.screencapture" && setting.key == "location" {
+                handler.create_dir_all(&value)?;
+            }
+
+            if !handler.defaults_write(&setting.domain, &setting.key, &value_type, &value)? {
+                say!("{}", format!("Failed to set {} {}", setting.domain, setting.key).red());
+                failed.push(format!("{} {}", setting.domain, setting.key));
+            }
+        }
+
+        say!("Restarting Finder to apply changes...");
+        for process in ["Finder", "SystemUIServer"] {
+            // 进程未运行时 killall 会失败，设置仍会在下次登录时生效
+            if !handler.run("killall", &[process])? {
+                say!("{}", format!("Warning: could not restart {}; log out and back in to apply the settings", process).yellow());
+            }
+        }
+
+        if !failed.is_empty() {
+            say!("{}", format!("Could not set {}", failed.join(", ")).red());
+            return Ok(false);
+        }
+        say!("{}", "macOS settings configured".green());
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::runner::{CommandOutput, RecordingRunner};
+    use crate::test_support::{handler, TempHome};
+
+    #[test]
+    fn apply_writes_defaults_and_restarts_finder() {
+        let home = TempHome::new("macos-applied");
+        let runner = Arc::new(RecordingRunner::new());
+
+        assert!(MacOS.apply(&handler(&runner, &home)).unwrap());
+        let lines = runner.command_lines();
+        assert!(lines.iter().any(|line| line.starts_with("defaults write ")));
+        assert!(lines.contains(&"killall Finder".to_string()));
+    }
+
+    #[test]
+    fn apply_reports_failed_defaults() {
+        let home = TempHome::new("macos-failed");
+        let runner = Arc::new(RecordingRunner::new().respond("defaults write", CommandOutput::failure("Could not write domain")));
+
+        assert!(!MacOS.apply(&handler(&runner, &home)).unwrap());
+    }
+
+    #[test]
+    fn apply_only_warns_when_finder_is_not_running() {
+        let home = TempHome::new("macos-killall");
+        let runner = Arc::new(RecordingRunner::new().respond("killall", CommandOutput::failure("No matching processes")));
+
+        assert!(MacOS.apply(&handler(&runner, &home)).unwrap());
+    }
+}
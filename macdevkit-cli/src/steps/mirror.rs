@@ -0,0 +1,287 @@
+use clap::{value_parser, Arg, ArgMatches};
+use colored::*;
+use std::path::Path;
+
+use super::Step;
+use crate::error::SetupError;
+use crate::managed_block;
+use crate::mirror::{MirrorName, Registries};
+use crate::script_handler::ScriptHandler;
+
+/// Name of the managed block written to each config file.
+const BLOCK: &str = "mirror";
+
+pub struct Mirror;
+
+/// A config file that holds a registry URL in a managed block.
+struct ConfigFile {
+    path: String,
+    body: String,
+    /// Line that must not appear outside the block, because the block
+    /// would then define the same setting twice.
+    conflict: &'static str,
+}
+
+impl Mirror {
+    fn config_files(&self, handler: &ScriptHandler, registries: &Registries) -> Vec<ConfigFile> {
+        let home = handler.home_dir();
+        let mut files = Vec::new();
+
+        if let Some(url) = &registries.npm {
+            files.push(ConfigFile {
+                path: format!("{}/.npmrc", home),
+                body: format!("registry={}", url),
+                conflict: "registry",
+            });
+        }
+        if let Some(url) = &registries.pypi {
+            files.push(ConfigFile {
+                path: pip_config_path(&home),
+                body: format!("[global]\nindex-url = {}", url),
+                conflict: "[global]",
+            });
+        }
+        if let Some(url) = &registries.crates {
+            let cargo_home = std::env::var("CARGO_HOME").unwrap_or_else(|_| format!("{}/.cargo", home));
+            files.push(ConfigFile {
+                path: format!("{}/config.toml", cargo_home),
+                body: format!(
+                    "[source.crates-io]\nreplace-with = \"macdevkit-mirror\"\n\n[source.macdevkit-mirror]\nregistry = \"{}\"",
+                    url
+                ),
+                conflict: "[source.crates-io]",
+            });
+        }
+        files
+    }
+
+    /// Points npm, pip, Go, Cargo and Git at the mirror `name`.
+    fn set(&self, handler: &ScriptHandler, name: MirrorName) -> Result<bool, SetupError> {
+        let registries = Registries::new(name, &handler.manifest().mirror);
+        if registries == Registries::default() {
+            return Err(SetupError::Config(
+                "The custom mirror has no registry URLs; set them in the [mirror] table of the manifest".to_string(),
+            ));
+        }
+
+        say!("{}", format!("Using mirror {}", name.as_str()).cyan());
+
+        let mut all_set = true;
+        for file in self.config_files(handler, &registries) {
+            let current = handler.read_file(&file.path)?;
+            let outside = managed_block::remove(&current, BLOCK);
+            if outside.lines().any(|line| line.trim_start().starts_with(file.conflict)) {
+                say!(
+                    "{}",
+                    format!("Warning: {} already sets {}; leaving it unchanged", file.path, file.conflict).yellow()
+                );
+                all_set = false;
+                continue;
+            }
+
+            if let Some(parent) = Path::new(&file.path).parent() {
+                handler.create_dir_all(&parent.to_string_lossy())?;
+            }
+            handler.write_block(&file.path, BLOCK, &file.body)?;
+            say!("{}", format!("✓ Updated {}", file.path).green());
+        }
+
+        if let Some(goproxy) = &registries.goproxy {
+            if handler.command_exists("go") {
+                if handler.go_env_write("GOPROXY", goproxy)? {
+                    say!("{}", format!("✓ GOPROXY set to {}", goproxy).green());
+                } else {
+                    say!("{}", "Failed to set GOPROXY".red());
+                    all_set = false;
+                }
+            } else {
+                say!("{}", "Go is not installed; skipping GOPROXY".yellow());
+            }
+        }
+
+        if let Some(base) = &registries.github {
+            let key = format!("url.{}.insteadOf", base);
+            if !handler.command_exists("git") {
+                say!("{}", "Git is not installed; skipping the GitHub proxy".yellow());
+            } else if handler.git_config_global(&key, "https://github.com/")? {
+                say!("{}", format!("✓ GitHub URLs now go through {}", base).green());
+            } else {
+                say!("{}", "Failed to set the GitHub proxy".red());
+                all_set = false;
+            }
+        }
+
+        Ok(all_set)
+    }
+}
+
+impl Step for Mirror {
+    fn id(&self) -> &'static str {
+        "mirror"
+    }
+
+    fn title(&self) -> &'static str {
+        "Configure package registry mirrors"
+    }
+
+    fn description(&self) -> &'static str {
+        "Points npm, pip, Go, Cargo and GitHub at the mirror chosen with --mirror or in the [mirror] table of the manifest. Does nothing if no mirror is chosen."
+    }
+
+    fn check(&self, handler: &ScriptHandler) -> bool {
+        let Some(name) = handler.manifest().mirror.name else {
+            return true;
+        };
+        let registries = Registries::new(name, &handler.manifest().mirror);
+        self.config_files(handler, &registries).iter().all(|file| {
+            let current = handler.read_file(&file.path).unwrap_or_default();
+            managed_block::find(&current, BLOCK) == Some(file.body.as_str())
+        })
+    }
+
+    fn subcommands(&self) -> Vec<clap::Command> {
+        vec![
+            clap::Command::new("set")
+                .about("Point npm, pip, Go, Cargo and GitHub at a mirror")
+                .arg(
+                    Arg::new("profile")
+                        .value_name("PROFILE")
+                        .required(true)
+                        .value_parser(value_parser!(MirrorName)),
+                ),
+            clap::Command::new("reset").about("Restore the registry settings from before `mirror set`"),
+        ]
+    }
+
+    fn run_subcommand(&self, handler: &ScriptHandler, name: &str, matches: &ArgMatches) -> Result<bool, SetupError> {
+        match name {
+            "set" => {
+                let profile = matches.get_one::<MirrorName>("profile").ok_or("Missing mirror profile")?;
+                self.set(handler, *profile)
+            }
+            "reset" => handler.undo_section(self),
+            _ => Err(format!("Unknown subcommand: mirror {}", name).into()),
+        }
+    }
+
+    fn apply(&self, handler: &ScriptHandler) -> Result<bool, SetupError> {
+        say!("{}", "\n==== Configuring Package Registry Mirrors ====\n".blue());
+
+        match handler.manifest().mirror.name {
+            Some(name) => self.set(handler, name),
+            None => {
+                say!("{}", "No mirror chosen; pass --mirror or set mirror.name in the manifest".yellow());
+                Ok(true)
+            }
+        }
+    }
+}
+
+/// pip reads `~/Library/Application Support/pip/pip.conf` on macOS if that
+/// directory exists, and `~/.config/pip/pip.conf` otherwise.
+fn pip_config_path(home: &str) -> String {
+    let library = format!("{}/Library/Application Support/pip", home);
+    if Path::new(&library).is_dir() {
+        format!("{}/pip.conf", library)
+    } else {
+        format!("{}/.config/pip/pip.conf", home)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::changes::ChangeLog;
+    use crate::manifest::Manifest;
+    use crate::prompt::Answers;
+    use crate::runner::{CommandOutput, RecordingRunner};
+    use crate::test_support::{handler_with, TempHome};
+
+    const PROXY: &str = "https://gh.example.com/https://github.com/";
+
+    fn manifest() -> Manifest {
+        let mut manifest = Manifest::default();
+        manifest.mirror.github_proxy = Some(PROXY.to_string());
+        manifest
+    }
+
+    fn run(handler: &ScriptHandler, args: &[&str]) -> bool {
+        let command = Mirror.subcommands().into_iter().find(|command| command.get_name() == args[0]).unwrap();
+        let matches = command.try_get_matches_from(args).unwrap();
+        handler.run_subcommand(&Mirror, args[0], &matches).unwrap()
+    }
+
+    fn read(home: &TempHome, relative: &str) -> String {
+        std::fs::read_to_string(home.join(relative)).unwrap_or_default()
+    }
+
+    #[test]
+    fn set_and_reset_every_registry() {
+        let home = TempHome::new("mirror-set");
+        // 测试进程中只有本测试读取 CARGO_HOME
+        std::env::set_var("CARGO_HOME", home.join(".cargo"));
+        std::fs::write(home.join(".npmrc"), "save-exact=true\n").unwrap();
+        let runner = Arc::new(
+            RecordingRunner::new()
+                .with_program("go")
+                .with_program("git")
+                .respond("git config --global --get", CommandOutput::failure(""))
+                .respond("go env GOENV", CommandOutput::success(&format!("{}\n", home.join("goenv")))),
+        );
+        let changes = ChangeLog::open(home.path.join("changes.json")).unwrap();
+        let handler = handler_with(manifest(), &runner, &home, Answers::default()).with_changes(changes);
+
+        assert!(run(&handler, &["set", "tuna"]));
+        assert_eq!(managed_block::find(&read(&home, ".npmrc"), BLOCK), Some("registry=https://registry.npmmirror.com"));
+        assert!(read(&home, ".npmrc").starts_with("save-exact=true\n"));
+        assert_eq!(
+            managed_block::find(&read(&home, ".config/pip/pip.conf"), BLOCK),
+            Some("[global]\nindex-url = https://pypi.tuna.tsinghua.edu.cn/simple")
+        );
+        let cargo = read(&home, ".cargo/config.toml");
+        assert!(cargo.contains("replace-with = \"macdevkit-mirror\""));
+        assert!(cargo.contains("registry = \"sparse+https://mirrors.tuna.tsinghua.edu.cn/crates.io-index/\""));
+        let lines = runner.command_lines();
+        assert!(lines.contains(&"go env -w GOPROXY=https://goproxy.cn,direct".to_string()));
+        assert!(lines.contains(&format!("git config --global url.{}.insteadOf https://github.com/", PROXY)));
+
+        assert!(run(&handler, &["reset"]));
+        assert_eq!(read(&home, ".npmrc"), "save-exact=true\n");
+        assert_eq!(read(&home, ".config/pip/pip.conf"), "");
+        assert_eq!(read(&home, ".cargo/config.toml"), "");
+        let lines = runner.command_lines();
+        assert!(lines.contains(&"go env -u GOPROXY".to_string()));
+        assert!(lines.contains(&format!("git config --global --unset url.{}.insteadOf", PROXY)));
+    }
+
+    #[test]
+    fn missing_go_and_git_are_skipped() {
+        let home = TempHome::new("mirror-no-tools");
+        let mut manifest = manifest();
+        manifest.mirror.name = Some(MirrorName::Custom);
+        manifest.mirror.npm_registry = Some("https://npm.example.com".to_string());
+        manifest.mirror.goproxy = Some("https://goproxy.example.com".to_string());
+        let runner = Arc::new(RecordingRunner::new());
+        let handler = handler_with(manifest, &runner, &home, Answers::default());
+
+        assert!(Mirror.apply(&handler).unwrap());
+        assert_eq!(managed_block::find(&read(&home, ".npmrc"), BLOCK), Some("registry=https://npm.example.com"));
+        assert!(!runner.command_lines().iter().any(|line| line.starts_with("go ") || line.starts_with("git ")));
+    }
+
+    #[test]
+    fn existing_registry_is_left_alone() {
+        let home = TempHome::new("mirror-conflict");
+        std::fs::write(home.join(".npmrc"), "registry=https://npm.example.com\n").unwrap();
+        let mut manifest = Manifest::default();
+        manifest.mirror.name = Some(MirrorName::Custom);
+        manifest.mirror.npm_registry = Some("https://registry.npmmirror.com".to_string());
+        let runner = Arc::new(RecordingRunner::new());
+        let handler = handler_with(manifest, &runner, &home, Answers::default());
+
+        assert!(!Mirror.apply(&handler).unwrap());
+        assert_eq!(read(&home, ".npmrc"), "registry=https://npm.example.com\n");
+    }
+}
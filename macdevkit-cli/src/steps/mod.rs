@@ -0,0 +1,108 @@
+use clap::ArgMatches;
+
+use crate::doctor::Diagnosis;
+use crate::error::SetupError;
+use crate::install::Batch;
+use crate::script_handler::ScriptHandler;
+
+mod apps;
+mod brew;
+mod devtools;
+mod docker;
+mod git;
+mod iterm;
+mod macos;
+mod mirror;
+mod node;
+mod ssh;
+mod vscode;
+mod workspace;
+mod xcode;
+mod zsh;
+
+/// A single section of the setup.
+///
+/// Every step is listed once in [`registry`], which drives the clap
+/// subcommands, the interactive menu and the full setup. The full setup runs
+/// the steps in registry order, moved only as far as [`Step::dependencies`]
+/// requires; see [`crate::graph`].
+pub trait Step {
+    /// Subcommand name, also used as the key in the journal and change log.
+    fn id(&self) -> &'static str;
+
+    /// Short title shown in the menu and in confirmation prompts.
+    fn title(&self) -> &'static str;
+
+    /// Longer explanation of what the step does.
+    fn description(&self) -> &'static str;
+
+    /// Additional subcommand names accepted for this step.
+    fn aliases(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Ids of the steps that must have run first, e.g. `brew` for `vscode`.
+    fn dependencies(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Returns true if the step's result is already present on this machine.
+    fn check(&self, handler: &ScriptHandler) -> bool;
+
+    /// Read-only health check used by `doctor`. By default it reports
+    /// [`Step::check`] as pass or fail.
+    fn diagnose(&self, handler: &ScriptHandler) -> Diagnosis {
+        if self.check(handler) {
+            Diagnosis::pass("OK")
+        } else {
+            Diagnosis::fail("Not set up", format!("run `macdevkit-cli {}`", self.id()))
+        }
+    }
+
+    /// Runs the step with the built-in Rust implementation.
+    fn apply(&self, handler: &ScriptHandler) -> Result<bool, SetupError>;
+
+    /// Packages to install once [`Step::apply`] has succeeded, leaving out
+    /// those already installed. The full setup installs the packages of all
+    /// steps together, so no step may depend on them.
+    fn packages(&self, _handler: &ScriptHandler) -> Vec<Batch> {
+        Vec::new()
+    }
+
+    /// Extra subcommands nested under the step's own, e.g. `brew export`.
+    fn subcommands(&self) -> Vec<clap::Command> {
+        Vec::new()
+    }
+
+    /// Runs one of the commands returned by [`Step::subcommands`].
+    fn run_subcommand(&self, _handler: &ScriptHandler, name: &str, _matches: &ArgMatches) -> Result<bool, SetupError> {
+        Err(format!("Unknown subcommand: {} {}", self.id(), name).into())
+    }
+}
+
+/// All steps, in the order the full setup runs them.
+pub fn registry() -> Vec<Box<dyn Step>> {
+    vec![
+        Box::new(xcode::Xcode),
+        Box::new(brew::Brew),
+        Box::new(mirror::Mirror),
+        Box::new(git::Git),
+        Box::new(ssh::Ssh),
+        Box::new(vscode::Vscode),
+        Box::new(node::Node),
+        Box::new(iterm::Iterm),
+        Box::new(zsh::Zsh),
+        Box::new(docker::Docker),
+        Box::new(devtools::DevTools),
+        Box::new(apps::Apps),
+        Box::new(macos::MacOS),
+        Box::new(workspace::Workspace),
+    ]
+}
+
+/// Looks up a step by its id or one of its aliases.
+pub fn find(name: &str) -> Option<Box<dyn Step>> {
+    registry()
+        .into_iter()
+        .find(|step| step.id() == name || step.aliases().contains(&name))
+}
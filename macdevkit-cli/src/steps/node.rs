@@ -0,0 +1,311 @@
+use colored::*;
+use std::path::Path;
+
+use super::Step;
+use crate::doctor::Diagnosis;
+use crate::error::SetupError;
+use crate::install::Batch;
+use crate::node::{self, NodeManager};
+use crate::script_handler::ScriptHandler;
+use crate::shell_profile;
+
+/// Package managers that corepack provides.
+const COREPACK_TOOLS: [&str; 2] = ["pnpm", "yarn"];
+
+pub struct Node;
+
+impl Node {
+    fn manager(&self, handler: &ScriptHandler) -> NodeManager {
+        handler.manifest().node.manager
+    }
+
+    /// The version from the manifest, else from `.nvmrc` in the current or
+    /// home directory, else the latest LTS.
+    fn wanted_version(&self, handler: &ScriptHandler) -> String {
+        if let Some(version) = &handler.manifest().node.version {
+            return version.clone();
+        }
+        let dirs = [
+            std::env::current_dir().map(|dir| dir.to_string_lossy().into_owned()).unwrap_or_default(),
+            handler.home_dir(),
+        ];
+        dirs.iter()
+            .filter_map(|dir| std::fs::read_to_string(Path::new(dir).join(".nvmrc")).ok())
+            .find_map(|content| node::parse_nvmrc(&content))
+            .unwrap_or_else(|| node::LTS.to_string())
+    }
+
+    /// Shell code that puts the manager's default Node.js on `PATH`.
+    fn prelude(&self, handler: &ScriptHandler) -> String {
+        // dry-run 时 nvm 尚未安装，使用 Homebrew 将安装的位置
+        let nvm_script = node::nvm_script(&handler.home_dir(), &handler.platform().brew_prefix);
+        self.manager(handler).prelude(&nvm_script)
+    }
+
+    fn manager_installed(&self, handler: &ScriptHandler) -> bool {
+        match self.manager(handler) {
+            NodeManager::Nvm => node::find_nvm(&handler.home_dir(), &handler.platform().brew_prefix).is_some(),
+            manager => handler.command_exists(manager.name()),
+        }
+    }
+
+    /// Installs the manager with Homebrew and loads it from the shell
+    /// profiles.
+    fn install_manager(&self, handler: &ScriptHandler) -> Result<(), SetupError> {
+        let manager = self.manager(handler);
+        if !handler.brew_install(manager.name(), false)? {
+            return Err(format!("Failed to install {}", manager.name()).into());
+        }
+        if manager == NodeManager::Nvm {
+            handler.create_dir_all(&format!("{}/.nvm", handler.home_dir()))?;
+        }
+
+        let block = manager.profile_block(&handler.platform().brew_prefix);
+        shell_profile::write(handler, &block)?;
+
+        say!("{}", format!("{} installed", manager.name()).green());
+        Ok(())
+    }
+
+    /// Version of the manager's default Node.js, e.g. `v22.11.0`.
+    fn node_version(&self, handler: &ScriptHandler) -> Option<String> {
+        self.query_node(handler, &["node", "--version"])
+            .map(|version| version.trim().to_string())
+            .filter(|version| !version.is_empty())
+    }
+
+    /// Output of `command` run with the manager loaded, `None` if it fails.
+    fn query_node(&self, handler: &ScriptHandler, command: &[&str]) -> Option<String> {
+        // 管理器不存在时前导代码不生效，会查到系统自带的 node
+        if !self.manager_installed(handler) {
+            return None;
+        }
+        let args = node::bash_args(&self.prelude(handler), command);
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        handler
+            .query("bash", &args)
+            .ok()
+            .filter(|output| output.success)
+            .map(|output| output.stdout)
+    }
+
+    fn run_node(&self, handler: &ScriptHandler, command: &[&str]) -> Result<(), SetupError> {
+        let args = node::bash_args(&self.prelude(handler), command);
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        handler.run_checked("bash", &args)
+    }
+
+    /// Installs the Node.js version the manifest asks for and makes it the
+    /// default, unless the default already matches.
+    fn install_node(&self, handler: &ScriptHandler) -> Result<(), SetupError> {
+        let manager = self.manager(handler);
+        let wanted = self.wanted_version(handler);
+        match self.node_version(handler) {
+            Some(version) if node::version_matches(&wanted, &version) => {
+                say!("{}", format!("✓ Node.js {} already installed", version).green());
+            }
+            _ => {
+                let shown = if wanted == node::LTS { "LTS" } else { wanted.as_str() };
+                say!("{}", format!("Installing Node.js {} with {}", shown, manager.name()).cyan());
+                for command in manager.install_commands(&wanted) {
+                    let command: Vec<&str> = command.iter().map(String::as_str).collect();
+                    self.run_node(handler, &command)?;
+                }
+                say!("{}", format!("Node.js {} installed and set as default", shown).green());
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs `corepack enable`, which puts pnpm and yarn next to `node`.
+    fn enable_corepack(&self, handler: &ScriptHandler) -> Result<(), SetupError> {
+        let on_path = |tool: &str| self.query_node(handler, &["command", "-v", tool]).is_some();
+        if COREPACK_TOOLS.iter().all(|tool| on_path(tool)) {
+            say!("{}", "✓ pnpm and yarn already available".green());
+            return Ok(());
+        }
+
+        // Node.js 25 起不再自带 corepack
+        if !on_path("corepack") {
+            self.run_node(handler, &["npm", "install", "-g", "corepack"])?;
+        }
+        match self.manager(handler) {
+            // Volta 的垫片目录在 node 之前，需要把 pnpm 和 yarn 装到那里
+            NodeManager::Volta => {
+                let volta_home = std::env::var("VOLTA_HOME").unwrap_or_else(|_| format!("{}/.volta", handler.home_dir()));
+                let bin = format!("{}/bin", volta_home);
+                self.run_node(handler, &["corepack", "enable", "--install-directory", &bin])?;
+            }
+            _ => self.run_node(handler, &["corepack", "enable"])?,
+        }
+        say!("{}", "✓ corepack enabled for pnpm and yarn".green());
+        Ok(())
+    }
+
+    /// Global packages from the manifest, without those corepack provides.
+    fn globals(&self, handler: &ScriptHandler) -> Vec<String> {
+        let manifest = handler.manifest();
+        manifest
+            .npm
+            .globals
+            .iter()
+            .filter(|package| !(manifest.node.corepack && COREPACK_TOOLS.contains(&node::package_name(package))))
+            .cloned()
+            .collect()
+    }
+}
+
+impl Step for Node {
+    fn id(&self) -> &'static str {
+        "node"
+    }
+
+    fn title(&self) -> &'static str {
+        "Install Node.js"
+    }
+
+    fn description(&self) -> &'static str {
+        "A version manager lets you install and switch between Node.js versions. This step installs nvm, fnm, Volta or mise, the Node.js version from the manifest or .nvmrc (the latest LTS by default), enables corepack for pnpm and yarn and installs the global npm packages listed in the manifest."
+    }
+
+    fn dependencies(&self) -> &'static [&'static str] {
+        &["brew"]
+    }
+
+    fn check(&self, handler: &ScriptHandler) -> bool {
+        self.manager_installed(handler)
+            && self
+                .node_version(handler)
+                .is_some_and(|version| node::version_matches(&self.wanted_version(handler), &version))
+    }
+
+    fn diagnose(&self, handler: &ScriptHandler) -> Diagnosis {
+        let name = self.manager(handler).name();
+        if !self.manager_installed(handler) {
+            return Diagnosis::fail(format!("{} not found", name), "run `macdevkit-cli node`");
+        }
+
+        let wanted = self.wanted_version(handler);
+        match self.node_version(handler) {
+            Some(version) if node::version_matches(&wanted, &version) => {
+                Diagnosis::pass(format!("{} installed, Node.js {} is the default", name, version))
+            }
+            Some(version) => Diagnosis::warn(
+                format!("default Node.js is {}, not {}", version, wanted),
+                "run `macdevkit-cli node`",
+            ),
+            None => Diagnosis::warn(format!("{} has no default Node.js", name), "run `macdevkit-cli node`"),
+        }
+    }
+
+    fn apply(&self, handler: &ScriptHandler) -> Result<bool, SetupError> {
+        let manager = self.manager(handler);
+        say!("{}", format!("\n==== Installing Node.js via {} ====\n", manager.name()).blue());
+
+        if self.manager_installed(handler) {
+            say!("{}", format!("✓ {} already installed", manager.name()).green());
+        } else {
+            self.install_manager(handler)?;
+        }
+
+        self.install_node(handler)?;
+        if handler.manifest().node.corepack {
+            self.enable_corepack(handler)?;
+        }
+
+        Ok(true)
+    }
+
+    fn packages(&self, handler: &ScriptHandler) -> Vec<Batch> {
+        let prelude = self.prelude(handler);
+
+        // npm ls 在依赖有问题时返回非零，但仍输出完整的 JSON
+        let args = node::bash_args(&prelude, &["npm", "ls", "-g", "--depth=0", "--json"]);
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let installed = if self.manager_installed(handler) {
+            handler
+                .query("bash", &args)
+                .map(|output| node::installed_globals(&output.stdout))
+                .unwrap_or_default()
+        } else {
+            Default::default()
+        };
+
+        let missing = self
+            .globals(handler)
+            .into_iter()
+            .filter(|package| !installed.contains(node::package_name(package)))
+            .collect();
+
+        vec![Batch::npm(self.id(), missing, self.manager(handler), prelude)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::changes::ChangeLog;
+    use crate::manifest::Manifest;
+    use crate::prompt::Answers;
+    use crate::runner::{CommandOutput, RecordingRunner};
+    use crate::test_support::{handler_with, TempHome};
+
+    #[test]
+    fn packages_skip_installed_globals() {
+        let home = TempHome::new("node-packages");
+        let mut manifest = Manifest::default();
+        manifest.node.manager = NodeManager::Fnm;
+        manifest.npm.globals = vec!["typescript@5".to_string(), "@vue/cli@latest".to_string(), "eslint".to_string()];
+        let prelude = NodeManager::Fnm.prelude("");
+        let npm_ls = format!("bash -c {} && \"$@\" bash npm ls -g --depth=0 --json", prelude);
+        let json = r#"{"dependencies":{"typescript":{"version":"5.6.3"},"@vue/cli":{"version":"5.0.8"}}}"#;
+        // npm ls 因依赖问题失败时也会输出 JSON
+        let runner = Arc::new(
+            RecordingRunner::new()
+                .with_program("fnm")
+                .respond(&npm_ls, CommandOutput { success: false, code: Some(1), ..CommandOutput::success(json) }),
+        );
+        let handler = handler_with(manifest, &runner, &home, Answers::default());
+
+        let batches = Node.packages(&handler);
+        let names: Vec<_> = batches.iter().flat_map(|batch| batch.names.iter()).collect();
+        assert_eq!(names, ["eslint"]);
+        assert!(runner.command_lines().contains(&npm_ls));
+    }
+
+    #[test]
+    fn packages_install_everything_without_the_manager() {
+        let home = TempHome::new("node-packages-fresh");
+        let mut manifest = Manifest::default();
+        manifest.node.manager = NodeManager::Fnm;
+        manifest.npm.globals = vec!["typescript".to_string()];
+        let runner = Arc::new(RecordingRunner::new());
+        let handler = handler_with(manifest, &runner, &home, Answers::default());
+
+        let batches = Node.packages(&handler);
+        assert_eq!(batches[0].names, ["typescript"]);
+        assert!(!runner.command_lines().iter().any(|line| line.contains("npm ls")));
+    }
+
+    #[test]
+    fn undo_uninstalls_globals_with_the_manager() {
+        let home = TempHome::new("node-undo");
+        let mut manifest = Manifest::default();
+        manifest.node.manager = NodeManager::Fnm;
+        manifest.npm.globals = vec!["typescript".to_string()];
+        let runner = Arc::new(RecordingRunner::new().with_program("fnm"));
+        let changes = ChangeLog::open(home.path.join("changes.json")).unwrap();
+        let handler = handler_with(manifest, &runner, &home, Answers::default()).with_changes(changes);
+
+        assert!(handler.run_section(&Node).unwrap());
+        let prelude = NodeManager::Fnm.prelude("");
+        let install = format!("bash -c {} && \"$@\" bash npm install -g typescript", prelude);
+        assert!(runner.command_lines().contains(&install));
+
+        assert!(handler.undo_section(&Node).unwrap());
+        let uninstall = format!("bash -c {} && \"$@\" bash npm uninstall -g typescript", prelude);
+        assert_eq!(runner.command_lines().last(), Some(&uninstall));
+    }
+}
@@ -0,0 +1,613 @@
+use clap::{value_parser, Arg, ArgAction, ArgMatches};
+use colored::*;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::Step;
+use crate::doctor::Diagnosis;
+use crate::error::SetupError;
+use crate::git_host::{GitHost, KeyUse, Provider};
+use crate::managed_block;
+use crate::script_handler::ScriptHandler;
+use crate::signing::{self, SigningMethod};
+use crate::ssh::{self, SshKey};
+
+pub struct Ssh;
+
+/// An `SSH_ASKPASS` program that answers each prompt with the next line of
+/// its stdin, which it shares with `ssh-keygen`. Deleted when dropped.
+struct Askpass(String);
+
+impl Askpass {
+    fn create(handler: &ScriptHandler) -> Result<Self, SetupError> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let name = format!("macdevkit-askpass-{}-{}", std::process::id(), COUNT.fetch_add(1, Ordering::Relaxed));
+        let path = std::env::temp_dir().join(name);
+        let askpass = Askpass(path.to_string_lossy().into_owned());
+        if handler.planner().is_none() {
+            use std::os::unix::fs::PermissionsExt;
+            let write = || {
+                std::fs::write(&path, "#!/bin/sh\nIFS= read -r passphrase\nprintf '%s\\n' \"$passphrase\"\n")?;
+                std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o700))
+            };
+            write().map_err(|e| SetupError::io(format!("Could not write {}", askpass.0), e))?;
+        }
+        Ok(askpass)
+    }
+}
+
+impl Drop for Askpass {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+impl Ssh {
+    /// Generates the missing keys, writes their `~/.ssh/config` entries and
+    /// adds the new keys to the agent. Returns the keys it generated.
+    fn set_up(&self, handler: &ScriptHandler, keys: &[SshKey]) -> Result<Vec<SshKey>, SetupError> {
+        let home = handler.home_dir();
+        let ssh_dir = format!("{}/.ssh", home);
+        if !Path::new(&ssh_dir).is_dir() {
+            handler.create_dir_all(&ssh_dir)?;
+            handler.run_checked("chmod", &["700", &ssh_dir])?;
+        }
+
+        let mut generated = Vec::new();
+        let mut git_email = None;
+        for key in keys {
+            if Path::new(&key.path(&home)).exists() {
+                say!("{}", format!("✓ {} already exists", key.path(&home)).green());
+            } else {
+                self.generate(handler, key, &mut git_email)?;
+                generated.push(key.clone());
+            }
+        }
+
+        self.write_config(handler, keys)?;
+
+        let keychain = handler.manifest().ssh.keychain;
+        for key in generated.iter() {
+            // 使用 Apple 的 ssh-add 将口令存入钥匙串
+            let path = key.path(&home);
+            let args: &[&str] = if keychain {
+                &["--apple-use-keychain", &path]
+            } else {
+                &[&path]
+            };
+            if !handler.run("ssh-add", args)? {
+                say!("{}", format!("Warning: could not add {} to ssh-agent", path).yellow());
+            }
+        }
+
+        Ok(generated)
+    }
+
+    /// Generates `key`; `git_email` caches the default comment.
+    fn generate(&self, handler: &ScriptHandler, key: &SshKey, git_email: &mut Option<String>) -> Result<(), SetupError> {
+        let path = key.path(&handler.home_dir());
+        let comment = match (&key.comment, git_email.as_ref()) {
+            (Some(comment), _) | (None, Some(comment)) => comment.clone(),
+            (None, None) => git_email.insert(self.git_email(handler)?).clone(),
+        };
+
+        let answer = handler.answers().ssh.passphrase.as_deref();
+        let passphrase = if handler.manifest().ssh.passphrase {
+            handler.password(&format!("Passphrase for {} (empty for none)", path), "ssh.passphrase", answer)?
+        } else {
+            String::new()
+        };
+
+        say!("Generating a new SSH key (Ed25519 algorithm) at {}", path);
+        let keygen = ["-t", "ed25519", "-C", &comment, "-f", &path];
+        if passphrase.is_empty() {
+            return handler.run_checked("ssh-keygen", &[&keygen[..], &["-N", ""]].concat());
+        }
+
+        // 有终端时 ssh-keygen 从 /dev/tty 读口令；强制使用 SSH_ASKPASS 后由脚本逐行转交 stdin，
+        // 生成时即加密，口令也不出现在 ps 可见的参数中
+        let askpass = Askpass::create(handler)?;
+        let askpass_env = format!("SSH_ASKPASS={}", askpass.0);
+        let args = [&[askpass_env.as_str(), "SSH_ASKPASS_REQUIRE=force", "ssh-keygen"][..], &keygen[..]].concat();
+        handler.run_checked_input("env", &args, &format!("{0}\n{0}\n", passphrase))
+    }
+
+    /// Git email, or the answer to a prompt, for key comments.
+    fn git_email(&self, handler: &ScriptHandler) -> Result<String, SetupError> {
+        let git_email = handler
+            .query("git", &["config", "--global", "user.email"])
+            .ok()
+            .map(|output| output.stdout.trim().to_string())
+            .filter(|email| !email.is_empty());
+        match git_email {
+            Some(email) => Ok(email),
+            None => {
+                let answer = handler.answers().git.email.as_deref();
+                handler.input("Enter the email for your SSH key", "git.email", answer)
+            }
+        }
+    }
+
+    /// Writes the `Host *` block and a block for each key that is for
+    /// particular hosts, backing up the config first.
+    fn write_config(&self, handler: &ScriptHandler, keys: &[SshKey]) -> Result<(), SetupError> {
+        let home = handler.home_dir();
+        let path = ssh::config_path(&home);
+        handler.backup_file(&path)?;
+
+        // 新块写在文件开头，所以先写 Host *，各主机的块会排在它前面
+        handler.write_block_first(&path, ssh::DEFAULTS_BLOCK, &ssh::defaults_entry(&handler.manifest().ssh, &home))?;
+        for key in keys {
+            if let Some(entry) = key.host_entry(&home) {
+                handler.write_block_first(&path, &key.block_name(), &entry)?;
+            }
+        }
+        say!("{}", format!("✓ Updated {}", path).green());
+        Ok(())
+    }
+
+    /// Prints the public key of `key` and, with `copy`, puts it on the
+    /// clipboard.
+    fn show(&self, handler: &ScriptHandler, key: &SshKey, copy: bool) -> Result<bool, SetupError> {
+        let public_key_path = format!("{}.pub", key.path(&handler.home_dir()));
+        let public_key = match std::fs::read_to_string(&public_key_path) {
+            Ok(public_key) => public_key,
+            Err(_) if handler.planner().is_some() => return Ok(true),
+            Err(e) => return Err(SetupError::io(format!("Could not read {}", public_key_path), e)),
+        };
+        println!("{}", public_key.trim());
+
+        if copy && handler.command_exists("pbcopy") && handler.run("sh", &["-c", "pbcopy < \"$1\"", "sh", &public_key_path])? {
+            say!("{}", "SSH public key copied to clipboard".green());
+        }
+        Ok(true)
+    }
+
+    /// Uploads a public key to a Git hosting service, skipping lists that
+    /// already hold it.
+    fn publish(&self, handler: &ScriptHandler, matches: &ArgMatches) -> Result<bool, SetupError> {
+        let provider = *matches.get_one::<Provider>("provider").ok_or("Missing provider")?;
+        let host = GitHost::new(
+            provider,
+            matches.get_one::<String>("base-url").map(String::as_str),
+            matches.get_one::<String>("token-env").map(String::as_str),
+        );
+        host.require_token()?;
+        if !handler.command_exists("curl") {
+            return Err("curl is not installed".into());
+        }
+
+        let key = handler.manifest().ssh.key(matches.get_one::<String>("name").map(String::as_str));
+        let public_key_path = format!("{}.pub", key.path(&handler.home_dir()));
+        let public_key = std::fs::read_to_string(&public_key_path)
+            .map_err(|e| SetupError::io(format!("Could not read {}; run `macdevkit-cli ssh` first", public_key_path), e))?;
+        let title = match matches.get_one::<String>("title") {
+            Some(title) => title.clone(),
+            None => self.default_title(handler),
+        };
+        let signing = matches.get_flag("signing");
+
+        let mut uses = vec![KeyUse::Authentication];
+        if signing {
+            uses.push(KeyUse::Signing);
+        }
+        let mut added = false;
+        for key_use in uses {
+            let list = match key_use {
+                KeyUse::Authentication => "authentication key",
+                KeyUse::Signing => "signing key",
+            };
+            if host.has_key(handler, &public_key, key_use)? {
+                say!("{}", format!("✓ {} already has {} ({})", provider.name(), key.name, list).green());
+            } else if host.add_key(handler, &title, &public_key, key_use, signing)? {
+                say!("{}", format!("✓ Added {} to {} as \"{}\" ({})", key.name, provider.name(), title, list).green());
+                added = true;
+            } else if provider == Provider::Gitlab && added {
+                say!("{}", "✓ GitLab will also use the key to verify signatures".green());
+            } else if provider == Provider::Gitlab {
+                // GitLab 只能在添加密钥时设置用途
+                say!(
+                    "{}",
+                    "Warning: GitLab already had the key for authentication only; delete it there and publish again to use it for signing"
+                        .yellow()
+                );
+                return Ok(false);
+            } else {
+                say!("{}", format!("{} verifies signatures with authentication keys; nothing more to upload", provider.name()).cyan());
+            }
+        }
+        Ok(true)
+    }
+
+    /// `<user>@<computer name>`, like the titles `gh` gives keys.
+    fn default_title(&self, handler: &ScriptHandler) -> String {
+        let computer = handler
+            .query("scutil", &["--get", "ComputerName"])
+            .ok()
+            .filter(|output| output.success)
+            .map(|output| output.stdout.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| "Mac".to_string());
+        let user = std::env::var("USER").unwrap_or_else(|_| "macdevkit".to_string());
+        format!("{}@{}", user, computer)
+    }
+
+    /// A key for the hosts given on the command line, e.g. `ssh new work
+    /// --host gitlab.example.com`.
+    fn key_from_args(&self, matches: &ArgMatches) -> Result<SshKey, SetupError> {
+        let name = matches.get_one::<String>("name").ok_or("Missing key name")?;
+        if name == ssh::DEFAULT_KEY {
+            return Err(SetupError::Config(format!("`{}` is the key for all other hosts; choose another name", name)));
+        }
+
+        let mut key = SshKey::new(name);
+        key.hosts = matches.get_many::<String>("host").into_iter().flatten().cloned().collect();
+        key.hostname = matches.get_one::<String>("hostname").cloned();
+        key.user = matches.get_one::<String>("user").cloned();
+        key.comment = matches.get_one::<String>("comment").cloned();
+        Ok(key)
+    }
+
+    fn missing_keys(&self, handler: &ScriptHandler) -> Vec<String> {
+        let home = handler.home_dir();
+        handler
+            .manifest()
+            .ssh
+            .keys()
+            .iter()
+            .map(|key| key.path(&home))
+            .filter(|path| !Path::new(path).exists())
+            .collect()
+    }
+
+    fn config_up_to_date(&self, handler: &ScriptHandler) -> bool {
+        let home = handler.home_dir();
+        let config = handler.read_file(&ssh::config_path(&home)).unwrap_or_default();
+        let settings = &handler.manifest().ssh;
+
+        managed_block::find(&config, ssh::DEFAULTS_BLOCK) == Some(ssh::defaults_entry(settings, &home).as_str())
+            && settings.keys().iter().all(|key| match key.host_entry(&home) {
+                Some(entry) => managed_block::find(&config, &key.block_name()) == Some(entry.as_str()),
+                None => true,
+            })
+    }
+}
+
+impl Step for Ssh {
+    fn id(&self) -> &'static str {
+        "ssh"
+    }
+
+    fn title(&self) -> &'static str {
+        "Generate SSH key"
+    }
+
+    fn description(&self) -> &'static str {
+        "SSH keys authenticate you to GitHub, GitLab and remote servers. This step generates Ed25519 keys, per host if the manifest says so, adds them to ~/.ssh/config and to the SSH agent."
+    }
+
+    fn dependencies(&self) -> &'static [&'static str] {
+        &["git"]
+    }
+
+    fn check(&self, handler: &ScriptHandler) -> bool {
+        self.missing_keys(handler).is_empty() && self.config_up_to_date(handler)
+    }
+
+    fn diagnose(&self, handler: &ScriptHandler) -> Diagnosis {
+        let missing = self.missing_keys(handler);
+        if !missing.is_empty() {
+            Diagnosis::fail(format!("{} missing", missing.join(", ")), "run `macdevkit-cli ssh`")
+        } else if !self.config_up_to_date(handler) {
+            Diagnosis::warn("~/.ssh/config has no entries for the keys", "run `macdevkit-cli ssh`")
+        } else {
+            Diagnosis::pass("keys present, ~/.ssh/config up to date")
+        }
+    }
+
+    fn subcommands(&self) -> Vec<clap::Command> {
+        vec![
+            clap::Command::new("new")
+                .about("Generate a key for particular hosts and add it to ~/.ssh/config")
+                .arg(Arg::new("name").value_name("NAME").required(true).help("Key name; the key is ~/.ssh/id_ed25519_<NAME>"))
+                .arg(
+                    Arg::new("host")
+                        .long("host")
+                        .value_name("HOST")
+                        .required(true)
+                        .action(ArgAction::Append)
+                        .help("Host pattern the key is for; repeat for several"),
+                )
+                .arg(Arg::new("hostname").long("hostname").value_name("HOSTNAME").help("Real host name, if HOST is an alias"))
+                .arg(Arg::new("user").long("user").value_name("USER").help("User to log in as, e.g. git"))
+                .arg(Arg::new("comment").long("comment").value_name("COMMENT").help("Key comment; defaults to your Git email")),
+            clap::Command::new("publish")
+                .about("Upload a public key to GitHub, GitLab or Gitea")
+                .arg(Arg::new("name").value_name("NAME").help("Key name; defaults to the first key in the manifest"))
+                .arg(
+                    Arg::new("provider")
+                        .long("provider")
+                        .value_name("PROVIDER")
+                        .required(true)
+                        .value_parser(value_parser!(Provider)),
+                )
+                .arg(
+                    Arg::new("token-env")
+                        .long("token-env")
+                        .value_name("VAR")
+                        .help("Environment variable holding the API token [default: GITHUB_TOKEN, GITLAB_TOKEN or GITEA_TOKEN]"),
+                )
+                .arg(
+                    Arg::new("base-url")
+                        .long("base-url")
+                        .value_name("URL")
+                        .help("API root of a self-hosted instance, e.g. https://git.example.com/api/v4"),
+                )
+                .arg(Arg::new("title").long("title").value_name("TITLE").help("Key title [default: <user>@<computer name>]"))
+                .arg(
+                    Arg::new("signing")
+                        .long("signing")
+                        .action(ArgAction::SetTrue)
+                        .help("Also register the key for verifying signed commits"),
+                ),
+            clap::Command::new("show")
+                .about("Print a public key")
+                .arg(Arg::new("name").value_name("NAME").help("Key name; defaults to the first key in the manifest"))
+                .arg(
+                    Arg::new("copy")
+                        .long("copy")
+                        .action(ArgAction::SetTrue)
+                        .help("Also copy it to the clipboard"),
+                ),
+        ]
+    }
+
+    fn run_subcommand(&self, handler: &ScriptHandler, name: &str, matches: &ArgMatches) -> Result<bool, SetupError> {
+        match name {
+            "new" => {
+                let key = self.key_from_args(matches)?;
+                for key in self.set_up(handler, std::slice::from_ref(&key))? {
+                    self.show(handler, &key, true)?;
+                }
+                Ok(true)
+            }
+            "publish" => self.publish(handler, matches),
+            "show" => {
+                let key = handler.manifest().ssh.key(matches.get_one::<String>("name").map(String::as_str));
+                self.show(handler, &key, matches.get_flag("copy"))
+            }
+            _ => Err(format!("Unknown subcommand: ssh {}", name).into()),
+        }
+    }
+
+    fn apply(&self, handler: &ScriptHandler) -> Result<bool, SetupError> {
+        say!("{}", "\n==== SSH Key Generation ====\n".blue());
+
+        let generated = self.set_up(handler, &handler.manifest().ssh.keys())?;
+
+        // git 步骤先于本步骤运行，新密钥的签名设置在这里完成
+        let mut all_set = true;
+        if handler.manifest().git.signing == Some(SigningMethod::Ssh)
+            && (!generated.is_empty() || !signing::is_configured(handler, SigningMethod::Ssh))
+        {
+            all_set = signing::set_up(handler, SigningMethod::Ssh, None, None)?;
+        }
+
+        if generated.is_empty() {
+            say!("{}", "✓ SSH keys already exist".green());
+            return Ok(all_set);
+        }
+
+        // 只生成一把密钥时才复制到剪贴板，否则后一把会覆盖前一把
+        let copy = generated.len() == 1;
+        for key in generated.iter() {
+            say!("Public key {}:", key.name);
+            if let Err(e) = self.show(handler, key, copy) {
+                say!("{}", format!("Warning: {}", e).yellow());
+            }
+        }
+        say!("Add the public key to your GitHub/GitLab account, or run `macdevkit-cli ssh publish --provider github`");
+
+        Ok(all_set)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::manifest::Manifest;
+    use crate::prompt::{Answers, GitAnswers, SshAnswers};
+    use crate::runner::{RecordingRunner, SystemRunner};
+    use crate::script_handler::ScriptHandler;
+    use crate::test_support::{handler_with, prompter, TempHome};
+
+    fn work_key(hostname: &str) -> SshKey {
+        let mut key = SshKey::new("work");
+        key.hosts = vec!["gitlab.example.com".to_string()];
+        key.hostname = Some(hostname.to_string());
+        key.user = Some("git".to_string());
+        key
+    }
+
+    fn manifest(keychain: bool, keys: Vec<SshKey>) -> Manifest {
+        let mut manifest = Manifest::default();
+        manifest.ssh.keychain = keychain;
+        manifest.ssh.keys = keys;
+        manifest
+    }
+
+    fn answers() -> Answers {
+        answers_with_passphrase("")
+    }
+
+    fn answers_with_passphrase(passphrase: &str) -> Answers {
+        Answers {
+            git: GitAnswers {
+                email: Some("ada@example.com".to_string()),
+                ..GitAnswers::default()
+            },
+            ssh: SshAnswers {
+                passphrase: Some(passphrase.to_string()),
+            },
+            ..Answers::default()
+        }
+    }
+
+    #[test]
+    fn apply_inserts_and_replaces_config_entries() {
+        let home = TempHome::new("ssh-config");
+        std::fs::create_dir_all(home.join(".ssh")).unwrap();
+        std::fs::write(home.join(".ssh/config"), "Host old.example.com\n  User me\n").unwrap();
+        let keys = vec![SshKey::new(ssh::DEFAULT_KEY), work_key("gitlab.example.com")];
+
+        let runner = Arc::new(RecordingRunner::new());
+        let handler = handler_with(manifest(true, keys), &runner, &home, answers());
+        assert!(Ssh.apply(&handler).unwrap());
+
+        let config = std::fs::read_to_string(home.join(".ssh/config")).unwrap();
+        let work = managed_block::find(&config, "ssh work").unwrap();
+        assert!(work.contains("Host gitlab.example.com\n  HostName gitlab.example.com\n  User git\n  IdentityFile ~/.ssh/id_ed25519_work"));
+        let defaults = managed_block::find(&config, ssh::DEFAULTS_BLOCK).unwrap();
+        assert!(defaults.contains("UseKeychain yes\n  IdentityFile ~/.ssh/id_ed25519"));
+        // 各主机的块在 Host * 之前，原有内容保留在后面
+        assert!(config.find("Host gitlab.example.com").unwrap() < config.find("Host *").unwrap());
+        assert!(config.ends_with("Host old.example.com\n  User me\n"));
+        assert_eq!(std::fs::read_to_string(home.join(".ssh/config.macdevkit.bak")).unwrap(), "Host old.example.com\n  User me\n");
+
+        let keys = vec![SshKey::new(ssh::DEFAULT_KEY), work_key("gitlab.internal")];
+        let handler = handler_with(manifest(true, keys), &runner, &home, answers());
+        assert!(Ssh.apply(&handler).unwrap());
+
+        let config = std::fs::read_to_string(home.join(".ssh/config")).unwrap();
+        assert_eq!(config.matches("Host gitlab.example.com").count(), 1);
+        assert!(config.contains("  HostName gitlab.internal\n"));
+        assert!(!config.contains("  HostName gitlab.example.com\n"));
+    }
+
+    #[test]
+    fn apply_leaves_existing_key_alone() {
+        let home = TempHome::new("ssh-existing");
+        std::fs::create_dir_all(home.join(".ssh")).unwrap();
+        std::fs::write(home.join(".ssh/id_ed25519"), "existing private key\n").unwrap();
+        std::fs::write(home.join(".ssh/id_ed25519.pub"), "ssh-ed25519 AAAA existing\n").unwrap();
+
+        let runner = Arc::new(RecordingRunner::new());
+        let handler = handler_with(manifest(true, Vec::new()), &runner, &home, answers());
+        assert!(Ssh.apply(&handler).unwrap());
+
+        let lines = runner.command_lines();
+        assert!(!lines.iter().any(|line| line.starts_with("ssh-keygen") || line.starts_with("ssh-add")));
+        assert_eq!(std::fs::read_to_string(home.join(".ssh/id_ed25519")).unwrap(), "existing private key\n");
+    }
+
+    #[test]
+    fn apply_adds_new_keys_to_keychain_when_asked() {
+        for keychain in [true, false] {
+            let home = TempHome::new(&format!("ssh-keychain-{}", keychain));
+            let runner = Arc::new(RecordingRunner::new());
+            let handler = handler_with(manifest(keychain, Vec::new()), &runner, &home, answers());
+            assert!(Ssh.apply(&handler).unwrap());
+
+            let path = home.join(".ssh/id_ed25519");
+            let lines = runner.command_lines();
+            assert!(lines.iter().any(|line| line.starts_with("ssh-keygen -t ed25519 -C ada@example.com")));
+            let expected = if keychain {
+                format!("ssh-add --apple-use-keychain {}", path)
+            } else {
+                format!("ssh-add {}", path)
+            };
+            assert!(lines.contains(&expected), "missing {}", expected);
+
+            let config = std::fs::read_to_string(home.join(".ssh/config")).unwrap();
+            assert_eq!(config.contains("UseKeychain yes"), keychain);
+        }
+    }
+
+    #[test]
+    fn passphrase_goes_to_stdin() {
+        let home = TempHome::new("ssh-passphrase");
+        let runner = Arc::new(RecordingRunner::new());
+        let handler = handler_with(manifest(true, Vec::new()), &runner, &home, answers_with_passphrase("s3cret"));
+        assert!(Ssh.apply(&handler).unwrap());
+
+        let path = home.join(".ssh/id_ed25519");
+        let invocations = runner.invocations();
+        assert!(!invocations.iter().any(|invocation| invocation.args.iter().any(|arg| arg.contains("s3cret"))));
+        let keygen = invocations.iter().find(|invocation| invocation.program == "env").unwrap();
+        assert!(keygen.args[0].starts_with("SSH_ASKPASS="));
+        assert_eq!(keygen.args[1..3], ["SSH_ASKPASS_REQUIRE=force", "ssh-keygen"]);
+        assert_eq!(keygen.args[keygen.args.len() - 2..], ["-f", path.as_str()]);
+        assert_eq!(keygen.stdin.as_deref(), Some("s3cret\ns3cret\n"));
+        assert!(!invocations.iter().any(|invocation| invocation.program == "ssh-keygen"));
+    }
+
+    fn generate_with_passphrase(home: &str) {
+        let handler = ScriptHandler::new(Manifest::default(), Arc::new(SystemRunner))
+            .with_home(home)
+            .with_prompter(prompter(answers_with_passphrase("s3cret")));
+        Ssh.generate(&handler, &SshKey::new(ssh::DEFAULT_KEY), &mut Some("ada@example.com".to_string()))
+            .unwrap();
+    }
+
+    fn assert_protected(home: &TempHome) {
+        let handler = ScriptHandler::new(Manifest::default(), Arc::new(SystemRunner));
+        let path = home.join(".ssh/id_ed25519");
+        let unlock = |passphrase: &str| handler.query("ssh-keygen", &["-y", "-P", passphrase, "-f", &path]).unwrap().success;
+        assert!(unlock("s3cret"));
+        assert!(!unlock(""));
+    }
+
+    #[test]
+    fn generated_key_is_protected_by_passphrase() {
+        let home = TempHome::new("ssh-keygen");
+        std::fs::create_dir_all(home.join(".ssh")).unwrap();
+        generate_with_passphrase(&home.as_str());
+        assert_protected(&home);
+    }
+
+    /// Set to the home directory when this test runs itself under `script`.
+    const TERMINAL_HOME: &str = "MACDEVKIT_TEST_TERMINAL_HOME";
+
+    #[test]
+    fn passphrase_is_set_with_a_terminal_attached() {
+        if let Some(home) = std::env::var_os(TERMINAL_HOME) {
+            generate_with_passphrase(&home.to_string_lossy());
+            return;
+        }
+
+        let home = TempHome::new("ssh-terminal");
+        std::fs::create_dir_all(home.join(".ssh")).unwrap();
+        // 在 script 分配的伪终端中重新运行本测试，ssh-keygen 因而有控制终端
+        let test = std::env::current_exe().unwrap();
+        let test = test.to_string_lossy().into_owned();
+        let name = "steps::ssh::tests::passphrase_is_set_with_a_terminal_attached";
+        let mut command = std::process::Command::new("script");
+        if cfg!(target_os = "macos") {
+            command.args(["-q", "/dev/null", test.as_str(), "--exact", name]);
+        } else {
+            command.args(["-qec", &format!("{} --exact {}", test, name), "/dev/null"]);
+        }
+        let mut child = command
+            .env(TERMINAL_HOME, home.as_str())
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .spawn()
+            .unwrap();
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(30);
+        let status = loop {
+            if let Some(status) = child.try_wait().unwrap() {
+                break status;
+            }
+            if std::time::Instant::now() > deadline {
+                let _ = child.kill();
+                panic!("ssh-keygen waited for a passphrase on the terminal");
+            }
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        };
+        assert!(status.success());
+        assert_protected(&home);
+    }
+}
@@ -0,0 +1,86 @@
+use colored::*;
+
+use super::Step;
+use crate::doctor::Diagnosis;
+use crate::error::SetupError;
+use crate::install::Batch;
+use crate::script_handler::ScriptHandler;
+
+pub struct Vscode;
+
+impl Step for Vscode {
+    fn id(&self) -> &'static str {
+        "vscode"
+    }
+
+    fn title(&self) -> &'static str {
+        "Install Visual Studio Code"
+    }
+
+    fn description(&self) -> &'static str {
+        "Visual Studio Code is a popular code editor with syntax highlighting, completion and debugging. This step also installs the extensions listed in the manifest."
+    }
+
+    fn dependencies(&self) -> &'static [&'static str] {
+        &["brew"]
+    }
+
+    fn check(&self, handler: &ScriptHandler) -> bool {
+        handler.command_exists("code")
+    }
+
+    fn diagnose(&self, handler: &ScriptHandler) -> Diagnosis {
+        if self.check(handler) {
+            Diagnosis::pass("code on PATH")
+        } else if std::path::Path::new("/Applications/Visual Studio Code.app").exists() {
+            Diagnosis::warn(
+                "VS Code installed but `code` is not on PATH",
+                "run \"Shell Command: Install 'code' command in PATH\" from the VS Code command palette",
+            )
+        } else {
+            Diagnosis::fail("VS Code not installed", "run `macdevkit-cli vscode`")
+        }
+    }
+
+    fn apply(&self, handler: &ScriptHandler) -> Result<bool, SetupError> {
+        say!("{}", "\n==== Installing Visual Studio Code ====\n".blue());
+
+        // 检查VS Code是否已安装
+        if self.check(handler) {
+            say!("{}", "✓ VS Code already installed".green());
+        } else {
+            say!("{}", "Installing VS Code...".cyan());
+
+            // 使用Homebrew安装VS Code
+            if !handler.brew_install("visual-studio-code", true)? {
+                return Err("Failed to install VS Code".into());
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn packages(&self, handler: &ScriptHandler) -> Vec<Batch> {
+        let extensions = &handler.manifest().vscode.extensions;
+        if extensions.is_empty() {
+            return Vec::new();
+        }
+
+        // 刚安装的VS Code可能还没有code命令
+        if !handler.command_exists("code") && handler.planner().is_none() {
+            say!("{}", "The `code` command is not on PATH yet; skipping extensions.".yellow());
+            return Vec::new();
+        }
+
+        let installed = handler
+            .query("code", &["--list-extensions"])
+            .map(|output| output.stdout.to_lowercase())
+            .unwrap_or_default();
+        let missing = extensions
+            .iter()
+            .filter(|extension| !installed.lines().any(|line| line == extension.to_lowercase()))
+            .cloned()
+            .collect();
+        vec![Batch::extensions(self.id(), missing)]
+    }
+}
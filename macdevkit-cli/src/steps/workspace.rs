@@ -0,0 +1,40 @@
+use colored::*;
+use std::path::Path;
+
+use super::Step;
+use crate::error::SetupError;
+use crate::script_handler::ScriptHandler;
+
+pub struct Workspace;
+
+impl Step for Workspace {
+    fn id(&self) -> &'static str {
+        "workspace"
+    }
+
+    fn title(&self) -> &'static str {
+        "Create development workspace"
+    }
+
+    fn description(&self) -> &'static str {
+        "Creates the workspace directories listed in the manifest, by default ~/Workspace, for organizing your development projects."
+    }
+
+    fn check(&self, handler: &ScriptHandler) -> bool {
+        let dirs = &handler.manifest().workspace.dirs;
+        dirs.iter().all(|dir| Path::new(&handler.expand_home(dir)).is_dir())
+    }
+
+    fn apply(&self, handler: &ScriptHandler) -> Result<bool, SetupError> {
+        say!("{}", "\n==== Creating Development Workspace ====\n".blue());
+
+        // 创建Workspace目录
+        for dir in handler.manifest().workspace.dirs.iter() {
+            let workspace_path = handler.expand_home(dir);
+            handler.create_dir_all(&workspace_path)?;
+            say!("{}", format!("✓ Created workspace directory at {}", workspace_path).green());
+        }
+
+        Ok(true)
+    }
+}
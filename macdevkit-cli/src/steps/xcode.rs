@@ -0,0 +1,97 @@
+use colored::*;
+
+use super::Step;
+use crate::doctor::Diagnosis;
+use crate::error::SetupError;
+use crate::script_handler::ScriptHandler;
+
+pub struct Xcode;
+
+impl Step for Xcode {
+    fn id(&self) -> &'static str {
+        "xcode"
+    }
+
+    fn title(&self) -> &'static str {
+        "Install Xcode Command Line Tools"
+    }
+
+    fn description(&self) -> &'static str {
+        "Xcode Command Line Tools provide the compilers, build tools and Git that most development work and Homebrew depend on."
+    }
+
+    fn check(&self, handler: &ScriptHandler) -> bool {
+        // 检查xcode command line tools是否已安装
+        handler
+            .query("xcode-select", &["-p"])
+            .map(|output| output.success)
+            .unwrap_or(false)
+    }
+
+    fn diagnose(&self, handler: &ScriptHandler) -> Diagnosis {
+        match handler.query("xcode-select", &["-p"]) {
+            Ok(output) if output.success => Diagnosis::pass(format!("Command Line Tools at {}", output.stdout.trim())),
+            _ => Diagnosis::fail("Command Line Tools not installed", "run `xcode-select --install`"),
+        }
+    }
+
+    fn apply(&self, handler: &ScriptHandler) -> Result<bool, SetupError> {
+        say!("{}", "\n==== Installing Xcode Command Line Tools ====\n".blue());
+
+        if self.check(handler) {
+            say!("{}", "✓ Xcode Command Line Tools already installed".green());
+            say!("{}", "Xcode Command Line Tools installation completed".green());
+            return Ok(true);
+        }
+
+        say!("{}", "Installing Xcode Command Line Tools...".cyan());
+
+        // 触发安装
+        handler.run_checked("xcode-select", &["--install"])?;
+
+        say!("{}", "Xcode Command Line Tools installation triggered".green());
+        say!("Please wait for the installation to complete.");
+        say!("{}", "Xcode Command Line Tools installation completed".green());
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::runner::{CommandOutput, RecordingRunner};
+    use crate::steps::Step;
+    use crate::test_support::{handler, TempHome};
+
+    #[test]
+    fn apply_skips_installed_tools() {
+        let home = TempHome::new("xcode-installed");
+        let runner = Arc::new(RecordingRunner::new().respond("xcode-select -p", CommandOutput::success("/Library/Developer/CommandLineTools\n")));
+
+        assert!(Xcode.apply(&handler(&runner, &home)).unwrap());
+        assert_eq!(runner.command_lines(), ["xcode-select -p"]);
+    }
+
+    #[test]
+    fn apply_triggers_install() {
+        let home = TempHome::new("xcode-missing");
+        let runner = Arc::new(RecordingRunner::new().respond("xcode-select -p", CommandOutput::failure("unable to get active developer directory")));
+
+        assert!(Xcode.apply(&handler(&runner, &home)).unwrap());
+        assert_eq!(runner.command_lines(), ["xcode-select -p", "xcode-select --install"]);
+    }
+
+    #[test]
+    fn apply_fails_when_install_fails() {
+        let home = TempHome::new("xcode-failed");
+        let runner = Arc::new(
+            RecordingRunner::new()
+                .respond("xcode-select -p", CommandOutput::failure(""))
+                .respond("xcode-select --install", CommandOutput::failure("already requested")),
+        );
+
+        assert!(Xcode.apply(&handler(&runner, &home)).is_err());
+    }
+}
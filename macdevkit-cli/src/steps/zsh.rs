@@ -0,0 +1,237 @@
+use colored::*;
+use std::fs;
+use std::path::Path;
+
+use super::Step;
+use crate::doctor::Diagnosis;
+use crate::error::SetupError;
+use crate::script_handler::ScriptHandler;
+
+pub struct Zsh;
+
+impl Step for Zsh {
+    fn id(&self) -> &'static str {
+        "zsh"
+    }
+
+    fn title(&self) -> &'static str {
+        "Install Oh My Zsh"
+    }
+
+    fn description(&self) -> &'static str {
+        "Oh My Zsh is a framework for managing your Zsh configuration, with helpful functions, plugins and themes. This step installs it with the theme and plugins from the manifest."
+    }
+
+    fn dependencies(&self) -> &'static [&'static str] {
+        &["git"]
+    }
+
+    fn check(&self, handler: &ScriptHandler) -> bool {
+        // 检查Oh My Zsh是否已安装
+        Path::new(&format!("{}/.oh-my-zsh", handler.home_dir())).exists()
+    }
+
+    fn diagnose(&self, handler: &ScriptHandler) -> Diagnosis {
+        if self.check(handler) {
+            Diagnosis::pass("~/.oh-my-zsh present")
+        } else {
+            Diagnosis::fail("Oh My Zsh not installed", "run `macdevkit-cli zsh`")
+        }
+    }
+
+    fn apply(&self, handler: &ScriptHandler) -> Result<bool, SetupError> {
+        say!("{}", "\n==== Installing Oh My Zsh ====\n".blue());
+
+        if self.check(handler) {
+            say!("{}", "✓ Oh My Zsh already installed".green());
+        } else {
+            say!("{}", "Installing Oh My Zsh...".cyan());
+            // --unattended 不切换默认shell，也不进入新的zsh会话
+            let install_cmd = r#"sh -c "$(curl -fsSL https://raw.githubusercontent.com/ohmyzsh/ohmyzsh/master/tools/install.sh)" "" --unattended"#;
+            handler.run_checked("bash", &["-c", install_cmd])?;
+            say!("{}", "Oh My Zsh installed".green());
+        }
+
+        let settings = &handler.manifest().zsh;
+        let custom_dir = self.custom_dir(handler);
+
+        let theme = Theme::find(&settings.theme);
+        if let Some((repo, dir)) = theme.repo {
+            self.clone_once(handler, repo, &format!("{}/themes/{}", custom_dir, dir))?;
+        }
+        if let Some((link, target)) = theme.link {
+            let link = format!("{}/themes/{}", custom_dir, link);
+            if !Path::new(&link).exists() {
+                handler.run_checked("ln", &["-s", &format!("{}/themes/{}", custom_dir, target), &link])?;
+            }
+        }
+
+        for plugin in settings.plugins.iter() {
+            if let Some(repo) = external_plugin(plugin) {
+                self.clone_once(handler, repo, &format!("{}/plugins/{}", custom_dir, plugin))?;
+            }
+        }
+
+        self.update_zshrc(handler, &theme.name, &settings.plugins)?;
+
+        say!("{}", "Oh My Zsh installed and configured".green());
+        Ok(true)
+    }
+}
+
+impl Zsh {
+    /// `$ZSH_CUSTOM`, where themes and plugins that do not ship with Oh My
+    /// Zsh are cloned.
+    fn custom_dir(&self, handler: &ScriptHandler) -> String {
+        std::env::var("ZSH_CUSTOM").unwrap_or_else(|_| format!("{}/.oh-my-zsh/custom", handler.home_dir()))
+    }
+
+    fn clone_once(&self, handler: &ScriptHandler, repo: &str, dest: &str) -> Result<(), SetupError> {
+        if Path::new(dest).exists() {
+            return Ok(());
+        }
+        say!("Cloning {}...", repo);
+        handler.run_checked("git", &["clone", "--depth=1", repo, dest])
+    }
+
+    /// Points `ZSH_THEME` and `plugins=(...)` in `~/.zshrc` at the manifest's
+    /// theme and plugins.
+    fn update_zshrc(&self, handler: &ScriptHandler, theme: &str, plugins: &[String]) -> Result<(), SetupError> {
+        let zshrc = format!("{}/.zshrc", handler.home_dir());
+        let Ok(content) = fs::read_to_string(&zshrc) else {
+            say!("{}", format!("{} not found; skipping theme and plugin settings", zshrc).yellow());
+            return Ok(());
+        };
+
+        let theme_line = format!("ZSH_THEME=\"{}\"", theme);
+        let plugins_line = format!("plugins=({})", plugins.join(" "));
+        let replacements: Vec<(&str, &str)> = content
+            .lines()
+            .filter_map(|line| {
+                if line.starts_with("ZSH_THEME=") {
+                    Some((line, theme_line.as_str()))
+                } else if line.starts_with("plugins=(") && line.ends_with(')') {
+                    Some((line, plugins_line.as_str()))
+                } else {
+                    None
+                }
+            })
+            .filter(|(line, wanted)| line != wanted)
+            .collect();
+        if replacements.is_empty() {
+            return Ok(());
+        }
+
+        // 逐行替换并记录原来的行，undo 时可以恢复
+        handler.backup_file(&zshrc)?;
+        for (line, wanted) in replacements {
+            handler.replace_line(&zshrc, line, wanted)?;
+        }
+        say!("{}", format!("✓ Set theme {} and plugins {}", theme, plugins.join(", ")).green());
+        Ok(())
+    }
+}
+
+/// How to install a theme that is not bundled with Oh My Zsh.
+struct Theme {
+    /// Value for `ZSH_THEME`.
+    name: String,
+    /// Git repository and the directory under `themes/` to clone it into.
+    repo: Option<(&'static str, &'static str)>,
+    /// Symlink to create under `themes/` and the file it points to.
+    link: Option<(&'static str, &'static str)>,
+}
+
+impl Theme {
+    fn find(name: &str) -> Theme {
+        match name {
+            "powerlevel10k" => Theme {
+                name: "powerlevel10k/powerlevel10k".to_string(),
+                repo: Some(("https://github.com/romkatv/powerlevel10k.git", "powerlevel10k")),
+                link: None,
+            },
+            "spaceship" => Theme {
+                name: "spaceship".to_string(),
+                repo: Some(("https://github.com/spaceship-prompt/spaceship-prompt.git", "spaceship-prompt")),
+                link: Some(("spaceship.zsh-theme", "spaceship-prompt/spaceship.zsh-theme")),
+            },
+            // 其余主题随Oh My Zsh一起提供
+            bundled => Theme {
+                name: bundled.to_string(),
+                repo: None,
+                link: None,
+            },
+        }
+    }
+}
+
+/// Repository of a plugin that does not ship with Oh My Zsh.
+fn external_plugin(name: &str) -> Option<&'static str> {
+    match name {
+        "zsh-autosuggestions" => Some("https://github.com/zsh-users/zsh-autosuggestions.git"),
+        "zsh-syntax-highlighting" => Some("https://github.com/zsh-users/zsh-syntax-highlighting.git"),
+        "zsh-completions" => Some("https://github.com/zsh-users/zsh-completions.git"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::changes::ChangeLog;
+    use crate::runner::RecordingRunner;
+    use crate::test_support::{handler, TempHome};
+
+    #[test]
+    fn apply_installs_and_clones_theme_and_plugins() {
+        let home = TempHome::new("zsh-fresh");
+        let runner = Arc::new(RecordingRunner::new());
+        let handler = handler(&runner, &home);
+        let custom_dir = Zsh.custom_dir(&handler);
+
+        assert!(Zsh.apply(&handler).unwrap());
+        let lines = runner.command_lines();
+        assert!(lines[0].starts_with("bash -c sh -c"));
+        assert!(lines[0].ends_with("--unattended"));
+        for (repo, dest) in [
+            ("romkatv/powerlevel10k", "themes/powerlevel10k"),
+            ("zsh-users/zsh-autosuggestions", "plugins/zsh-autosuggestions"),
+            ("zsh-users/zsh-syntax-highlighting", "plugins/zsh-syntax-highlighting"),
+        ] {
+            let expected = format!("git clone --depth=1 https://github.com/{}.git {}/{}", repo, custom_dir, dest);
+            assert!(lines.contains(&expected), "missing {}", expected);
+        }
+    }
+
+    #[test]
+    fn apply_updates_zshrc_of_existing_install() {
+        let home = TempHome::new("zsh-existing");
+        std::fs::create_dir_all(home.join(".oh-my-zsh")).unwrap();
+        std::fs::write(home.join(".zshrc"), "export ZSH=\"$HOME/.oh-my-zsh\"\nZSH_THEME=\"robbyrussell\"\nplugins=(git)\n").unwrap();
+        let runner = Arc::new(RecordingRunner::new());
+
+        assert!(Zsh.apply(&handler(&runner, &home)).unwrap());
+        assert!(!runner.command_lines().iter().any(|line| line.starts_with("bash ")));
+        let zshrc = std::fs::read_to_string(home.join(".zshrc")).unwrap();
+        assert!(zshrc.contains("ZSH_THEME=\"powerlevel10k/powerlevel10k\"\n"));
+        assert!(zshrc.contains("plugins=(git zsh-autosuggestions zsh-syntax-highlighting)\n"));
+    }
+
+    #[test]
+    fn undo_restores_zshrc() {
+        let home = TempHome::new("zsh-undo");
+        std::fs::create_dir_all(home.join(".oh-my-zsh")).unwrap();
+        let original = "export ZSH=\"$HOME/.oh-my-zsh\"\nZSH_THEME=\"robbyrussell\"\nplugins=(git)\nsource $ZSH/oh-my-zsh.sh\n";
+        std::fs::write(home.join(".zshrc"), original).unwrap();
+        let runner = Arc::new(RecordingRunner::new());
+        let changes = ChangeLog::open(home.path.join("changes.json")).unwrap();
+        let handler = handler(&runner, &home).with_changes(changes);
+
+        assert!(handler.run_section(&Zsh).unwrap());
+        assert_eq!(std::fs::read_to_string(home.join(".zshrc.macdevkit.bak")).unwrap(), original);
+        assert!(handler.undo_section(&Zsh).unwrap());
+        assert_eq!(std::fs::read_to_string(home.join(".zshrc")).unwrap(), original);
+    }
+}
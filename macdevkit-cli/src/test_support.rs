@@ -0,0 +1,57 @@
+//! Helpers shared by the unit tests.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::manifest::Manifest;
+use crate::prompt::{Answers, Confirmation, Prompter};
+use crate::runner::RecordingRunner;
+use crate::script_handler::ScriptHandler;
+
+/// A temporary home directory, deleted when dropped.
+pub struct TempHome {
+    pub path: PathBuf,
+}
+
+impl TempHome {
+    /// A fresh directory named after the test, so tests can run in parallel.
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("macdevkit-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        TempHome { path }
+    }
+
+    /// Path of `relative` inside the home directory.
+    pub fn join(&self, relative: &str) -> String {
+        self.path.join(relative).to_string_lossy().into_owned()
+    }
+
+    pub fn as_str(&self) -> String {
+        self.path.to_string_lossy().into_owned()
+    }
+}
+
+impl Drop for TempHome {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+/// A handler that runs `manifest` through `runner` in `home`, answering
+/// prompts from `answers` and never from a terminal.
+pub fn handler_with(manifest: Manifest, runner: &Arc<RecordingRunner>, home: &TempHome, answers: Answers) -> ScriptHandler {
+    ScriptHandler::new(manifest, runner.clone())
+        .with_home(home.as_str())
+        .with_prompter(prompter(answers))
+}
+
+/// A prompter that answers from `answers` and never from a terminal.
+pub fn prompter(answers: Answers) -> Prompter {
+    Prompter::new(Confirmation::Yes, answers).with_terminal(false)
+}
+
+/// [`handler_with`] for the default manifest and no answers.
+pub fn handler(runner: &Arc<RecordingRunner>, home: &TempHome) -> ScriptHandler {
+    handler_with(Manifest::default(), runner, home, Answers::default())
+}
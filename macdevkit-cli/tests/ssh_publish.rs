@@ -0,0 +1,212 @@
+//! `ssh publish` against a local mock of the GitHub, GitLab and Gitea APIs,
+//! sending real requests with `curl` through `--base-url`.
+
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use macdevkit_cli::manifest::Manifest;
+use macdevkit_cli::runner::SystemRunner;
+use macdevkit_cli::script_handler::ScriptHandler;
+use macdevkit_cli::steps;
+use serde_json::{json, Value};
+
+const PUBLIC_KEY: &str = "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIGxqbW9ja2tleW1vY2trZXltb2Nra2V5bW9ja2tleQ ada@example.com";
+const TOKEN_ENV: &str = "MACDEVKIT_TEST_TOKEN";
+const TOKEN: &str = "test-token";
+
+#[derive(Debug, Clone)]
+struct Request {
+    method: String,
+    path: String,
+    headers: BTreeMap<String, String>,
+    body: Value,
+}
+
+/// A tiny HTTP server that keeps the keys posted to each list and answers
+/// `GET` with them.
+struct MockHost {
+    url: String,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl MockHost {
+    fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let lists = Arc::new(Mutex::new(BTreeMap::<String, Vec<Value>>::new()));
+
+        let recorded = requests.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                serve(stream, &recorded, &lists);
+            }
+        });
+        MockHost { url, requests }
+    }
+
+    /// Method and path of each request so far, e.g. `GET /user/keys?per_page=100`.
+    fn lines(&self) -> Vec<String> {
+        self.requests().iter().map(|request| format!("{} {}", request.method, request.path)).collect()
+    }
+
+    fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    fn clear(&self) {
+        self.requests.lock().unwrap().clear();
+    }
+}
+
+fn serve(stream: TcpStream, requests: &Mutex<Vec<Request>>, lists: &Mutex<BTreeMap<String, Vec<Value>>>) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).unwrap();
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+
+    let mut headers = BTreeMap::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+    let length: usize = headers.get("content-length").and_then(|length| length.parse().ok()).unwrap_or(0);
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).unwrap();
+    let body: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+
+    let list = path.split('?').next().unwrap_or_default().to_string();
+    let (status, response) = match method.as_str() {
+        "GET" => ("200 OK", Value::Array(lists.lock().unwrap().get(&list).cloned().unwrap_or_default())),
+        _ => {
+            let mut key = body.clone();
+            key["id"] = json!(1);
+            lists.lock().unwrap().entry(list).or_default().push(key.clone());
+            ("201 Created", key)
+        }
+    };
+    requests.lock().unwrap().push(Request {
+        method,
+        path,
+        headers,
+        body,
+    });
+
+    let response = response.to_string();
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        response.len(),
+        response
+    )
+    .unwrap();
+}
+
+/// A home directory holding the default public key, deleted when dropped.
+struct TempHome(PathBuf);
+
+impl TempHome {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("macdevkit-publish-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(path.join(".ssh")).unwrap();
+        std::fs::write(path.join(".ssh/id_ed25519.pub"), format!("{}\n", PUBLIC_KEY)).unwrap();
+        TempHome(path)
+    }
+}
+
+impl Drop for TempHome {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Runs `macdevkit-cli ssh publish` with `args` against `host`.
+fn publish(home: &TempHome, host: &MockHost, args: &[&str]) -> bool {
+    std::env::set_var(TOKEN_ENV, TOKEN);
+    let handler = ScriptHandler::new(Manifest::default(), Arc::new(SystemRunner)).with_home(home.0.to_string_lossy());
+    let step = steps::find("ssh").unwrap();
+    let command = step.subcommands().into_iter().find(|command| command.get_name() == "publish").unwrap();
+    let matches = command.try_get_matches_from(
+        ["publish", "--base-url", host.url.as_str(), "--token-env", TOKEN_ENV, "--title", "laptop"]
+            .into_iter()
+            .chain(args.iter().copied()),
+    );
+    handler.run_subcommand(step.as_ref(), "publish", &matches.unwrap()).unwrap()
+}
+
+#[test]
+fn github_adds_authentication_and_signing_keys_once() {
+    let home = TempHome::new("github");
+    let host = MockHost::start();
+
+    assert!(publish(&home, &host, &["--provider", "github", "--signing"]));
+    assert_eq!(
+        host.lines(),
+        [
+            "GET /user/keys?per_page=100",
+            "POST /user/keys",
+            "GET /user/ssh_signing_keys?per_page=100",
+            "POST /user/ssh_signing_keys",
+        ]
+    );
+    for request in host.requests() {
+        assert_eq!(request.headers["authorization"], format!("Bearer {}", TOKEN));
+        if request.method == "POST" {
+            assert_eq!(request.body, json!({ "title": "laptop", "key": PUBLIC_KEY }));
+        }
+    }
+
+    host.clear();
+    assert!(publish(&home, &host, &["--provider", "github", "--signing"]));
+    assert_eq!(host.lines(), ["GET /user/keys?per_page=100", "GET /user/ssh_signing_keys?per_page=100"]);
+}
+
+#[test]
+fn gitlab_adds_one_key_for_authentication_and_signing() {
+    let home = TempHome::new("gitlab");
+    let host = MockHost::start();
+
+    assert!(publish(&home, &host, &["--provider", "gitlab", "--signing"]));
+    assert_eq!(host.lines(), ["GET /user/keys?per_page=100", "POST /user/keys", "GET /user/keys?per_page=100"]);
+    let requests = host.requests();
+    assert_eq!(requests[0].headers["private-token"], TOKEN);
+    assert_eq!(
+        requests[1].body,
+        json!({ "title": "laptop", "key": PUBLIC_KEY, "usage_type": "auth_and_signing" })
+    );
+
+    host.clear();
+    assert!(publish(&home, &host, &["--provider", "gitlab", "--signing"]));
+    assert_eq!(host.lines(), ["GET /user/keys?per_page=100", "GET /user/keys?per_page=100"]);
+}
+
+#[test]
+fn gitea_adds_authentication_key_once() {
+    let home = TempHome::new("gitea");
+    let host = MockHost::start();
+
+    assert!(publish(&home, &host, &["--provider", "gitea", "--signing"]));
+    assert_eq!(host.lines(), ["GET /user/keys?limit=50", "POST /user/keys"]);
+    let requests = host.requests();
+    assert_eq!(requests[0].headers["authorization"], format!("token {}", TOKEN));
+    assert_eq!(requests[1].body, json!({ "title": "laptop", "key": PUBLIC_KEY }));
+
+    host.clear();
+    assert!(publish(&home, &host, &["--provider", "gitea"]));
+    assert_eq!(host.lines(), ["GET /user/keys?limit=50"]);
+}